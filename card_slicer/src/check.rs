@@ -0,0 +1,145 @@
+// Makes sure every card in a deck has an image to go with it, and that the deck lists cards that are really there
+
+use std::{collections::{BTreeMap, HashSet}, fs, path::Path};
+
+use serde::Deserialize;
+
+// Lists how many of each card are in the deck, it isn't a card itself
+const DECK_INFO_FILE: &str = "deck_info.json";
+
+// Only the parts of a card json needed to find its image
+#[derive(Deserialize)]
+struct CardFile {
+    id: String,
+    img_path: String,
+}
+
+pub struct DeckCard {
+    pub card_id: String,
+    pub img_path: String,
+    pub json_file: String,
+}
+
+/// Every card in the deck, sorted by file name
+pub fn deck_cards(deck_dir: &Path) -> Result<Vec<DeckCard>, String> {
+    let entries = fs::read_dir(deck_dir).map_err(|e| format!("Failed to read deck {}: {}", deck_dir.display(), e))?;
+
+    let mut cards = vec![];
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let json_file = entry.file_name().to_string_lossy().to_string();
+        if !json_file.ends_with(".json") || json_file == DECK_INFO_FILE {
+            continue;
+        }
+        let data = fs::read_to_string(entry.path()).map_err(|e| format!("Failed to read {}: {}", json_file, e))?;
+        let card: CardFile = serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", json_file, e))?;
+        cards.push(DeckCard { card_id: card.id, img_path: card.img_path, json_file });
+    }
+    cards.sort_by(|a, b| a.json_file.cmp(&b.json_file));
+    Ok(cards)
+}
+
+/// Everything wrong with the deck - missing images, files not named after their card,
+/// images nothing uses and deck_info entries with no card behind them
+pub fn check_deck(deck_dir: &Path) -> Result<Vec<String>, String> {
+    let cards = deck_cards(deck_dir)?;
+    let mut warnings = vec![];
+
+    for card in &cards {
+        // e.g. invisible_clothes.json holding the card invisible_cloths
+        let stem = card.json_file.trim_end_matches(".json");
+        if stem != card.card_id {
+            warnings.push(format!("{}: card id {} doesn't match the file name", card.json_file, card.card_id));
+        }
+
+        let expected = format!("{}.png", card.card_id);
+        if !card.img_path.ends_with(".png") {
+            warnings.push(format!("{}: img_path {} isn't a png", card.json_file, card.img_path));
+        } else if !deck_dir.join(&card.img_path).is_file() {
+            let hint = if deck_dir.join(&expected).is_file() { format!(" ({} does, it might be that)", expected) } else { String::new() };
+            warnings.push(format!("{}: img_path {} doesn't exist{}", card.json_file, card.img_path, hint));
+        } else if card.img_path != expected {
+            warnings.push(format!("{}: img_path {} doesn't match the card id, expected {}", card.json_file, card.img_path, expected));
+        }
+    }
+
+    let used: HashSet<&str> = cards.iter().map(|card| card.img_path.as_str()).collect();
+    let mut unused: Vec<String> = fs::read_dir(deck_dir)
+        .map_err(|e| format!("Failed to read deck {}: {}", deck_dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|file| file.ends_with(".png") && !used.contains(file.as_str()))
+        .collect();
+    unused.sort();
+    for file in unused {
+        warnings.push(format!("{}: no card uses this image", file));
+    }
+
+    // The game deals from deck_info, so an id there without a card can never be drawn properly
+    let info_path = deck_dir.join(DECK_INFO_FILE);
+    let data = fs::read_to_string(&info_path).map_err(|e| format!("Failed to read {}: {}", info_path.display(), e))?;
+    let deck_info: BTreeMap<String, usize> = serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", DECK_INFO_FILE, e))?;
+    let card_ids: HashSet<&str> = cards.iter().map(|card| card.card_id.as_str()).collect();
+    for card_id in deck_info.keys().filter(|card_id| !card_ids.contains(card_id.as_str())) {
+        warnings.push(format!("{}: {} isn't a card in this deck", DECK_INFO_FILE, card_id));
+    }
+
+    Ok(warnings)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A deck folder of its own for each test, written from (file name, contents)
+    fn test_deck(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let deck_dir = std::env::temp_dir().join(format!("card_slicer_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&deck_dir);
+        fs::create_dir_all(&deck_dir).unwrap();
+        for (file, contents) in files {
+            fs::write(deck_dir.join(file), contents).unwrap();
+        }
+        deck_dir
+    }
+
+    fn card(id: &str, img_path: &str) -> String {
+        format!(r#"{{ "id": "{}", "img_path": "{}" }}"#, id, img_path)
+    }
+
+    #[test]
+    fn a_good_deck_has_no_problems() {
+        let deck_dir = test_deck("good", &[("dog.json", &card("dog", "dog.png")), ("dog.png", ""), ("deck_info.json", r#"{ "dog": 2 }"#)]);
+        assert!(check_deck(&deck_dir).unwrap().is_empty());
+        fs::remove_dir_all(deck_dir).unwrap();
+    }
+
+    #[test]
+    fn card_ids_have_to_match_their_file_name() {
+        let deck_dir = test_deck("stem", &[
+            ("invisible_clothes.json", &card("invisible_cloths", "invisible_cloths.png")),
+            ("invisible_cloths.png", ""),
+            ("deck_info.json", r#"{ "invisible_cloths": 1 }"#),
+        ]);
+        let warnings = check_deck(&deck_dir).unwrap();
+        assert_eq!(warnings, vec!["invisible_clothes.json: card id invisible_cloths doesn't match the file name"]);
+        fs::remove_dir_all(deck_dir).unwrap();
+    }
+
+    #[test]
+    fn deck_info_ids_have_to_be_cards() {
+        let deck_dir = test_deck("info", &[("dog.json", &card("dog", "dog.png")), ("dog.png", ""), ("deck_info.json", r#"{ "dog": 2, "cat": 1 }"#)]);
+        let warnings = check_deck(&deck_dir).unwrap();
+        assert_eq!(warnings, vec!["deck_info.json: cat isn't a card in this deck"]);
+        fs::remove_dir_all(deck_dir).unwrap();
+    }
+
+    #[test]
+    fn missing_and_unused_images_are_found() {
+        let deck_dir = test_deck("images", &[("dog.json", &card("dog", "doggo.png")), ("dog.png", ""), ("deck_info.json", r#"{ "dog": 1 }"#)]);
+        let warnings = check_deck(&deck_dir).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("dog.json: img_path doggo.png doesn't exist"));
+        assert_eq!(warnings[1], "dog.png: no card uses this image");
+        fs::remove_dir_all(deck_dir).unwrap();
+    }
+}
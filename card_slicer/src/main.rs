@@ -0,0 +1,154 @@
+// Cuts the printable card sheets in res/card_sheets up into the single card images the decks use
+// Usage: card_slicer slice <manifest> <deck_dir> [--force]
+//        card_slicer grid <sheet> <out_dir> [--columns N] [--rows N]
+//        card_slicer check <deck_dir>
+
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use crate::{check::{check_deck, deck_cards}, sheet::{cut_cell, load_sheet, Manifest, DEFAULT_COLUMNS, DEFAULT_ROWS}};
+
+pub mod check;
+pub mod sheet;
+
+const USAGE: &str = "Usage: card_slicer slice <manifest> <deck_dir> [--force]
+       card_slicer grid <sheet> <out_dir> [--columns N] [--rows N]
+       card_slicer check <deck_dir>";
+
+enum Command {
+    // Cut out every card the manifest lists, named after the img_path in the deck
+    // Images already in the deck are left alone unless forced, they may have been touched up by hand
+    Slice { manifest: PathBuf, deck_dir: PathBuf, force: bool },
+    // Cut out every cell of one sheet, handy for working out what goes in the manifest
+    Grid { sheet: PathBuf, out_dir: PathBuf, columns: u32, rows: u32 },
+    Check { deck_dir: PathBuf },
+}
+
+fn parse_args() -> Result<Command, String> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().ok_or("No command given")?;
+
+    let mut paths = vec![];
+    let mut columns = DEFAULT_COLUMNS;
+    let mut rows = DEFAULT_ROWS;
+    let mut force = false;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("Missing value for {}", name));
+        match arg.as_str() {
+            "--columns" => columns = value("--columns")?.parse().map_err(|_| "--columns must be a number")?,
+            "--rows" => rows = value("--rows")?.parse().map_err(|_| "--rows must be a number")?,
+            "--force" if command == "slice" => force = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown argument: {}", arg)),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let expected = match command.as_str() {
+        "slice" | "grid" => 2,
+        "check" => 1,
+        _ => return Err(format!("Unknown command: {}", command)),
+    };
+    if paths.len() != expected {
+        return Err(format!("{} takes {} paths, got {}", command, expected, paths.len()));
+    }
+
+    let mut paths = paths.into_iter();
+    let mut next = || paths.next().unwrap_or_default();
+    Ok(match command.as_str() {
+        "slice" => Command::Slice { manifest: next(), deck_dir: next(), force },
+        "grid" => Command::Grid { sheet: next(), out_dir: next(), columns, rows },
+        _ => Command::Check { deck_dir: next() },
+    })
+}
+
+fn slice(manifest_path: &Path, deck_dir: &Path, force: bool) -> Result<(), String> {
+    let manifest = Manifest::load(manifest_path)?;
+    let sheet_dir = manifest_path.parent().unwrap_or(Path::new("."));
+
+    // Card id, img_path
+    let img_paths: HashMap<String, String> = deck_cards(deck_dir)?
+        .into_iter()
+        .map(|card| (card.card_id, card.img_path))
+        .collect();
+
+    let mut skipped = 0;
+    for entry in &manifest.sheets {
+        let sheet = load_sheet(&sheet_dir.join(&entry.file))?;
+        let columns = entry.columns.unwrap_or(manifest.columns);
+        let rows = entry.rows.unwrap_or(manifest.rows);
+
+        for (card_id, cell) in &entry.cards {
+            let expected = format!("{}.png", card_id);
+            // Go with whatever the card json says so the game finds it, unless that can't be a png
+            let img_path = match img_paths.get(card_id) {
+                Some(img_path) if img_path.ends_with(".png") => img_path.clone(),
+                Some(img_path) => {
+                    eprintln!("Warning: {} has img_path {} which isn't a png, saving it as {}", card_id, img_path, expected);
+                    expected
+                },
+                None => {
+                    eprintln!("Warning: {} isn't in {}, saving it as {}", card_id, deck_dir.display(), expected);
+                    expected
+                },
+            };
+
+            if !force && deck_dir.join(&img_path).exists() {
+                println!("{} cell {} -> {} already exists, skipping", entry.file, cell, img_path);
+                skipped += 1;
+                continue;
+            }
+
+            let card = cut_cell(&sheet, columns, rows, *cell).map_err(|e| format!("{} in {}: {}", card_id, entry.file, e))?;
+            card.save(deck_dir.join(&img_path)).map_err(|e| format!("Failed to save {}: {}", img_path, e))?;
+            println!("{} cell {} -> {}", entry.file, cell, img_path);
+        }
+    }
+    if skipped > 0 {
+        println!("Skipped {} images that were already there, use --force to overwrite them", skipped);
+    }
+    Ok(())
+}
+
+fn grid(sheet_path: &Path, out_dir: &Path, columns: u32, rows: u32) -> Result<(), String> {
+    let sheet = load_sheet(sheet_path)?;
+    let name = sheet_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    fs::create_dir_all(out_dir).map_err(|e| format!("Failed to create {}: {}", out_dir.display(), e))?;
+
+    for cell in 0..columns * rows {
+        let card = cut_cell(&sheet, columns, rows, cell)?;
+        let path = out_dir.join(format!("{}_{}.png", name, cell));
+        card.save(&path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    }
+    println!("Wrote {} cells to {}", columns * rows, out_dir.display());
+    Ok(())
+}
+
+fn check(deck_dir: &Path) -> Result<(), String> {
+    let warnings = check_deck(deck_dir)?;
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    println!("{} problems in {}", warnings.len(), deck_dir.display());
+    Ok(())
+}
+
+fn main() {
+    let command = match parse_args() {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    let result = match &command {
+        // Check afterwards so anything the manifest missed still shows up
+        Command::Slice { manifest, deck_dir, force } => slice(manifest, deck_dir, *force).and_then(|_| check(deck_dir)),
+        Command::Grid { sheet, out_dir, columns, rows } => grid(sheet, out_dir, *columns, *rows),
+        Command::Check { deck_dir } => check(deck_dir),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,73 @@
+// Reading card sheets and cutting them up into single cards
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use image::{imageops::{self, FilterType}, RgbaImage};
+use serde::Deserialize;
+
+// The printed sheets are 4 cards across and 4 down
+pub const DEFAULT_COLUMNS: u32 = 4;
+pub const DEFAULT_ROWS: u32 = 4;
+
+// Same size as the card images already in the decks
+pub const CARD_WIDTH: u32 = 350;
+pub const CARD_HEIGHT: u32 = 500;
+
+/// Says which card is in which cell of each sheet
+#[derive(Deserialize)]
+pub struct Manifest {
+    #[serde(default = "default_columns")]
+    pub columns: u32,
+    #[serde(default = "default_rows")]
+    pub rows: u32,
+    pub sheets: Vec<SheetEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct SheetEntry {
+    /// File name of the sheet, relative to the manifest
+    pub file: String,
+    /// Only needed if this sheet isn't laid out like the rest
+    pub columns: Option<u32>,
+    pub rows: Option<u32>,
+    /// Card id, cell it is in - counting from 0, left to right then top to bottom
+    /// Sheets often have the same card more than once, only one of them is needed
+    pub cards: BTreeMap<String, u32>,
+}
+
+fn default_columns() -> u32 {
+    DEFAULT_COLUMNS
+}
+
+fn default_rows() -> u32 {
+    DEFAULT_ROWS
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read manifest {}: {}", path.display(), e))?;
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse manifest {}: {}", path.display(), e))
+    }
+}
+
+pub fn load_sheet(path: &Path) -> Result<RgbaImage, String> {
+    image::open(path)
+        .map(|sheet| sheet.to_rgba8())
+        .map_err(|e| format!("Failed to load sheet {}: {}", path.display(), e))
+}
+
+/// Cut one cell out of a sheet, resized to match the rest of the deck
+pub fn cut_cell(sheet: &RgbaImage, columns: u32, rows: u32, cell: u32) -> Result<RgbaImage, String> {
+    if columns == 0 || rows == 0 {
+        return Err("A sheet needs at least one column and row".to_string());
+    }
+    if cell >= columns * rows {
+        return Err(format!("Cell {} is outside of the {}x{} grid", cell, columns, rows));
+    }
+
+    // Sheets don't always divide evenly, so work each cell's corner out from the full size
+    let x = (cell % columns) * sheet.width() / columns;
+    let y = (cell / columns) * sheet.height() / rows;
+    let card = imageops::crop_imm(sheet, x, y, sheet.width() / columns, sheet.height() / rows).to_image();
+    Ok(imageops::resize(&card, CARD_WIDTH, CARD_HEIGHT, FilterType::Lanczos3))
+}
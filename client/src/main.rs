@@ -5,14 +5,19 @@ use common::server::{messages::{ClientToMatchmakingServer, ClientToServer, Match
 use macroquad::prelude::*;
 use uuid::Uuid;
 mod net;
+mod settings;
+mod textures;
 mod ui;
 
 use net::WsMessage;
 
-use crate::{net::{platform, ConnectionResult, WebSocketClient}, ui::{label::Label, Alignment, Position, UIContext, UIElement, UIMessage}, views::{connection_error::ConnectionError, direct_connect::DirectConnect, in_room::InRoom, main_menu::MainMenu, room_browser::RoomBrowser, MenuState}};
+use crate::{net::{platform, ConnectionResult, WebSocketClient}, settings::ClientSettings, textures::TextureCache, ui::{container::Container, drag::DropTarget, label::Label, theme::StyleRole, Alignment, Position, Size, UIContext, UIElement, UIMessage}, views::{connection_error::ConnectionError, direct_connect::DirectConnect, in_game::InGame, in_room::InRoom, main_menu::MainMenu, room_browser::RoomBrowser, settings::Settings, MenuState}};
 
 mod views;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ui::layout_file::LayoutWatcher;
+
 
 pub struct AppState {
     pub menu_state: MenuState,
@@ -20,11 +25,27 @@ pub struct AppState {
     pub game_server_client: Option<Box<dyn WebSocketClient>>,
     pub rooms: HashMap<Uuid, RoomInfo>,
     pub error_message: Option<String>,
+    pub error_time: f64, // When the error message was set, errors from the room only show for a few seconds
+    pub retry: Option<UIMessage>, // What the retry button on the connection error screen does, if there is one
     pub player_id: Uuid,
     pub player_name: String,
-    pub other_players: HashMap<Uuid, String>
+    pub other_players: HashMap<Uuid, String>,
+    pub settings: ClientSettings,
+    pub game_server_address: Option<String>,
+    pub session_token: Option<Uuid>, // Lets us take our seat back if the connection drops
+    pub deck: String, // The deck the room is using, the card images come from it
+    pub textures: TextureCache,
+    pub reconnect_started: Option<f64>,
+    pub last_reconnect_attempt: f64,
 }
 
+// How long to keep trying to get back into a room after losing connection (the server keeps the seat for 60s)
+const RECONNECT_TIMEOUT: f64 = 55.0;
+const RECONNECT_INTERVAL: f64 = 2.0;
+
+// How long something the game server turned down stays on screen
+const ERROR_TOAST_TIME: f64 = 5.0;
+
 #[macroquad::main("Client")]
 async fn main() {
     let mut app_state = AppState {
@@ -32,22 +53,43 @@ async fn main() {
         matchmaking_client: None,
         game_server_client: None,
         error_message: None,
+        error_time: 0.0,
+        retry: None,
         rooms: HashMap::new(),
         player_id: Uuid::new_v4(),
         player_name: "NO NAME".to_string(),
         other_players: HashMap::new(),
+        settings: ClientSettings::load(),
+        game_server_address: None,
+        session_token: None,
+        deck: String::new(),
+        textures: TextureCache::new(),
+        reconnect_started: None,
+        last_reconnect_attempt: 0.0,
     };
 
     let mut ctx = UIContext::new();
+    if let Some(path) = &app_state.settings.font_path
+        && let Err(e) = ctx.theme.load_font(path).await {
+        println!("Failed to load font, using the default one: {}", e);
+    }
 
     let mut main_menu = MainMenu::new();
     let mut room_browser = RoomBrowser::new();
-    let mut direct_connect = DirectConnect::new();
+    let mut direct_connect = DirectConnect::new(&app_state.settings.recent_servers);
     let mut in_room = InRoom::new();
+    let mut in_game = InGame::new();
+    let mut settings_view = Settings::new(&app_state.settings);
+    let mut connection_error = ConnectionError::new();
+
+    // Layout files get picked up again when they are saved, so the UI can be tweaked without recompiling
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut layout_watcher = LayoutWatcher::new();
 
 
     loop {
-        clear_background(BLACK);
+        clear_background(ctx.theme.style(StyleRole::Background).background);
+        app_state.textures.update();
 
         match app_state.menu_state {
             MenuState::MainMenu => {
@@ -69,27 +111,74 @@ async fn main() {
                 direct_connect.container.borrow_mut().draw(&mut ctx, 0.0, 0.0, screen_width(), screen_height());
             },
             MenuState::InGame => {
-                // Placeholder for in-game logic
+                in_game.draw(&mut ctx);
             },
             MenuState::ConnectionError(ref error) => {
-                let connection_error = ConnectionError::new(error.clone());
+                connection_error.set_error(error, &app_state.retry);
+                connection_error.container.borrow_mut().draw(&mut ctx, 0.0, 0.0, screen_width(), screen_height());
+            },
+            // Same screen as an error without the retry, the back button gives up on joining
+            MenuState::Connecting(ref address) => {
+                connection_error.set_error(&format!("Joining {}...", address), &None);
                 connection_error.container.borrow_mut().draw(&mut ctx, 0.0, 0.0, screen_width(), screen_height());
             },
             MenuState::InRoom => {
                 in_room.container.borrow_mut().draw(&mut ctx, 0.0, 0.0, screen_width(), screen_height());
+            },
+            MenuState::Settings => {
+                settings_view.container.borrow_mut().draw(&mut ctx, 0.0, 0.0, screen_width(), screen_height());
+            }
+        }
+        if matches!(app_state.menu_state, MenuState::InRoom | MenuState::InGame)
+            && let Some(error) = &app_state.error_message
+            && get_time() - app_state.error_time < ERROR_TOAST_TIME {
+            draw_error_toast(&mut ctx, error);
+        }
+        ctx.draw_overlay();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        for path in layout_watcher.changed(&[&MainMenu::LAYOUT, &RoomBrowser::LAYOUT, &DirectConnect::LAYOUT, &InRoom::LAYOUT, &Settings::LAYOUT, &ConnectionError::LAYOUT]) {
+            // A half finished edit will often fail to load, the old layout stays until it does
+            let result = if path == MainMenu::LAYOUT.path {
+                MainMenu::load().map(|view| main_menu = view)
+            } else if path == RoomBrowser::LAYOUT.path {
+                RoomBrowser::load().map(|mut view| {
+                    view.update_rooms(&app_state.rooms.values().cloned().collect::<Vec<RoomInfo>>());
+                    room_browser = view;
+                })
+            } else if path == DirectConnect::LAYOUT.path {
+                DirectConnect::load(&app_state.settings.recent_servers).map(|view| direct_connect = view)
+            } else if path == InRoom::LAYOUT.path {
+                in_room.reload()
+            } else if path == Settings::LAYOUT.path {
+                Settings::load(&app_state.settings).map(|view| settings_view = view)
+            } else {
+                ConnectionError::load().map(|view| connection_error = view)
+            };
+            match result {
+                Ok(()) => {
+                    // The old widgets are gone, so whatever had focus has too
+                    ctx.focus.clear();
+                    println!("Reloaded {}", path);
+                },
+                Err(e) => println!("Couldn't reload {}: {}", path, e),
             }
         }
 
         for msg in ctx.message_queue.drain(..) {
             match msg {
                 UIMessage::SwitchView(new_state) => {
+                    // The tab order belongs to the old view
+                    ctx.focus.clear();
+                    app_state.retry = None;
                     app_state.menu_state = new_state;
                 },
                 UIMessage::DrawCard(_card_id) => {
                     // Implement draw logic later
                 },
                 UIMessage::TryConnectToMatchmaking => {
-                    match platform::connect("ws://localhost:9001").await {
+                    app_state.retry = None;
+                    match platform::connect(&app_state.settings.matchmaking_url, app_state.settings.heartbeat()).await {
                         ConnectionResult::Success(client) => {
                             client.send_text(&serde_json::to_string(&ClientToMatchmakingServer::GetRooms).unwrap());
                             app_state.matchmaking_client = Some(client);
@@ -97,10 +186,19 @@ async fn main() {
                         },
                         ConnectionResult::Failure(err) => {
                             app_state.error_message = Some(err.clone());
+                            app_state.retry = Some(UIMessage::TryConnectToMatchmaking);
                             app_state.menu_state = MenuState::ConnectionError(err);
                         }
                     }
                 },
+                UIMessage::DirectConnect => {
+                    let address = direct_connect.address_text_box.borrow().get_text();
+                    connect_to_room_server(&address, &mut app_state, &direct_connect).await;
+                },
+                UIMessage::ConnectToServer(address) => {
+                    direct_connect.address_text_box.borrow_mut().set_text(address.clone());
+                    connect_to_room_server(&address, &mut app_state, &direct_connect).await;
+                },
                 UIMessage::CreateRoom => {
                     if let Some(client) = &app_state.matchmaking_client {
 
@@ -125,6 +223,74 @@ async fn main() {
                         client.send_text(&serde_json::to_string(&ClientToMatchmakingServer::GetRoomInfo { room_id: room_id }).unwrap());
                     }
                 },
+                UIMessage::JoinByCode => {
+                    if let Some(client) = &app_state.matchmaking_client {
+                        let room_code = room_browser.room_code_text_box.borrow().get_text();
+                        client.send_text(&serde_json::to_string(&ClientToMatchmakingServer::JoinByCode { room_code }).unwrap());
+                    }
+                },
+                UIMessage::SetReady(ready) => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::SetReady { ready }).unwrap());
+                    }
+                },
+                UIMessage::SelectDeck(deck_name) => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::SelectDeck { deck_name }).unwrap());
+                    }
+                },
+                UIMessage::StartGame => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::StartGame).unwrap());
+                    }
+                },
+                UIMessage::PlayCard(card_instance_id) => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::PlayCard { card_instance_id, target: None }).unwrap());
+                    }
+                },
+                UIMessage::DropCard { card_instance_id, target } => {
+                    let target = match target {
+                        DropTarget::Character { instance_id, .. } => Some(instance_id),
+                        DropTarget::PlayArea => None,
+                    };
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::PlayCard { card_instance_id, target }).unwrap());
+                    }
+                },
+                UIMessage::MakeChoice { choice_id, selected } => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::MakeChoice { choice_id, selected }).unwrap());
+                    }
+                    in_game.close_choice(choice_id);
+                },
+                UIMessage::EndTurn => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::EndTurn).unwrap());
+                    }
+                },
+                UIMessage::KickPlayer(player_id) => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::KickPlayer { player_id }).unwrap());
+                    }
+                },
+                UIMessage::TransferHost(player_id) => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::TransferHost { player_id }).unwrap());
+                    }
+                },
+                UIMessage::SaveSettings => {
+                    app_state.settings.matchmaking_url = settings_view.matchmaking_url_text_box.borrow().get_text().trim().to_string();
+                    if let Err(e) = app_state.settings.save() {
+                        println!("Failed to save settings: {}", e);
+                    }
+                    app_state.menu_state = MenuState::MainMenu;
+                },
+                UIMessage::AddBot(difficulty) => {
+                    if let Some(client) = &app_state.game_server_client {
+                        client.send_text(&serde_json::to_string(&ClientToServer::AddBot { difficulty }).unwrap());
+                    }
+                },
             }
         }
 
@@ -139,9 +305,7 @@ async fn main() {
         }
 
         for text in mm_messages {
-            process_matchmaking_server_message(&text, &mut app_state, &room_browser, &in_room).await;
-            let rooms_vec: Vec<RoomInfo> = app_state.rooms.values().cloned().collect();
-            room_browser.update_rooms(&rooms_vec);
+            process_matchmaking_server_message(&text, &mut app_state, &mut room_browser).await;
         }
 
         let mut gs_messages: Vec<String> = Vec::new();
@@ -153,7 +317,23 @@ async fn main() {
         }
 
         for text in gs_messages {
-            process_game_server_message(&text, &mut app_state, &in_room).await;
+            process_game_server_message(&text, &mut app_state, &mut in_room, &mut in_game).await;
+        }
+
+        // Lost the game server while in a room, try and get our seat back
+        if let Some(client) = &app_state.game_server_client
+            && client.connection_failed() {
+            app_state.game_server_client = None;
+            if matches!(app_state.menu_state, MenuState::InRoom | MenuState::InGame) && app_state.session_token.is_some() {
+                println!("Lost connection to the game server, trying to reconnect");
+                app_state.reconnect_started = Some(get_time());
+            } else if let MenuState::Connecting(address) = &app_state.menu_state {
+                app_state.retry = Some(UIMessage::ConnectToServer(address.clone()));
+                app_state.menu_state = MenuState::ConnectionError("Lost connection before joining the room".to_string());
+            }
+        }
+        if let Some(started) = app_state.reconnect_started {
+            try_reconnect(&mut app_state, started).await;
         }
 
         next_frame().await;
@@ -161,31 +341,153 @@ async fn main() {
 }
 
 
-async fn process_game_server_message(msg: &str, app_state: &mut AppState, in_room: &InRoom) {
+// A short message across the top of the screen, drawn over whatever view is open
+fn draw_error_toast(ctx: &mut UIContext, error: &str) {
+    let mut toast = Container::new(
+        Position::Align(Alignment::Centre),
+        Position::Abs(50.0),
+        Size::Rel(0.6),
+        Size::Abs(40.0),
+        StyleRole::Dialog,
+        ui::Layout::RowCentre,
+        Size::Abs(0.0),
+    );
+    toast.add_child(Box::new(Label::new(
+        Position::Align(Alignment::Centre),
+        Position::Align(Alignment::Centre),
+        22,
+        error.to_string(),
+        StyleRole::TextAccent
+    )));
+    toast.draw(ctx, 0.0, 0.0, screen_width(), screen_height());
+}
+
+
+async fn try_reconnect(app_state: &mut AppState, started: f64) {
+    if get_time() - started > RECONNECT_TIMEOUT {
+        app_state.reconnect_started = None;
+        app_state.session_token = None;
+        app_state.menu_state = MenuState::ConnectionError("Lost connection to the game server".to_string());
+        return;
+    }
+    if get_time() - app_state.last_reconnect_attempt < RECONNECT_INTERVAL {
+        return;
+    }
+    app_state.last_reconnect_attempt = get_time();
+
+    let (Some(address), Some(session_token)) = (app_state.game_server_address.clone(), app_state.session_token) else {
+        app_state.reconnect_started = None;
+        return;
+    };
+    if let ConnectionResult::Success(client) = platform::connect(&address, app_state.settings.heartbeat()).await {
+        println!("Reconnected to the game server");
+        client.send_text(&serde_json::to_string(&ClientToServer::Reconnect { player_id: app_state.player_id, session_token }).unwrap());
+        app_state.game_server_client = Some(client);
+        app_state.reconnect_started = None;
+    }
+}
+
+
+/// Join a room server straight from its address, like joining from the room browser but without asking matchmaking where the room is
+async fn connect_to_room_server(address: &str, app_state: &mut AppState, direct_connect: &DirectConnect) {
+    let address = address.trim();
+    if address.is_empty() {
+        return;
+    }
+    // Let people leave off the ws:// when typing it in
+    let address = if address.starts_with("ws://") || address.starts_with("wss://") {
+        address.to_string()
+    } else {
+        format!("ws://{}", address)
+    };
+
+    app_state.retry = None;
+    println!("Connecting directly to: {}", address);
+    match platform::connect(&address, app_state.settings.heartbeat()).await {
+        ConnectionResult::Success(client) => {
+            println!("Connected to game server");
+            let player_name = {
+                let name = direct_connect.player_name_text_box.borrow().get_text();
+                if name.trim().is_empty() {
+                    "NO NAME".to_string()
+                } else {
+                    name
+                }
+            };
+            app_state.player_name = player_name.clone();
+
+            client.send_text(&serde_json::to_string(&ClientToServer::RegisterPlayer { player_name, player_id: app_state.player_id }).unwrap());
+            app_state.game_server_client = Some(client);
+            app_state.game_server_address = Some(address.clone());
+            // The room is only joined once the server gives us a seat, it can still turn us away
+            app_state.menu_state = MenuState::Connecting(address.clone());
+
+            app_state.settings.add_recent_server(&address);
+            if let Err(e) = app_state.settings.save() {
+                println!("Failed to save settings: {}", e);
+            }
+            direct_connect.set_recent_servers(&app_state.settings.recent_servers);
+        },
+        ConnectionResult::Failure(err) => {
+            println!("Cannot connect to the game server");
+            app_state.error_message = Some(err.clone());
+            app_state.retry = Some(UIMessage::ConnectToServer(address));
+            app_state.menu_state = MenuState::ConnectionError(err);
+        }
+    }
+}
+
+
+async fn process_game_server_message(msg: &str, app_state: &mut AppState, in_room: &mut InRoom, in_game: &mut InGame) {
     match serde_json::from_str::<ServerToClient>(msg) {
-        // Instead of doing it this way, just request the player list from the server
         Ok(ServerToClient::PlayerJoined { player_id, player_name }) => {
-            // First check if the player id is not the current one
+            // Keep track of everyone else in the room, the player list itself comes from the lobby updates
             if app_state.player_id != player_id {
-                // The id's are different so act on that info
-                // Check if the menu state is in the in_room state, and if it is then add the player to the current list
-                if app_state.menu_state == MenuState::InRoom {
-
-                    let player_label_to_add = Label::new(
-                        Position::Align(Alignment::Centre),
-                        Position::Align(Alignment::LeTop), 
-                        24, 
-                        player_name.clone(), 
-                        BLACK
-                    );
-
-                    in_room.player_container.borrow_mut().add_child(Box::new(player_label_to_add));
-                }
-
-                // Add the player to the other players hashmap
                 app_state.other_players.insert(player_id, player_name);
             }
-            // The player id is the current player id so do nothing
+        },
+        Ok(ServerToClient::PlayerRegistered { session_token, .. }) => {
+            app_state.session_token = Some(session_token);
+            // Whatever went wrong getting here shouldn't pop up once we're in
+            app_state.error_message = None;
+            if matches!(app_state.menu_state, MenuState::Connecting(_)) {
+                app_state.menu_state = MenuState::InRoom;
+            }
+        },
+        Ok(ServerToClient::PlayerDisconnect { player_id }) => {
+            app_state.other_players.remove(&player_id);
+        },
+        Ok(ServerToClient::LobbyUpdate(lobby)) => {
+            in_room.update_lobby(&lobby, app_state.player_id);
+            app_state.deck = lobby.deck;
+        },
+        Ok(ServerToClient::GameStarted) => {
+            app_state.menu_state = MenuState::InGame;
+        },
+        Ok(ServerToClient::GameView(view)) => {
+            in_game.update_game(&view, &app_state.deck, &mut app_state.textures);
+        },
+        Ok(ServerToClient::ChoiceRequest(request)) => {
+            in_game.show_choice(&request, &app_state.deck, &mut app_state.textures);
+        },
+        Ok(ServerToClient::JoinRejected(err)) => {
+            app_state.game_server_client = None;
+            app_state.session_token = None;
+            app_state.error_message = Some(err.to_string());
+            // The room might have a seat free by the time they try again
+            app_state.retry = app_state.game_server_address.clone().map(UIMessage::ConnectToServer);
+            app_state.menu_state = MenuState::ConnectionError(format!("Couldn't join the room: {}", err));
+        },
+        Ok(ServerToClient::Error(err)) => {
+            // Something we asked for was turned down (not our turn, can't start yet...), the room itself is fine
+            println!("Game server error: {}", err);
+            app_state.error_message = Some(err);
+            app_state.error_time = get_time();
+        },
+        Ok(ServerToClient::Kicked) => {
+            app_state.game_server_client = None;
+            app_state.session_token = None;
+            app_state.menu_state = MenuState::ConnectionError("You were kicked from the room".to_string());
         },
         _ => {},
     }
@@ -193,7 +495,7 @@ async fn process_game_server_message(msg: &str, app_state: &mut AppState, in_roo
 
 
 
-async fn process_matchmaking_server_message(msg: &str, app_state: &mut AppState, room_browser: &RoomBrowser, in_room: &InRoom) {
+async fn process_matchmaking_server_message(msg: &str, app_state: &mut AppState, room_browser: &mut RoomBrowser) {
     match serde_json::from_str::<MatchmakingServerToClient>(msg) {
         Ok(MatchmakingServerToClient::RoomDirectory(room_dir)) => {
             #[cfg(not(target_arch = "wasm32"))]
@@ -203,6 +505,21 @@ async fn process_matchmaking_server_message(msg: &str, app_state: &mut AppState,
             web_sys::console::log_1(&format!("Room dir received: {} rooms", room_dir.len()).into());
 
             app_state.rooms = room_dir; 
+            let rooms_vec: Vec<RoomInfo> = app_state.rooms.values().cloned().collect();
+            room_browser.update_rooms(&rooms_vec);
+        },
+        // Changes to the directory get pushed to us after that, so the list stays up to date
+        Ok(MatchmakingServerToClient::RoomAdded(room)) => {
+            room_browser.add_room(&room);
+            app_state.rooms.insert(room.id, room);
+        },
+        Ok(MatchmakingServerToClient::RoomUpdated(room)) => {
+            room_browser.update_room(&room);
+            app_state.rooms.insert(room.id, room);
+        },
+        Ok(MatchmakingServerToClient::RoomRemoved { room_id }) => {
+            room_browser.remove_room(&room_id);
+            app_state.rooms.remove(&room_id);
         },
         Ok(MatchmakingServerToClient::RoomCreated { room_id }) => {
             //Room has been created, now get the info and join it
@@ -214,7 +531,7 @@ async fn process_matchmaking_server_message(msg: &str, app_state: &mut AppState,
         Ok(MatchmakingServerToClient::RoomInfo {server_address }) => {
             //Room info recieved, now try join the room 
             println!("Room info recieved - trying to connect to: {}", server_address);
-            match platform::connect(&server_address).await {
+            match platform::connect(&server_address, app_state.settings.heartbeat()).await {
                 ConnectionResult::Success(client) => {
                     // Connected to the game server
                     println!("Connected to game server");
@@ -232,17 +549,8 @@ async fn process_matchmaking_server_message(msg: &str, app_state: &mut AppState,
 
                     client.send_text(&serde_json::to_string(&ClientToServer::RegisterPlayer { player_name: player_name.clone(), player_id: app_state.player_id.clone() }).unwrap());
                     app_state.game_server_client = Some(client);
-                    app_state.menu_state = MenuState::InRoom;
-                    // Add the player to the list
-                    let player_label_to_add = Label::new(
-                        Position::Align(Alignment::Centre),
-                        Position::Align(Alignment::LeTop), 
-                        24, 
-                        format!("{player_name} (self)"), 
-                        BLACK
-                    );
-
-                    in_room.player_container.borrow_mut().add_child(Box::new(player_label_to_add));
+                    app_state.game_server_address = Some(server_address.clone());
+                    app_state.menu_state = MenuState::Connecting(server_address);
                 },
                 ConnectionResult::Failure(err) => {
                     println!("Cannot connect to the game server");
@@ -252,6 +560,10 @@ async fn process_matchmaking_server_message(msg: &str, app_state: &mut AppState,
             }
             
         }
+        Ok(MatchmakingServerToClient::JoinError(err)) => {
+            app_state.error_message = Some(err.to_string());
+            app_state.menu_state = MenuState::ConnectionError(format!("Couldn't join the room: {}", err));
+        }
         Ok(MatchmakingServerToClient::Error(err)) => {
             app_state.error_message = Some(format!("Server error: {}", err));
             app_state.menu_state = MenuState::ConnectionError(format!("Server error: {}", err));
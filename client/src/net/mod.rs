@@ -6,6 +6,13 @@ pub type WebSocketClientBox = Box<dyn WebSocketClient + Send>;
 #[cfg(target_arch = "wasm32")]
 pub type WebSocketClientBox = Box<dyn WebSocketClient>;
 
+/// How often to ping the server and how long it can stay quiet before the connection counts as lost
+#[derive(Clone, Copy)]
+pub struct Heartbeat {
+    pub interval_secs: f64,
+    pub timeout_secs: f64,
+}
+
 pub enum ConnectionResult {
     Success(WebSocketClientBox),
     Failure(String),
@@ -21,7 +28,7 @@ pub trait WebSocketClient {
 pub mod platform {
     use super::*;
     use futures_util::{SinkExt, StreamExt};
-    use std::sync::mpsc::{self, Receiver};
+    use std::{sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, Receiver}, Arc, Mutex}, time::{Duration, Instant}};
     use tokio::{runtime::Runtime, sync::mpsc as tokio_mpsc};
     use tokio_tungstenite::connect_async;
     use tungstenite::Message;
@@ -29,6 +36,7 @@ pub mod platform {
     struct NativeClient {
         rx: Receiver<WsMessage>,
         tx: tokio_mpsc::UnboundedSender<WsMessage>,
+        failed: Arc<AtomicBool>,
     }
 
     impl WebSocketClient for NativeClient {
@@ -41,11 +49,11 @@ pub mod platform {
         }
 
         fn connection_failed(&self) -> bool {
-            false // not used in native
+            self.failed.load(Ordering::Relaxed)
         }
     }
 
-    pub async fn connect(url: &str) -> ConnectionResult {
+    pub async fn connect(url: &str, heartbeat: Heartbeat) -> ConnectionResult {
         let (msg_tx, msg_rx) = mpsc::channel();
         let (send_tx, mut send_rx) = tokio_mpsc::unbounded_channel();
         let url = url.to_string();
@@ -57,25 +65,57 @@ pub mod platform {
                 match connect_async(&url).await {
                     Ok((ws_stream, _)) => {
                         let (mut write, mut read) = ws_stream.split();
+                        let failed = Arc::new(AtomicBool::new(false));
+                        let last_seen = Arc::new(Mutex::new(Instant::now()));
 
                         result_tx.send(ConnectionResult::Success(Box::new(NativeClient {
                             rx: msg_rx,
                             tx: send_tx.clone(),
+                            failed: Arc::clone(&failed),
                         }))).unwrap();
 
+                        let reader_failed = Arc::clone(&failed);
+                        let reader_last_seen = Arc::clone(&last_seen);
                         tokio::spawn(async move {
                             while let Some(msg) = read.next().await {
-                                if let Ok(Message::Text(txt)) = msg {
-                                    let _ = msg_tx.send(WsMessage::Text(txt.to_string()));
+                                // Anything (pongs included) means the server is still there
+                                if let Ok(mut t) = reader_last_seen.lock() {
+                                    *t = Instant::now();
+                                }
+                                match msg {
+                                    Ok(Message::Text(txt)) => {
+                                        let _ = msg_tx.send(WsMessage::Text(txt.to_string()));
+                                    },
+                                    Ok(Message::Close(_)) | Err(_) => break,
+                                    _ => {},
                                 }
                             }
+                            // The server has gone away
+                            reader_failed.store(true, Ordering::Relaxed);
                         });
 
-                        while let Some(msg) = send_rx.recv().await {
-                            let _ = match msg {
-                                WsMessage::Text(txt) => write.send(Message::Text(txt.into())).await,
-                                WsMessage::Binary(data) => write.send(Message::Binary(data.into())).await,
-                            };
+                        let mut ping_timer = tokio::time::interval(Duration::from_secs_f64(heartbeat.interval_secs));
+                        loop {
+                            tokio::select! {
+                                msg = send_rx.recv() => {
+                                    let Some(msg) = msg else {
+                                        break;
+                                    };
+                                    let _ = match msg {
+                                        WsMessage::Text(txt) => write.send(Message::Text(txt.into())).await,
+                                        WsMessage::Binary(data) => write.send(Message::Binary(data.into())).await,
+                                    };
+                                },
+                                _ = ping_timer.tick() => {
+                                    let quiet_for = last_seen.lock().map(|t| t.elapsed()).unwrap_or_default();
+                                    if quiet_for.as_secs_f64() >= heartbeat.timeout_secs {
+                                        println!("Server stopped responding after {:?}", quiet_for);
+                                        failed.store(true, Ordering::Relaxed);
+                                        break;
+                                    }
+                                    let _ = write.send(Message::Ping(Vec::new().into())).await;
+                                },
+                            }
                         }
                     }
                     Err(e) => {
@@ -162,7 +202,9 @@ pub mod platform {
         .await
     }
 
-    pub async fn connect(url: &str) -> ConnectionResult {
+    // Browsers answer the servers pings themselves and don't let us send our own,
+    // so a dead connection shows up through onclose instead
+    pub async fn connect(url: &str, _heartbeat: Heartbeat) -> ConnectionResult {
         let socket = match WebSocket::new(url) {
             Ok(s) => s,
             Err(e) => return ConnectionResult::Failure(format!("WebSocket init error: {:?}", e)),
@@ -0,0 +1,123 @@
+// Client settings that stick around between runs (only saved to disk on native, the web build uses the defaults)
+
+use serde::{Deserialize, Serialize};
+
+use crate::net::Heartbeat;
+
+// Only the last few servers connected to directly are kept
+const MAX_RECENT_SERVERS: usize = 5;
+
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_PATH: &str = "client_settings.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClientSettings {
+    pub matchmaking_url: String,
+    pub ping_interval_secs: f64,
+    pub idle_timeout_secs: f64,
+    pub font_path: Option<String>, // A ttf font to use instead of the default one
+    pub recent_servers: Vec<String>, // Room servers connected to directly, newest first
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self {
+            matchmaking_url: "ws://localhost:9001".to_string(),
+            ping_interval_secs: 10.0,
+            idle_timeout_secs: 30.0,
+            font_path: None,
+            recent_servers: vec![],
+        }
+    }
+}
+
+impl ClientSettings {
+    pub fn heartbeat(&self) -> Heartbeat {
+        Heartbeat { interval_secs: self.ping_interval_secs, timeout_secs: self.idle_timeout_secs }
+    }
+
+    // The ping timer panics on an interval that isn't above 0, and a timeout shorter than the interval drops every connection
+    fn check_heartbeat(&mut self) {
+        let interval_ok = self.ping_interval_secs.is_finite() && self.ping_interval_secs > 0.0;
+        if interval_ok && self.idle_timeout_secs > self.ping_interval_secs {
+            return;
+        }
+        let default = Self::default();
+        println!("Ping interval {}s and idle timeout {}s don't work together, using {}s and {}s",
+            self.ping_interval_secs, self.idle_timeout_secs, default.ping_interval_secs, default.idle_timeout_secs);
+        self.ping_interval_secs = default.ping_interval_secs;
+        self.idle_timeout_secs = default.idle_timeout_secs;
+    }
+
+    /// Move the address to the top of the recent servers, dropping the oldest if there are too many
+    pub fn add_recent_server(&mut self, address: &str) {
+        self.recent_servers.retain(|server| server != address);
+        self.recent_servers.insert(0, address.to_string());
+        self.recent_servers.truncate(MAX_RECENT_SERVERS);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Self {
+        let mut settings: Self = std::fs::read_to_string(SETTINGS_PATH)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        settings.check_heartbeat();
+        settings
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(SETTINGS_PATH, json).map_err(|e| e.to_string())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_heartbeat(ping_interval_secs: f64, idle_timeout_secs: f64) -> ClientSettings {
+        let mut settings = ClientSettings { ping_interval_secs, idle_timeout_secs, ..ClientSettings::default() };
+        settings.check_heartbeat();
+        settings
+    }
+
+    #[test]
+    fn good_heartbeat_settings_are_kept() {
+        let settings = with_heartbeat(2.5, 7.0);
+        assert_eq!(settings.ping_interval_secs, 2.5);
+        assert_eq!(settings.idle_timeout_secs, 7.0);
+    }
+
+    #[test]
+    fn bad_heartbeat_settings_fall_back_to_the_defaults() {
+        let default = ClientSettings::default();
+        for (interval, timeout) in [(0.0, 30.0), (-1.0, 30.0), (f64::NAN, 30.0), (f64::INFINITY, 30.0), (10.0, 10.0), (10.0, f64::NAN)] {
+            let settings = with_heartbeat(interval, timeout);
+            assert_eq!(settings.ping_interval_secs, default.ping_interval_secs);
+            assert_eq!(settings.idle_timeout_secs, default.idle_timeout_secs);
+        }
+    }
+
+    #[test]
+    fn recent_servers_are_newest_first_without_repeats() {
+        let mut settings = ClientSettings::default();
+        for address in ["a", "b", "c", "d", "e", "f", "b"] {
+            settings.add_recent_server(address);
+        }
+        assert_eq!(settings.recent_servers, vec!["b", "f", "e", "d", "c"]);
+    }
+}
@@ -0,0 +1,156 @@
+// Card images, loaded in the background the first time a card is shown so a frame never has to wait on them
+// Native reads and decodes them on a worker thread, the web build fetches them over HTTP
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::{Arc, Mutex}};
+
+use macroquad::{time::get_time, texture::{Image, Texture2D}};
+
+/// A cards image, starts off as the placeholder and gets swapped for the real one once it has loaded
+pub type CardTexture = Rc<RefCell<Texture2D>>;
+
+// Uploading to the GPU is the only part done on the main thread, so only do a few each frame
+const MAX_UPLOADS_PER_FRAME: usize = 8;
+// How long a texture nothing is showing gets kept in case the card comes back
+const EVICT_AFTER: f64 = 60.0;
+const EVICT_CHECK_INTERVAL: f64 = 5.0;
+
+// Deck, card id - the same card id can have a different image in another deck
+type TextureKey = (String, String);
+
+// Which texture, the decoded image (or why it couldn't be loaded)
+type LoadedImages = Arc<Mutex<Vec<(TextureKey, Result<Image, String>)>>>;
+
+struct CachedTexture {
+    texture: CardTexture,
+    loading: bool,
+    failed: bool, // Kept around so a bad path is only tried (and logged) once
+    last_used: f64,
+}
+
+pub struct TextureCache {
+    textures: HashMap<TextureKey, CachedTexture>,
+    placeholder: Texture2D,
+    missing: Texture2D,
+    loaded: LoadedImages, // Finished loads waiting to be uploaded
+    last_evict_check: f64,
+    #[cfg(not(target_arch = "wasm32"))]
+    requests: std::sync::mpsc::Sender<(TextureKey, String)>, // Which texture, path - goes to the loader thread
+}
+
+impl TextureCache {
+    pub fn new() -> Self {
+        let loaded: LoadedImages = Arc::new(Mutex::new(Vec::new()));
+        Self {
+            textures: HashMap::new(),
+            placeholder: placeholder_texture(),
+            missing: missing_texture(),
+            #[cfg(not(target_arch = "wasm32"))]
+            requests: spawn_loader(Arc::clone(&loaded)),
+            loaded,
+            last_evict_check: 0.0,
+        }
+    }
+
+    /// The image for a card in a deck, which is the placeholder until it has loaded
+    pub fn get(&mut self, deck: &str, card_id: &str, path: &str) -> CardTexture {
+        let key = (deck.to_string(), card_id.to_string());
+        if let Some(cached) = self.textures.get_mut(&key) {
+            cached.last_used = get_time();
+            return Rc::clone(&cached.texture);
+        }
+
+        let texture = Rc::new(RefCell::new(self.placeholder.clone()));
+        self.textures.insert(key.clone(), CachedTexture { texture: Rc::clone(&texture), loading: true, failed: false, last_used: get_time() });
+        self.start_load(key, path);
+        texture
+    }
+
+    /// Call once a frame - swaps in any images that have finished loading and drops ones nothing has shown for a while
+    pub fn update(&mut self) {
+        let finished: Vec<(TextureKey, Result<Image, String>)> = match self.loaded.lock() {
+            Ok(mut loaded) => {
+                let count = loaded.len().min(MAX_UPLOADS_PER_FRAME);
+                loaded.drain(..count).collect()
+            },
+            Err(_) => vec![],
+        };
+        for (key, image) in finished {
+            // It may have been evicted while it was loading
+            let Some(cached) = self.textures.get_mut(&key) else {
+                continue;
+            };
+            cached.loading = false;
+            match image {
+                Ok(image) => *cached.texture.borrow_mut() = Texture2D::from_image(&image),
+                Err(e) => {
+                    println!("Couldn't load the image for {} from {}: {}", key.1, key.0, e);
+                    cached.failed = true;
+                    *cached.texture.borrow_mut() = self.missing.clone();
+                },
+            }
+        }
+
+        let now = get_time();
+        if now - self.last_evict_check < EVICT_CHECK_INTERVAL {
+            return;
+        }
+        self.last_evict_check = now;
+
+        // The cache holds one reference itself, any more means a card on screen is still using it
+        self.textures.retain(|_, cached| {
+            if Rc::strong_count(&cached.texture) > 1 {
+                cached.last_used = now;
+            }
+            cached.loading || cached.failed || now - cached.last_used < EVICT_AFTER
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_load(&self, key: TextureKey, path: &str) {
+        let _ = self.requests.send((key, path.to_string()));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn start_load(&self, key: TextureKey, path: &str) {
+        let loaded = Arc::clone(&self.loaded);
+        let path = path.to_string();
+
+        // load_file fetches over HTTP on the web, running it as a coroutine keeps it from holding up the main loop
+        macroquad::experimental::coroutines::start_coroutine(async move {
+            let image = macroquad::file::load_file(&path).await
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| Image::from_file_with_format(&bytes, None).map_err(|e| e.to_string()));
+            if let Ok(mut loaded) = loaded.lock() {
+                loaded.push((key, image));
+            }
+        });
+    }
+}
+
+
+// One thread does all the file reading and decoding so the main thread only has to upload the result
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_loader(loaded: LoadedImages) -> std::sync::mpsc::Sender<(TextureKey, String)> {
+    let (tx, rx) = std::sync::mpsc::channel::<(TextureKey, String)>();
+    std::thread::spawn(move || {
+        for (key, path) in rx {
+            let image = std::fs::read(&path)
+                .map_err(|e| format!("{}: {}", path, e))
+                .and_then(|bytes| Image::from_file_with_format(&bytes, None).map_err(|e| format!("{}: {}", path, e)));
+            if let Ok(mut loaded) = loaded.lock() {
+                loaded.push((key, image));
+            }
+        }
+    });
+    tx
+}
+
+// Plain grey card shown while the real one loads
+fn placeholder_texture() -> Texture2D {
+    Texture2D::from_rgba8(1, 1, &[200, 200, 200, 255])
+}
+
+// Shown instead when the image couldn't be loaded, so it doesn't look like it's still on its way
+fn missing_texture() -> Texture2D {
+    Texture2D::from_rgba8(1, 1, &[200, 60, 60, 255])
+}
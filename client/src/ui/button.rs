@@ -1,8 +1,8 @@
-use macroquad::{color::Color, input::{is_mouse_button_pressed, mouse_position, MouseButton}, shapes::draw_rectangle};
+use macroquad::input::{is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, KeyCode, MouseButton};
 
 use crate::ui::Padding;
 
-use super::{label::Label, Alignment, Position, Size, UIContext, UIElement, UIMessage};
+use super::{focus::FocusId, label::Label, theme::StyleRole, Alignment, Position, Size, UIContext, UIElement, UIMessage};
 // Button
 pub struct Button {
     label: Label,
@@ -10,18 +10,20 @@ pub struct Button {
     y: Position,
     w: Size,
     h: Size, 
-    bg_color: Color,
+    style: StyleRole,
     on_click: Option<UIMessage>,
+    focus_id: FocusId,
 }
 
 impl Button {
-    pub fn new(x: Position, y: Position, w: Size, h: Size, bg_color: Color, txt_color: Color, txt_content:String, txt_size: u16, on_click: Option<UIMessage>) -> Self {
+    pub fn new(x: Position, y: Position, w: Size, h: Size, style: StyleRole, txt_content:String, txt_size: u16, on_click: Option<UIMessage>) -> Self {
+        // The label takes its text color from the buttons style
         let label = Label::new(
             Position::Align(Alignment::Centre), 
             Position::Align(Alignment::Centre), 
             txt_size, 
             txt_content, 
-            txt_color,
+            StyleRole::Text,
         );
         Self { 
             label: label, 
@@ -29,8 +31,9 @@ impl Button {
             y: y, 
             w: w, 
             h: h, 
-            bg_color: bg_color, 
+            style, 
             on_click: on_click,
+            focus_id: FocusId::next(),
         }
     }
 
@@ -44,18 +47,26 @@ impl UIElement for Button {
         let x = self.x.coord(parent_x, parent_w, w);
         let y = self.y.coord(parent_y, parent_h, h);
 
-        // Check if clicked
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let(mouse_x, mouse_y) = mouse_position();
-            if mouse_x >= x && mouse_x <= (x + w) && mouse_y >= y && mouse_y <= (y+h) {
-                if let Some(ref msg) = self.on_click {
-                    ctx.message_queue.push(msg.clone());
-                }
-            }
+        ctx.focus.register(self.focus_id);
+
+        // Check if clicked, or Enter/Space was pressed while it has focus
+        let hovered = ctx.mouse_over(x, y, w, h);
+        let clicked = is_mouse_button_pressed(MouseButton::Left) && hovered;
+        if clicked {
+            ctx.focus.focus(self.focus_id);
+        }
+        let is_focused = ctx.focus.is_focused(self.focus_id);
+        let activated = is_focused && (is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) || is_key_pressed(KeyCode::Space));
+        if (clicked || activated) && let Some(ref msg) = self.on_click {
+            ctx.message_queue.push(msg.clone());
         }
 
         // Draw background
-        draw_rectangle(x, y, w, h, self.bg_color);
+        let style = ctx.theme.style(self.style);
+        ctx.theme.draw_box(x, y, w, h, style, style.background_for(hovered, is_mouse_button_down(MouseButton::Left)));
+        if is_focused {
+            ctx.theme.draw_focus(x, y, w, h, style.corner_radius);
+        }
 
         // Draw label in button
         self.label.draw(ctx, x, y, w, h);
@@ -1,27 +1,51 @@
-use macroquad::{color::WHITE, input::{is_mouse_button_pressed, mouse_position, MouseButton}, math::vec2, texture::{draw_texture_ex, DrawTextureParams, Texture2D}};
+use std::rc::Rc;
 
-use super::{Position, Size, UIContext, UIElement, UIMessage};
+use macroquad::{color::{Color, WHITE}, input::{is_mouse_button_pressed, MouseButton}, math::vec2, texture::{draw_texture_ex, DrawTextureParams}};
+
+use crate::textures::CardTexture;
+
+use super::{drag::{DragState, HoveredCard}, Position, Size, UIContext, UIElement, UIMessage};
 
 pub struct CardUi {
-    img: Texture2D,
+    img: CardTexture, // Shared with the texture cache, so it changes once the image has loaded
     x: Position,
     y: Position,
     w: Size,
     h: Size, 
-    on_click: Box<dyn Fn()>,
+    on_click: Option<UIMessage>,
+    drag_id: Option<uuid::Uuid>, // Instance id to drag the card as, cards without one can't be dragged
+    description: Option<(String, String)>, // Name, description - shown enlarged when hovered
 }
 
 impl CardUi {
-    pub fn new<F: Fn() + 'static>(x: Position, y: Position, img: Texture2D, on_click: F) -> Self { 
+    pub fn new(x: Position, y: Position, img: CardTexture, on_click: Option<UIMessage>) -> Self { 
         Self { 
             img: img, 
             x: x, 
             y: y, 
             w: Size::Abs(160.0), 
             h: Size::Abs(224.0), 
-            on_click: Box::new(on_click), 
+            on_click, 
+            drag_id: None,
+            description: None,
         }
     }
+
+    /// Let the card be dragged onto drop zones, a press that doesn't move still counts as a click
+    pub fn set_draggable(&mut self, card_instance_id: uuid::Uuid) {
+        self.drag_id = Some(card_instance_id);
+    }
+
+    /// Show the card bigger with its name and description while the mouse is over it
+    pub fn set_description(&mut self, name: String, description: String) {
+        self.description = Some((name, description));
+    }
+
+    /// Cards default to 160x224, smaller ones are used for cards on the table
+    pub fn set_size(&mut self, w: Size, h: Size) {
+        self.w = w;
+        self.h = h;
+    }
 }
 
 //Implement the trait for the Card object
@@ -32,16 +56,30 @@ impl UIElement for CardUi {
         let x = self.x.coord(parent_x, parent_w, w);
         let y = self.y.coord(parent_y, parent_h, h);
 
-        // Check if clicked
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let(mouse_x, mouse_y) = mouse_position();
-            if mouse_x >= x && mouse_x <= (x + w) && mouse_y >= y && mouse_y <= (y+h) {
-                (self.on_click)();
+        let hovered = ctx.mouse_over(x, y, w, h);
+
+        // Check if clicked, draggable cards send their click once they are let go instead
+        if is_mouse_button_pressed(MouseButton::Left) && hovered && ctx.drag.is_none() {
+            match self.drag_id {
+                Some(card_instance_id) => {
+                    ctx.drag = Some(DragState::new(card_instance_id, Rc::clone(&self.img), x, y, w, h, self.on_click.clone()));
+                },
+                None => if let Some(ref msg) = self.on_click {
+                    ctx.message_queue.push(msg.clone());
+                },
             }
         }
 
+        if hovered && ctx.drag.is_none() && let Some((name, description)) = &self.description {
+            ctx.hovered_card = Some(HoveredCard { img: Rc::clone(&self.img), name: name.clone(), description: description.clone() });
+        }
+
+        // Faded out where it was while it is being dragged
+        let being_dragged = self.drag_id.is_some() && ctx.drag.as_ref().is_some_and(|drag| drag.moved && Some(drag.card_instance_id) == self.drag_id);
+        let tint = if being_dragged { Color::new(1.0, 1.0, 1.0, 0.3) } else { WHITE };
+
         // Draw background
-        draw_texture_ex(&self.img, x, y, WHITE, 
+        draw_texture_ex(&self.img.borrow(), x, y, tint, 
                     DrawTextureParams {
                         dest_size: Some(vec2(w, h)),
                         ..Default::default()
@@ -1,7 +1,7 @@
 use std::{cell::RefCell, rc::Rc};
 
 use macroquad::prelude::*;
-use super::{UIContext, UIElement, Position, Size, Alignment, UIMessage, Padding};
+use super::{focus::FocusId, theme::StyleRole, UIContext, UIElement, Position, Size, Alignment, UIMessage, Padding};
 
 pub struct Checkbox {
     x: Position,
@@ -9,8 +9,7 @@ pub struct Checkbox {
     w: Size,
     h: Size,
     checked: bool,
-    box_color: Color,
-    check_color: Color,
+    focus_id: FocusId,
 }
 
 impl Checkbox {
@@ -19,8 +18,6 @@ impl Checkbox {
         y: Position,
         w: Size,
         h: Size,
-        box_color: Color,
-        check_color: Color,
     ) -> Self {
         Self {
             x,
@@ -28,8 +25,7 @@ impl Checkbox {
             w,
             h,
             checked: false,
-            box_color,
-            check_color,
+            focus_id: FocusId::next(),
         }
     }
     pub fn is_checked(&self) -> bool {
@@ -49,24 +45,30 @@ impl UIElement for Checkbox {
         let y = self.y.coord(parent_y, parent_h, h);
         let box_size = h.min(w);
 
-        // Handle click
-        let (mx, my) = mouse_position();
-        let clicked = is_mouse_button_pressed(MouseButton::Left)
-            && mx >= x && mx <= x + box_size
-            && my >= y && my <= y + box_size;
+        ctx.focus.register(self.focus_id);
 
+        // Handle click, or Space while it has focus
+        let clicked = is_mouse_button_pressed(MouseButton::Left) && ctx.mouse_over(x, y, box_size, box_size);
         if clicked {
+            ctx.focus.focus(self.focus_id);
+        }
+        let is_focused = ctx.focus.is_focused(self.focus_id);
+        if clicked || (is_focused && is_key_pressed(KeyCode::Space)) {
             self.checked = !self.checked;
         }
 
         // Draw checkbox square
-        draw_rectangle(x, y, box_size, box_size, self.box_color);
+        let style = ctx.theme.style(StyleRole::Checkbox);
+        ctx.theme.draw_box(x, y, box_size, box_size, style, style.background);
+        if is_focused {
+            ctx.theme.draw_focus(x, y, box_size, box_size, style.corner_radius);
+        }
 
         // Draw checkmark
         if self.checked {
             let inset = box_size * 0.2;
-            draw_line(x + inset, y + inset, x + box_size - inset, y + box_size - inset, 2.0, self.check_color);
-            draw_line(x + inset, y + box_size - inset, x + box_size - inset, y + inset, 2.0, self.check_color);
+            draw_line(x + inset, y + inset, x + box_size - inset, y + box_size - inset, 2.0, style.text);
+            draw_line(x + inset, y + box_size - inset, x + box_size - inset, y + inset, 2.0, style.text);
         }
 
     }
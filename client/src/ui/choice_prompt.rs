@@ -0,0 +1,195 @@
+use macroquad::{color::{Color, WHITE}, input::{is_mouse_button_pressed, mouse_position, MouseButton}, math::vec2, shapes::{draw_rectangle, draw_rectangle_lines}, texture::{draw_texture_ex, DrawTextureParams}, time::get_time};
+use uuid::Uuid;
+
+use crate::textures::CardTexture;
+
+use super::{theme::StyleRole, UIContext, UIElement, UIMessage};
+
+const CARD_W: f32 = 120.0;
+const CARD_H: f32 = 168.0;
+const BUTTON_W: f32 = 180.0;
+const BUTTON_H: f32 = 50.0;
+const GAP: f32 = 12.0;
+
+/// Something that can be picked in a choice prompt
+pub struct ChoiceItem {
+    pub id: Uuid,
+    pub label: String,
+    pub img: Option<CardTexture>, // Cards show their image, players and other options are just buttons
+}
+
+/// Modal prompt that covers whatever is under it until the player has picked between min and max of the items
+/// Sends UIMessage::MakeChoice once confirmed, or on its own when the countdown runs out
+pub struct ChoicePrompt {
+    choice_id: Uuid,
+    title: String,
+    items: Vec<ChoiceItem>,
+    min: usize,
+    max: usize,
+    deadline: Option<f64>,
+    selected: Vec<Uuid>,
+    sent: bool,
+}
+
+impl ChoicePrompt {
+    pub fn new(choice_id: Uuid, title: String, items: Vec<ChoiceItem>, min: usize, max: usize, timeout_secs: Option<f32>) -> Self {
+        Self {
+            choice_id,
+            title,
+            items,
+            min,
+            max,
+            deadline: timeout_secs.map(|secs| get_time() + secs as f64),
+            selected: vec![],
+            sent: false,
+        }
+    }
+
+    pub fn choice_id(&self) -> Uuid {
+        self.choice_id
+    }
+
+    fn is_valid(&self) -> bool {
+        self.selected.len() >= self.min && self.selected.len() <= self.max
+    }
+
+    fn toggle(&mut self, id: Uuid) {
+        if let Some(index) = self.selected.iter().position(|selected| *selected == id) {
+            self.selected.remove(index);
+        } else if self.max == 1 {
+            // With only one pick, clicking something else swaps to it
+            self.selected = vec![id];
+        } else if self.selected.len() < self.max {
+            self.selected.push(id);
+        }
+    }
+
+    fn send(&mut self, ctx: &mut UIContext) {
+        self.sent = true;
+        ctx.message_queue.push(UIMessage::MakeChoice { choice_id: self.choice_id, selected: self.selected.clone() });
+    }
+
+    // Out of time, so top up what was picked with the first options until there are enough
+    fn send_default(&mut self, ctx: &mut UIContext) {
+        for item in &self.items {
+            if self.selected.len() >= self.min {
+                break;
+            }
+            if !self.selected.contains(&item.id) {
+                self.selected.push(item.id);
+            }
+        }
+        self.selected.truncate(self.max);
+        self.send(ctx);
+    }
+
+    fn subtitle(&self) -> String {
+        if self.min == self.max {
+            format!("Pick {}", self.min)
+        } else {
+            format!("Pick {} to {}", self.min, self.max)
+        }
+    }
+}
+
+impl UIElement for ChoicePrompt {
+    fn draw(&mut self, ctx: &mut UIContext, parent_x: f32, parent_y: f32, parent_w: f32, parent_h: f32) {
+        // Dim everything behind it
+        draw_rectangle(parent_x, parent_y, parent_w, parent_h, Color::new(0.0, 0.0, 0.0, 0.6));
+
+        let w = (parent_w * 0.7).max(CARD_W * 2.0 + GAP * 3.0);
+        let h = parent_h * 0.6;
+        let x = parent_x + (parent_w - w) / 2.0;
+        let y = parent_y + (parent_h - h) / 2.0;
+        let theme = &ctx.theme;
+        let dialog = theme.style(StyleRole::Dialog);
+        let accent = theme.style(StyleRole::TextAccent).text;
+        theme.draw_box(x, y, w, h, dialog, dialog.background);
+
+        theme.draw_text(&self.title, x + GAP, y + 36.0, 32, dialog.text);
+        theme.draw_text(&self.subtitle(), x + GAP, y + 60.0, 20, dialog.text);
+
+        let mut timed_out = false;
+        if let Some(deadline) = self.deadline {
+            let remaining = (deadline - get_time()).max(0.0);
+            let countdown = format!("{:.0}s", remaining.ceil());
+            let countdown_w = theme.measure_text(&countdown, 32).width;
+            theme.draw_text(&countdown, x + w - GAP - countdown_w, y + 36.0, 32, if remaining < 5.0 { accent } else { dialog.text });
+            timed_out = remaining <= 0.0 && !self.sent;
+        }
+
+        let (mouse_x, mouse_y) = mouse_position();
+        let clicked = is_mouse_button_pressed(MouseButton::Left) && !self.sent;
+
+        // Lay the options out left to right, wrapping onto new rows
+        let mut item_x = x + GAP;
+        let mut item_y = y + 80.0;
+        let mut row_h: f32 = 0.0;
+        let mut toggled = None;
+        for item in &self.items {
+            let (item_w, item_h) = if item.img.is_some() { (CARD_W, CARD_H + 20.0) } else { (BUTTON_W, BUTTON_H) };
+            if item_x + item_w > x + w - GAP && item_x > x + GAP {
+                item_x = x + GAP;
+                item_y += row_h + GAP;
+                row_h = 0.0;
+            }
+
+            let is_selected = self.selected.contains(&item.id);
+            match &item.img {
+                Some(img) => {
+                    draw_texture_ex(&img.borrow(), item_x, item_y, WHITE,
+                        DrawTextureParams {
+                            dest_size: Some(vec2(CARD_W, CARD_H)),
+                            ..Default::default()
+                        }
+                    );
+                    theme.draw_text(&item.label, item_x, item_y + CARD_H + 16.0, 16, dialog.text);
+                },
+                None => {
+                    let button = theme.style(StyleRole::SecondaryButton);
+                    let hovered = mouse_x >= item_x && mouse_x <= item_x + item_w && mouse_y >= item_y && mouse_y <= item_y + item_h;
+                    theme.draw_box(item_x, item_y, item_w, item_h, button, button.background_for(hovered || is_selected, is_selected));
+                    let text_w = theme.measure_text(&item.label, 20).width;
+                    theme.draw_text(&item.label, item_x + (item_w - text_w) / 2.0, item_y + item_h / 2.0 + 6.0, 20, button.text);
+                },
+            }
+            if is_selected {
+                draw_rectangle_lines(item_x - 3.0, item_y - 3.0, item_w + 6.0, item_h + 6.0, 4.0, accent);
+            }
+
+            if clicked && mouse_x >= item_x && mouse_x <= item_x + item_w && mouse_y >= item_y && mouse_y <= item_y + item_h {
+                toggled = Some(item.id);
+            }
+
+            item_x += item_w + GAP;
+            row_h = row_h.max(item_h);
+        }
+        if let Some(id) = toggled {
+            self.toggle(id);
+        }
+
+        // Confirm only does anything once enough has been picked
+        let button_x = x + w - GAP - BUTTON_W;
+        let button_y = y + h - GAP - BUTTON_H;
+        let can_confirm = self.is_valid() && !self.sent;
+        let button = theme.style(if can_confirm { StyleRole::PrimaryButton } else { StyleRole::SecondaryButton });
+        let hovered = can_confirm && mouse_x >= button_x && mouse_x <= button_x + BUTTON_W && mouse_y >= button_y && mouse_y <= button_y + BUTTON_H;
+        theme.draw_box(button_x, button_y, BUTTON_W, BUTTON_H, button, button.background_for(hovered, false));
+        let text_w = theme.measure_text("Confirm", 24).width;
+        theme.draw_text("Confirm", button_x + (BUTTON_W - text_w) / 2.0, button_y + BUTTON_H / 2.0 + 8.0, 24, button.text);
+
+        if clicked && can_confirm && mouse_x >= button_x && mouse_x <= button_x + BUTTON_W && mouse_y >= button_y && mouse_y <= button_y + BUTTON_H {
+            self.send(ctx);
+        } else if timed_out {
+            self.send_default(ctx);
+        }
+    }
+
+    // Always covers all of its parent
+    fn get_width(&self, parent_w: f32) -> f32 {
+        parent_w
+    }
+    fn get_height(&self, parent_h: f32) -> f32 {
+        parent_h
+    }
+}
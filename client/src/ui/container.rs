@@ -1,9 +1,9 @@
 use std::{cell::RefCell, rc::Rc};
 
-use macroquad::{color::{Color, RED}, shapes::{draw_rectangle, draw_rectangle_lines}};
+use macroquad::{color::Color, shapes::draw_rectangle_lines};
 
 
-use super::{Layout, Padding, Position, Size, UIContext, UIElement, UIMessage};
+use super::{drag::DropTarget, theme::StyleRole, Layout, Padding, Position, Size, UIContext, UIElement, UIMessage};
 
 
 //Basic container object
@@ -13,29 +13,36 @@ pub struct Container {
     y: Position,
     w: Size,
     h: Size,
-    color: Color,
+    style: StyleRole,
     padding: Padding,
     layout: Layout,
     gap: Size,
+    drop_target: Option<DropTarget>, // Makes the container a drop zone for dragged cards
 }
 
 
 // Basic container object logic
 impl Container {
-    pub fn new(x: Position, y: Position, w: Size, h: Size, color: Color, layout: Layout, gap: Size) -> Self{
+    pub fn new(x: Position, y: Position, w: Size, h: Size, style: StyleRole, layout: Layout, gap: Size) -> Self{
         Self { 
             children: vec![], 
             x: x,
             y: y,
             w: w,
             h: h,
-            color: color,
+            style,
             padding: Padding::zero(),
             layout: layout,
             gap: gap,
+            drop_target: None,
         }
     }
 
+    /// Let cards be dropped on the container, dropping one sends UIMessage::DropCard with this target
+    pub fn set_drop_target(&mut self, target: DropTarget) {
+        self.drop_target = Some(target);
+    }
+
     /// When calling, wrap the object in a box Box::new()
     pub fn add_child(&mut self, child: Box<dyn UIElement>) {
         self.children.push(child);
@@ -67,8 +74,14 @@ impl UIElement for Container {
         let content_w = w - self.padding.left - self.padding.right;
         let content_h = h - self.padding.left - self.padding.right;
 
-        draw_rectangle(x, y, w, h, self.color);
-        // draw_rectangle_lines(x, y, w, h, 2.0, RED);
+        let style = ctx.theme.style(self.style);
+        ctx.theme.draw_box(x, y, w, h, style, style.background);
+
+        // Claim the drop before drawing the children so a drop zone inside this one takes over from it
+        let dragging = ctx.drag.as_ref().is_some_and(|drag| drag.moved);
+        if dragging && let Some(target) = &self.drop_target && ctx.mouse_over(x, y, w, h) {
+            ctx.drop_target = Some(target.clone());
+        }
 
         match self.layout {
             Layout::None => {
@@ -177,10 +190,14 @@ impl UIElement for Container {
             },
         }
 
-
-        
-
-        
+        // Outline every drop zone while a card is being dragged, and highlight the one it would land in
+        if dragging && let Some(target) = &self.drop_target {
+            if ctx.drop_target.as_ref() == Some(target) {
+                draw_rectangle_lines(x, y, w, h, 4.0, ctx.theme.style(StyleRole::TextAccent).text);
+            } else {
+                draw_rectangle_lines(x, y, w, h, 1.0, Color::new(1.0, 1.0, 1.0, 0.4));
+            }
+        }
     }
     fn get_width(&self, parent_w: f32) -> f32 {
         self.w.caclulate(parent_w)
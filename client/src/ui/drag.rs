@@ -0,0 +1,136 @@
+// Dragging cards onto drop zones, and the enlarged preview shown when hovering a card
+// Cards start a drag when pressed, containers with a drop target pick it up, and UIContext::draw_overlay finishes it off
+
+use macroquad::{color::{Color, WHITE}, input::mouse_position, math::vec2, texture::{draw_texture_ex, DrawTextureParams}, window::{screen_height, screen_width}};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::textures::CardTexture;
+
+use super::{theme::{StyleRole, Theme}, UIMessage};
+
+// How far the mouse has to move before a press counts as a drag rather than a click
+const DRAG_THRESHOLD: f32 = 6.0;
+
+const PREVIEW_WIDTH: f32 = 240.0;
+const PREVIEW_HEIGHT: f32 = 336.0;
+const PREVIEW_FONT_SIZE: u16 = 18;
+
+/// Somewhere a dragged card can be dropped
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub enum DropTarget {
+    /// A character on any player's table
+    Character { player_id: Uuid, instance_id: Uuid },
+    /// Anywhere else on the table
+    PlayArea,
+}
+
+/// The card currently held by the mouse
+pub struct DragState {
+    pub card_instance_id: Uuid,
+    pub img: CardTexture,
+    pub w: f32,
+    pub h: f32,
+    pub grab_x: f32, // Where on the card it was picked up, so it doesn't jump to the mouse
+    pub grab_y: f32,
+    pub start_x: f32,
+    pub start_y: f32,
+    pub on_click: Option<UIMessage>, // Sent instead if the card is let go without moving
+    pub moved: bool, // Stays true once set, so dragging back to where it started doesn't make it a click
+}
+
+impl DragState {
+    pub fn new(card_instance_id: Uuid, img: CardTexture, x: f32, y: f32, w: f32, h: f32, on_click: Option<UIMessage>) -> Self {
+        let (mouse_x, mouse_y) = mouse_position();
+        Self {
+            card_instance_id,
+            img,
+            w,
+            h,
+            grab_x: mouse_x - x,
+            grab_y: mouse_y - y,
+            start_x: mouse_x,
+            start_y: mouse_y,
+            on_click,
+            moved: false,
+        }
+    }
+
+    pub fn update(&mut self) {
+        let (mouse_x, mouse_y) = mouse_position();
+        if vec2(mouse_x - self.start_x, mouse_y - self.start_y).length() > DRAG_THRESHOLD {
+            self.moved = true;
+        }
+    }
+
+    pub fn draw(&self) {
+        if !self.moved {
+            return;
+        }
+        let (mouse_x, mouse_y) = mouse_position();
+        draw_texture_ex(&self.img.borrow(), mouse_x - self.grab_x, mouse_y - self.grab_y, Color::new(1.0, 1.0, 1.0, 0.85),
+            DrawTextureParams {
+                dest_size: Some(vec2(self.w, self.h)),
+                ..Default::default()
+            }
+        );
+    }
+}
+
+/// The card under the mouse, drawn bigger with its description once everything else has been drawn
+pub struct HoveredCard {
+    pub img: CardTexture,
+    pub name: String,
+    pub description: String,
+}
+
+impl HoveredCard {
+    pub fn draw(&self, theme: &Theme) {
+        let lines = wrap_text(theme, &self.description, PREVIEW_WIDTH - 10.0, PREVIEW_FONT_SIZE);
+        let line_height = PREVIEW_FONT_SIZE as f32 + 2.0;
+        let text_height = 30.0 + lines.len() as f32 * line_height;
+        let total_height = PREVIEW_HEIGHT + text_height;
+
+        // Next to the mouse, flipping to the other side rather than going off screen
+        let (mouse_x, mouse_y) = mouse_position();
+        let mut x = mouse_x + 20.0;
+        if x + PREVIEW_WIDTH > screen_width() {
+            x = mouse_x - 20.0 - PREVIEW_WIDTH;
+        }
+        let y = (mouse_y - total_height / 2.0).clamp(0.0, (screen_height() - total_height).max(0.0));
+
+        draw_texture_ex(&self.img.borrow(), x, y, WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(PREVIEW_WIDTH, PREVIEW_HEIGHT)),
+                ..Default::default()
+            }
+        );
+
+        let text_y = y + PREVIEW_HEIGHT;
+        let style = theme.style(StyleRole::Dialog);
+        theme.draw_box(x, text_y, PREVIEW_WIDTH, text_height, style, style.background);
+        theme.draw_text(&self.name, x + 5.0, text_y + 22.0, 24, style.text);
+        for (i, line) in lines.iter().enumerate() {
+            theme.draw_text(line, x + 5.0, text_y + 30.0 + (i + 1) as f32 * line_height, PREVIEW_FONT_SIZE, style.text);
+        }
+    }
+}
+
+// Split text into lines that fit in the given width, breaking between words
+fn wrap_text(theme: &Theme, text: &str, max_width: f32, font_size: u16) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if !line.is_empty() && theme.measure_text(&candidate, font_size).width > max_width {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        } else {
+            line = candidate;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
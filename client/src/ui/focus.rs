@@ -0,0 +1,174 @@
+// Keeps track of which widget has keyboard focus
+// Widgets register themselves as they are drawn, so the tab order is the order they are drawn in,
+// but focus is held by the widgets id so it stays put when things move around (scrolling, rows being hidden)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use macroquad::input::{is_key_down, is_key_pressed, is_mouse_button_pressed, KeyCode, MouseButton};
+
+/// Identifies a widget that can take focus, given out once when the widget is made
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FocusId(u64);
+
+impl FocusId {
+    pub fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+pub struct FocusManager {
+    focused: Option<FocusId>,
+    order: Vec<FocusId>, // Registered so far this frame
+    last_order: Vec<FocusId>, // Everything that registered last frame, in tab order
+    claimed: bool, // A widget was clicked on this frame, so the click doesn't clear the focus
+    blocked: bool, // Something modal is open, so what is drawn behind it can't take focus
+}
+
+impl FocusManager {
+    pub fn new() -> Self {
+        Self {
+            focused: None,
+            order: vec![],
+            last_order: vec![],
+            claimed: false,
+            blocked: false,
+        }
+    }
+
+    /// Call every frame from anything that can take focus, puts it next in the tab order
+    pub fn register(&mut self, id: FocusId) {
+        if !self.blocked {
+            self.order.push(id);
+        }
+    }
+
+    pub fn is_focused(&self, id: FocusId) -> bool {
+        !self.blocked && self.focused == Some(id)
+    }
+
+    /// Give the widget focus, e.g. when it was clicked on
+    pub fn focus(&mut self, id: FocusId) {
+        if !self.blocked {
+            self.focused = Some(id);
+            self.claimed = true;
+        }
+    }
+
+    /// Nothing has focus, e.g. after switching views
+    pub fn clear(&mut self) {
+        self.focused = None;
+    }
+
+    /// Set while drawing whatever is behind a modal, so only the modals own widgets can be focused
+    /// Focus isn't cleared, so unless it moves into the modal it comes back once the modal is gone
+    pub fn set_blocked(&mut self, blocked: bool) {
+        self.blocked = blocked;
+    }
+
+    /// Call once everything has been drawn - moves focus on Tab (back with Shift+Tab) and drops it when clicking on nothing
+    pub fn end_frame(&mut self) {
+        self.finish_order();
+
+        if is_mouse_button_pressed(MouseButton::Left) && !self.claimed {
+            self.focused = None;
+        }
+        self.claimed = false;
+
+        if is_key_pressed(KeyCode::Tab) {
+            self.tab(is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift));
+        }
+    }
+
+    // This frames registrations become the tab order
+    fn finish_order(&mut self) {
+        self.last_order = std::mem::take(&mut self.order);
+        self.blocked = false;
+    }
+
+    // Move to the next widget in the tab order, or the one before, wrapping round at the ends
+    // If the focused widget wasn't drawn (e.g. it was scrolled out of view) it starts again from the first (or last going back)
+    fn tab(&mut self, backwards: bool) {
+        let count = self.last_order.len();
+        if count == 0 {
+            return;
+        }
+        let current = self.focused.and_then(|id| self.last_order.iter().position(|other| *other == id));
+        let next = match (current, backwards) {
+            (Some(index), false) => (index + 1) % count,
+            (Some(index), true) => (index + count - 1) % count,
+            (None, false) => 0,
+            (None, true) => count - 1,
+        };
+        self.focused = Some(self.last_order[next]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Register the widgets as if they were drawn in this order for a frame
+    fn draw_frame(focus: &mut FocusManager, drawn: &[FocusId]) {
+        for id in drawn {
+            focus.register(*id);
+        }
+        focus.finish_order();
+    }
+
+    #[test]
+    fn focus_stays_with_the_widget_when_the_ones_before_it_stop_drawing() {
+        let ids: Vec<FocusId> = (0..4).map(|_| FocusId::next()).collect();
+        let mut focus = FocusManager::new();
+        draw_frame(&mut focus, &ids);
+        focus.focus(ids[2]);
+
+        // Scrolled down so the first two aren't drawn any more
+        draw_frame(&mut focus, &ids[2..]);
+        assert!(focus.is_focused(ids[2]));
+        focus.tab(false);
+        assert!(focus.is_focused(ids[3]));
+    }
+
+    #[test]
+    fn tab_goes_round_in_draw_order() {
+        let ids: Vec<FocusId> = (0..3).map(|_| FocusId::next()).collect();
+        let mut focus = FocusManager::new();
+        draw_frame(&mut focus, &ids);
+
+        focus.tab(false);
+        assert!(focus.is_focused(ids[0]));
+        focus.tab(true);
+        assert!(focus.is_focused(ids[2]));
+        focus.tab(false);
+        assert!(focus.is_focused(ids[0]));
+    }
+
+    #[test]
+    fn only_the_modal_can_be_focused_while_it_is_open() {
+        let board = FocusId::next();
+        let modal = FocusId::next();
+        let mut focus = FocusManager::new();
+        draw_frame(&mut focus, &[board]);
+        focus.focus(board);
+
+        focus.set_blocked(true);
+        focus.register(board);
+        assert!(!focus.is_focused(board));
+        focus.focus(board);
+        focus.set_blocked(false);
+        focus.register(modal);
+        focus.finish_order();
+
+        focus.tab(false);
+        assert!(focus.is_focused(modal));
+        focus.tab(false);
+        assert!(focus.is_focused(modal));
+
+        // Closed again, the board can be tabbed back to
+        draw_frame(&mut focus, &[board]);
+        focus.tab(false);
+        assert!(focus.is_focused(board));
+    }
+}
@@ -1,6 +1,8 @@
-use macroquad::{color::Color, text::{draw_text, measure_text}};
+use std::{cell::RefCell, rc::Rc};
 
-use super::{Position, Size, UIContext, UIElement};
+use macroquad::text::measure_text;
+
+use super::{theme::StyleRole, Position, Size, UIContext, UIElement};
 
 // Label
 pub struct Label {
@@ -10,35 +12,48 @@ pub struct Label {
     w: Size,
     h: Size,
     font_size: u16,
-    txt_color: Color
+    style: StyleRole, // Only the text color is used
 }
 
 // Label logic
 impl Label {
-    pub fn new(x: Position, y: Position, font_size: u16, text: String, color: Color) -> Self {
+    pub fn new(x: Position, y: Position, font_size: u16, text: String, style: StyleRole) -> Self {
+        // Measured with the default font for now, it gets measured again with the theme's font when drawn
         let text_dim = measure_text(&text, None, font_size, 1.0);
         let w = text_dim.width;
         let h = text_dim.height;
-        Self { 
-            text: text, 
-            x: x, 
-            y: y, 
+        Self {
+            text: text,
+            x: x,
+            y: y,
             w: Size::Abs(w),
             h: Size::Abs(h),
-            font_size: font_size, 
-            txt_color: color,
+            font_size: font_size,
+            style,
         }
     }
+
+    /// Change the text, the label resizes to fit it
+    pub fn set_text(&mut self, text: String) {
+        let text_dim = measure_text(&text, None, self.font_size, 1.0);
+        self.w = Size::Abs(text_dim.width);
+        self.h = Size::Abs(text_dim.height);
+        self.text = text;
+    }
 }
 
 //Implement the trait for the Label object
 impl UIElement for Label {
     fn draw(&mut self, ctx: &mut UIContext, parent_x: f32, parent_y: f32, parent_w: f32, parent_h: f32) {
-        
+        let text_dim = ctx.theme.measure_text(&self.text, self.font_size);
+        self.w = Size::Abs(text_dim.width);
+        self.h = Size::Abs(text_dim.height);
+
         let x = self.x.coord(parent_x, parent_w, self.w.caclulate(parent_w));
         let y = self.y.coord(parent_y, parent_h, self.h.caclulate(parent_h));
 
-        draw_text(&self.text, x, y + self.h.caclulate(parent_h), self.font_size as f32, self.txt_color);
+        let color = ctx.theme.style(self.style).text;
+        ctx.theme.draw_text(&self.text, x, y + self.h.caclulate(parent_h), self.font_size, color);
     }
 
     fn get_width(&self, parent_w: f32) -> f32 {
@@ -47,4 +62,20 @@ impl UIElement for Label {
     fn get_height(&self, parent_h: f32) -> f32 {
         self.h.caclulate(parent_h)
     }
-}
\ No newline at end of file
+}
+
+pub struct LabelWrapper(pub Rc<RefCell<Label>>);
+
+impl UIElement for LabelWrapper {
+    fn draw(&mut self, ctx: &mut UIContext, parent_x: f32, parent_y: f32, parent_w: f32, parent_h: f32) {
+        self.0.borrow_mut().draw(ctx, parent_x, parent_y, parent_w, parent_h);
+    }
+
+    fn get_width(&self, parent_w: f32) -> f32 {
+        self.0.borrow().get_width(parent_w)
+    }
+
+    fn get_height(&self, parent_h: f32) -> f32 {
+        self.0.borrow().get_height(parent_h)
+    }
+}
@@ -0,0 +1,376 @@
+// Views can describe their widgets in a json file under res/layouts instead of building them in code
+// Widgets with an id can be looked up afterwards, so the view can read them or fill them in
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use serde::Deserialize;
+
+use super::{button::Button, checkbox::{Checkbox, CheckboxWrapper}, container::{Container, RefCellContainerWrapper}, label::{Label, LabelWrapper}, scroll_container::{RefCellScrollContainerWrapper, ScrollContainer}, slider::{Slider, SliderWrapper}, textbox::{TextBox, TextBoxWrapper}, theme::StyleRole, Layout, Padding, Position, Size, UIElement, UIMessage};
+
+fn no_gap() -> Size {
+    Size::Abs(0.0)
+}
+
+/// One widget in a layout file, the fields match the widget's constructor
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum WidgetDef {
+    Container {
+        id: Option<String>,
+        x: Position,
+        y: Position,
+        w: Size,
+        h: Size,
+        style: StyleRole,
+        #[serde(default)]
+        layout: Layout,
+        #[serde(default = "no_gap")]
+        gap: Size,
+        padding: Option<Padding>,
+        #[serde(default)]
+        children: Vec<WidgetDef>,
+    },
+    ScrollContainer {
+        id: Option<String>,
+        x: Position,
+        y: Position,
+        w: Size,
+        h: Size,
+        style: StyleRole,
+        #[serde(default = "no_gap")]
+        gap: Size,
+        #[serde(default)]
+        children: Vec<WidgetDef>,
+    },
+    Button {
+        x: Position,
+        y: Position,
+        w: Size,
+        h: Size,
+        style: StyleRole,
+        text: String,
+        font_size: u16,
+        on_click: Option<UIMessage>,
+    },
+    Label {
+        id: Option<String>,
+        x: Position,
+        y: Position,
+        font_size: u16,
+        #[serde(default)]
+        text: String,
+        style: StyleRole,
+    },
+    TextBox {
+        id: Option<String>,
+        x: Position,
+        y: Position,
+        w: Size,
+        h: Size,
+        max_length: Option<usize>,
+        on_submit: Option<UIMessage>,
+    },
+    Slider {
+        id: Option<String>,
+        x: Position,
+        y: Position,
+        w: Size,
+        h: Size,
+        min: f32,
+        max: f32,
+        step: f32,
+        #[serde(default)]
+        show_ticks: bool,
+    },
+    Checkbox {
+        id: Option<String>,
+        x: Position,
+        y: Position,
+        w: Size,
+        h: Size,
+        #[serde(default)]
+        checked: bool,
+    },
+}
+
+// A widget that was given an id
+enum Widget {
+    Container(Rc<RefCell<Container>>),
+    ScrollContainer(Rc<RefCell<ScrollContainer>>),
+    Label(Rc<RefCell<Label>>),
+    TextBox(Rc<RefCell<TextBox>>),
+    Slider(Rc<RefCell<Slider>>),
+    Checkbox(Rc<RefCell<Checkbox>>),
+}
+
+/// The widgets in a built layout that have ids
+pub struct Widgets(HashMap<String, Widget>);
+
+impl Widgets {
+    fn add(&mut self, id: Option<String>, widget: Widget) -> Result<(), String> {
+        if let Some(id) = id
+            && self.0.insert(id.clone(), widget).is_some() {
+            return Err(format!("More than one widget has the id '{}'", id));
+        }
+        Ok(())
+    }
+
+    fn get<T>(&self, id: &str, kind: &str, pick: impl Fn(&Widget) -> Option<&Rc<RefCell<T>>>) -> Result<Rc<RefCell<T>>, String> {
+        let widget = self.0.get(id).ok_or_else(|| format!("No widget has the id '{}'", id))?;
+        pick(widget).cloned().ok_or_else(|| format!("'{}' isn't a {}", id, kind))
+    }
+
+    pub fn container(&self, id: &str) -> Result<Rc<RefCell<Container>>, String> {
+        self.get(id, "Container", |widget| match widget { Widget::Container(c) => Some(c), _ => None })
+    }
+
+    pub fn scroll_container(&self, id: &str) -> Result<Rc<RefCell<ScrollContainer>>, String> {
+        self.get(id, "ScrollContainer", |widget| match widget { Widget::ScrollContainer(c) => Some(c), _ => None })
+    }
+
+    pub fn label(&self, id: &str) -> Result<Rc<RefCell<Label>>, String> {
+        self.get(id, "Label", |widget| match widget { Widget::Label(l) => Some(l), _ => None })
+    }
+
+    pub fn text_box(&self, id: &str) -> Result<Rc<RefCell<TextBox>>, String> {
+        self.get(id, "TextBox", |widget| match widget { Widget::TextBox(t) => Some(t), _ => None })
+    }
+
+    pub fn slider(&self, id: &str) -> Result<Rc<RefCell<Slider>>, String> {
+        self.get(id, "Slider", |widget| match widget { Widget::Slider(s) => Some(s), _ => None })
+    }
+
+    pub fn checkbox(&self, id: &str) -> Result<Rc<RefCell<Checkbox>>, String> {
+        self.get(id, "Checkbox", |widget| match widget { Widget::Checkbox(c) => Some(c), _ => None })
+    }
+}
+
+/// Where a view's layout lives - native builds read the file so it can be changed without recompiling,
+/// the web build (or a native build run from somewhere the file can't be found) uses the copy built into the binary
+pub struct LayoutFile {
+    pub path: &'static str,
+    pub built_in: &'static str,
+}
+
+impl LayoutFile {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read(&self) -> String {
+        std::fs::read_to_string(self.path).unwrap_or_else(|_| self.built_in.to_string())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read(&self) -> String {
+        self.built_in.to_string()
+    }
+
+    /// Build the widget tree, the outermost widget has to be a Container
+    pub fn build(&self) -> Result<(Rc<RefCell<Container>>, Widgets), String> {
+        let def: WidgetDef = serde_json::from_str(&self.read()).map_err(|e| format!("{}: {}", self.path, e))?;
+        let mut widgets = Widgets(HashMap::new());
+        let WidgetDef::Container { id, x, y, w, h, style, layout, gap, padding, children } = def else {
+            return Err(format!("{}: the outermost widget has to be a Container", self.path));
+        };
+        let root = Rc::new(RefCell::new(build_container(x, y, w, h, style, layout, gap, padding, children, &mut widgets)?));
+        widgets.add(id, Widget::Container(Rc::clone(&root)))?;
+        Ok((root, widgets))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_container(x: Position, y: Position, w: Size, h: Size, style: StyleRole, layout: Layout, gap: Size, padding: Option<Padding>, children: Vec<WidgetDef>, widgets: &mut Widgets) -> Result<Container, String> {
+    let mut container = Container::new(x, y, w, h, style, layout, gap);
+    if let Some(padding) = padding {
+        container.add_padding(padding);
+    }
+    for child in children {
+        container.add_child(build_widget(child, widgets)?);
+    }
+    Ok(container)
+}
+
+fn build_widget(def: WidgetDef, widgets: &mut Widgets) -> Result<Box<dyn UIElement>, String> {
+    // Widgets with an id are kept in an Rc so the view can get at them, the rest are just boxed
+    let element: Box<dyn UIElement> = match def {
+        WidgetDef::Container { id, x, y, w, h, style, layout, gap, padding, children } => {
+            let container = build_container(x, y, w, h, style, layout, gap, padding, children, widgets)?;
+            match id {
+                Some(_) => {
+                    let container = Rc::new(RefCell::new(container));
+                    widgets.add(id, Widget::Container(Rc::clone(&container)))?;
+                    Box::new(RefCellContainerWrapper(container))
+                },
+                None => Box::new(container),
+            }
+        },
+        WidgetDef::ScrollContainer { id, x, y, w, h, style, gap, children } => {
+            let mut scroll_container = ScrollContainer::new(x, y, w, h, style, gap);
+            for child in children {
+                scroll_container.add_child(build_widget(child, widgets)?);
+            }
+            let scroll_container = Rc::new(RefCell::new(scroll_container));
+            widgets.add(id, Widget::ScrollContainer(Rc::clone(&scroll_container)))?;
+            Box::new(RefCellScrollContainerWrapper(scroll_container))
+        },
+        WidgetDef::Button { x, y, w, h, style, text, font_size, on_click } => {
+            Box::new(Button::new(x, y, w, h, style, text, font_size, on_click))
+        },
+        WidgetDef::Label { id, x, y, font_size, text, style } => {
+            let label = Rc::new(RefCell::new(Label::new(x, y, font_size, text, style)));
+            widgets.add(id, Widget::Label(Rc::clone(&label)))?;
+            Box::new(LabelWrapper(label))
+        },
+        WidgetDef::TextBox { id, x, y, w, h, max_length, on_submit } => {
+            let mut text_box = TextBox::new(x, y, w, h);
+            if let Some(max_length) = max_length {
+                text_box.set_max_length(max_length);
+            }
+            if let Some(msg) = on_submit {
+                text_box.set_on_submit(msg);
+            }
+            let text_box = Rc::new(RefCell::new(text_box));
+            widgets.add(id, Widget::TextBox(Rc::clone(&text_box)))?;
+            Box::new(TextBoxWrapper(text_box))
+        },
+        WidgetDef::Slider { id, x, y, w, h, min, max, step, show_ticks } => {
+            let slider = Rc::new(RefCell::new(Slider::new(x, y, w, h, min, max, step, show_ticks)));
+            widgets.add(id, Widget::Slider(Rc::clone(&slider)))?;
+            Box::new(SliderWrapper(slider))
+        },
+        WidgetDef::Checkbox { id, x, y, w, h, checked } => {
+            let mut checkbox = Checkbox::new(x, y, w, h);
+            checkbox.set_checked(checked);
+            let checkbox = Rc::new(RefCell::new(checkbox));
+            widgets.add(id, Widget::Checkbox(Rc::clone(&checkbox)))?;
+            Box::new(CheckboxWrapper(checkbox))
+        },
+    };
+    Ok(element)
+}
+
+/// Notices when layout files are saved, so the views using them can be rebuilt while the client is running
+#[cfg(not(target_arch = "wasm32"))]
+pub struct LayoutWatcher {
+    modified: HashMap<&'static str, std::time::SystemTime>,
+    last_check: f64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LayoutWatcher {
+    // Looking at the files every frame would be a waste
+    const CHECK_INTERVAL: f64 = 1.0;
+
+    pub fn new() -> Self {
+        Self {
+            modified: HashMap::new(),
+            last_check: 0.0,
+        }
+    }
+
+    /// Which of the files have changed since they were last checked
+    pub fn changed(&mut self, files: &[&LayoutFile]) -> Vec<&'static str> {
+        let now = macroquad::time::get_time();
+        if now - self.last_check < Self::CHECK_INTERVAL {
+            return vec![];
+        }
+        self.last_check = now;
+
+        let mut changed = vec![];
+        for file in files {
+            let Ok(modified) = std::fs::metadata(file.path).and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            // The first time a file is seen is just to know what it was like to begin with
+            if let Some(previous) = self.modified.insert(file.path, modified)
+                && previous != modified {
+                changed.push(file.path);
+            }
+        }
+        changed
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Built from the built in copy, nothing is at the path
+    fn layout(built_in: &'static str) -> LayoutFile {
+        LayoutFile { path: "res/layouts/not_a_layout.json", built_in }
+    }
+
+    // Labels need a window to measure their text, so these layouts leave them out
+    const FORM: &str = r#"{
+        "type": "Container", "id": "root",
+        "x": {"Align": "LeTop"}, "y": {"Align": "LeTop"}, "w": {"Rel": 1.0}, "h": {"Rel": 1.0},
+        "style": "Background",
+        "children": [
+            { "type": "TextBox", "id": "name", "x": {"Abs": 10.0}, "y": {"Abs": 10.0}, "w": {"Abs": 200.0}, "h": {"Abs": 30.0}, "max_length": 4 },
+            {
+                "type": "Container", "id": "options",
+                "x": {"Align": "Centre"}, "y": {"Rel": 0.5}, "w": {"Abs": 300.0}, "h": {"Abs": 100.0},
+                "style": "Transparent", "layout": "ColumnCentre",
+                "children": [
+                    { "type": "Slider", "id": "players", "x": {"Abs": 0.0}, "y": {"Abs": 0.0}, "w": {"Abs": 200.0}, "h": {"Abs": 20.0}, "min": 2.0, "max": 8.0, "step": 1.0 },
+                    { "type": "Checkbox", "id": "private", "x": {"Abs": 0.0}, "y": {"Abs": 0.0}, "w": {"Abs": 20.0}, "h": {"Abs": 20.0}, "checked": true }
+                ]
+            },
+            { "type": "Container", "x": {"Abs": 0.0}, "y": {"Abs": 0.0}, "w": {"Abs": 10.0}, "h": {"Abs": 10.0}, "style": "Transparent" }
+        ]
+    }"#;
+
+    #[test]
+    fn widgets_with_ids_can_be_looked_up() {
+        let (_, widgets) = layout(FORM).build().unwrap();
+        assert!(widgets.container("root").is_ok());
+        assert!(widgets.container("options").is_ok());
+        assert!(widgets.slider("players").is_ok());
+        assert!(widgets.checkbox("private").unwrap().borrow().is_checked());
+
+        // The settings from the file get applied
+        let name = widgets.text_box("name").unwrap();
+        name.borrow_mut().set_text("abcdef".to_string());
+        assert_eq!(name.borrow().get_text(), "abcd");
+    }
+
+    #[test]
+    fn looking_up_the_wrong_id_or_kind_is_an_error() {
+        let (_, widgets) = layout(FORM).build().unwrap();
+        assert!(widgets.text_box("nope").is_err());
+        assert!(widgets.slider("name").is_err());
+    }
+
+    #[test]
+    fn ids_have_to_be_unique() {
+        let duplicate = layout(r#"{
+            "type": "Container", "x": {"Abs": 0.0}, "y": {"Abs": 0.0}, "w": {"Rel": 1.0}, "h": {"Rel": 1.0}, "style": "Background",
+            "children": [
+                { "type": "Checkbox", "id": "same", "x": {"Abs": 0.0}, "y": {"Abs": 0.0}, "w": {"Abs": 20.0}, "h": {"Abs": 20.0} },
+                { "type": "Checkbox", "id": "same", "x": {"Abs": 0.0}, "y": {"Abs": 0.0}, "w": {"Abs": 20.0}, "h": {"Abs": 20.0} }
+            ]
+        }"#);
+        assert!(duplicate.build().is_err());
+    }
+
+    #[test]
+    fn the_outermost_widget_has_to_be_a_container() {
+        let checkbox = layout(r#"{ "type": "Checkbox", "x": {"Abs": 0.0}, "y": {"Abs": 0.0}, "w": {"Abs": 20.0}, "h": {"Abs": 20.0} }"#);
+        assert!(checkbox.build().is_err());
+        assert!(layout(r#"{ "type": "Container" }"#).build().is_err());
+        assert!(layout("not json").build().is_err());
+    }
+
+    #[test]
+    fn every_layout_file_parses() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/layouts");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let data = std::fs::read_to_string(&path).unwrap();
+            let def = serde_json::from_str::<WidgetDef>(&data).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            assert!(matches!(def, WidgetDef::Container { .. }), "{} doesn't start with a Container", path.display());
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+}
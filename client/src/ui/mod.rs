@@ -1,15 +1,28 @@
+use macroquad::{input::{is_mouse_button_released, mouse_position, MouseButton}, window::{get_internal_gl, screen_dpi_scale}};
+
+use serde::Deserialize;
+
 use crate::views::{MenuState};
 
+use self::{drag::{DragState, DropTarget, HoveredCard}, focus::FocusManager, theme::Theme};
+
 
 pub mod cardui;
 pub mod container;
 pub mod label;
+pub mod scroll_container;
 pub mod button;
 pub mod slider;
 pub mod textbox;
+pub mod theme;
 pub mod checkbox;
+pub mod choice_prompt;
+pub mod drag;
+pub mod focus;
+pub mod layout_file;
 
 // Align along an axis
+#[derive(Deserialize)]
 pub enum Alignment {
     Centre,
     LeTop,
@@ -17,6 +30,7 @@ pub enum Alignment {
 }
 
 // Position the object relative to the parent object
+#[derive(Deserialize)]
 pub enum Position {
     Abs(f32),
     Rel(f32), // Percentage of the parent
@@ -38,6 +52,7 @@ impl Position {
     }
 }
 
+#[derive(Deserialize)]
 pub struct Padding {
     top: f32,
     bottom: f32,
@@ -71,6 +86,7 @@ impl Padding {
 }
 
 // Size of the object
+#[derive(Deserialize)]
 pub enum Size {
     Abs(f32),
     Rel(f32),
@@ -87,7 +103,9 @@ impl Size {
 
 
 // Layout - similar to flex
+#[derive(Deserialize, Default)]
 pub enum Layout {
+    #[default]
     None,
     RowCentre,
     ColumnCentre,
@@ -105,23 +123,112 @@ pub trait UIElement {
 
 
 pub struct UIContext {
-    pub message_queue: Vec<UIMessage>
+    pub message_queue: Vec<UIMessage>,
+    pub drag: Option<DragState>, // Card being dragged, if any
+    pub drop_target: Option<DropTarget>, // Drop zone under the mouse this frame, the innermost one wins
+    pub hovered_card: Option<HoveredCard>, // Card to show enlarged this frame
+    pub focus: FocusManager, // Which widget keyboard input goes to
+    pub theme: Theme,
+    clip_stack: Vec<(f32, f32, f32, f32)>, // Areas drawing is cut off to, the last one is the one in use
 }
 
 impl UIContext {
     pub fn new() -> Self {
-        Self { message_queue: vec![] }
+        Self {
+            message_queue: vec![],
+            drag: None,
+            drop_target: None,
+            hovered_card: None,
+            focus: FocusManager::new(),
+            theme: Theme::new(),
+            clip_stack: vec![],
+        }
+    }
+
+    /// Cut off anything drawn outside of the area (and any area it is already inside of) until pop_clip
+    pub fn push_clip(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let clip = match self.clip_stack.last() {
+            Some(&(px, py, pw, ph)) => {
+                let left = x.max(px);
+                let top = y.max(py);
+                (left, top, ((x + w).min(px + pw) - left).max(0.0), ((y + h).min(py + ph) - top).max(0.0))
+            },
+            None => (x, y, w, h),
+        };
+        self.clip_stack.push(clip);
+        self.apply_clip();
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+        self.apply_clip();
+    }
+
+    fn apply_clip(&self) {
+        // The scissor works in actual pixels rather than the scaled ones everything else uses
+        let dpi = screen_dpi_scale();
+        let clip = self.clip_stack.last().map(|&(x, y, w, h)| {
+            ((x * dpi) as i32, (y * dpi) as i32, (w * dpi).ceil() as i32, (h * dpi).ceil() as i32)
+        });
+        unsafe { get_internal_gl() }.quad_gl.scissor(clip);
+    }
+
+    /// Is the mouse over the area, and not over a part of it that has been clipped off
+    pub fn mouse_over(&self, x: f32, y: f32, w: f32, h: f32) -> bool {
+        let (mouse_x, mouse_y) = mouse_position();
+        let inside = |(x, y, w, h): (f32, f32, f32, f32)| mouse_x >= x && mouse_x <= (x + w) && mouse_y >= y && mouse_y <= (y + h);
+        inside((x, y, w, h)) && self.clip_stack.last().is_none_or(|clip| inside(*clip))
+    }
+
+    /// Call after drawing the view - draws anything that sits on top of it, finishes a drag when the mouse is let go
+    /// and moves the keyboard focus
+    pub fn draw_overlay(&mut self) {
+        self.focus.end_frame();
+
+        if let Some(hovered) = self.hovered_card.take() {
+            hovered.draw(&self.theme);
+        }
+        if let Some(drag) = &mut self.drag {
+            drag.update();
+            drag.draw();
+        }
+
+        if is_mouse_button_released(MouseButton::Left) && let Some(drag) = self.drag.take() {
+            if !drag.moved {
+                // Barely moved, so treat it as a click
+                if let Some(msg) = drag.on_click {
+                    self.message_queue.push(msg);
+                }
+            } else if let Some(target) = self.drop_target.take() {
+                self.message_queue.push(UIMessage::DropCard { card_instance_id: drag.card_instance_id, target });
+            }
+        }
+        self.drop_target = None;
     }
 }
 
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Deserialize)]
 pub enum UIMessage {
     DrawCard(uuid::Uuid),
     SwitchView(MenuState),
     TryConnectToMatchmaking,
+    DirectConnect, // Connect to whatever is in the direct connect address box
+    ConnectToServer(String), // Connect straight to a room server, e.g. a recent one
     CreateRoom,
     JoinRoom(uuid::Uuid),
+    JoinByCode,
+    AddBot(common::server::messages::BotDifficulty),
+    SetReady(bool),
+    SelectDeck(String),
+    StartGame,
+    KickPlayer(uuid::Uuid),
+    TransferHost(uuid::Uuid),
+    PlayCard(uuid::Uuid),
+    DropCard { card_instance_id: uuid::Uuid, target: DropTarget },
+    EndTurn,
+    MakeChoice { choice_id: uuid::Uuid, selected: Vec<uuid::Uuid> },
+    SaveSettings,
 }
 
 
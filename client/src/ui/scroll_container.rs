@@ -0,0 +1,154 @@
+use std::{cell::RefCell, rc::Rc};
+
+use macroquad::{color::Color, input::{is_mouse_button_down, is_mouse_button_pressed, mouse_position, mouse_wheel, MouseButton}, shapes::draw_rectangle};
+
+use super::{theme::StyleRole, Position, Size, UIContext, UIElement};
+
+const SCROLLBAR_WIDTH: f32 = 10.0;
+const MIN_THUMB_HEIGHT: f32 = 20.0;
+// How far one notch of the mouse wheel moves the list
+const SCROLL_STEP: f32 = 40.0;
+
+/// A column of children (like Layout::ColumnTop) that scrolls once they don't fit
+/// Only the children that can be seen get drawn, so it stays fast with hundreds of them
+pub struct ScrollContainer {
+    children: Vec<Box<dyn UIElement>>,
+    x: Position,
+    y: Position,
+    w: Size,
+    h: Size,
+    style: StyleRole,
+    gap: Size,
+    scroll: f32, // How far down the list has been moved, in pixels
+    drag_start: Option<(f32, f32)>, // Mouse y and scroll when the list (or its scrollbar) was grabbed
+    dragging_bar: bool,
+}
+
+impl ScrollContainer {
+    pub fn new(x: Position, y: Position, w: Size, h: Size, style: StyleRole, gap: Size) -> Self {
+        Self {
+            children: vec![],
+            x,
+            y,
+            w,
+            h,
+            style,
+            gap,
+            scroll: 0.0,
+            drag_start: None,
+            dragging_bar: false,
+        }
+    }
+
+    /// When calling, wrap the object in a box Box::new()
+    pub fn add_child(&mut self, child: Box<dyn UIElement>) {
+        self.children.push(child);
+    }
+
+    pub fn clear_children(&mut self) {
+        self.children.clear();
+    }
+
+    /// Remove the child at the position it was added in
+    pub fn remove_child(&mut self, index: usize) {
+        if index < self.children.len() {
+            self.children.remove(index);
+        }
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll = 0.0;
+    }
+}
+
+impl UIElement for ScrollContainer {
+    fn draw(&mut self, ctx: &mut UIContext, parent_x: f32, parent_y: f32, parent_w: f32, parent_h: f32) {
+        let w = self.w.caclulate(parent_w);
+        let h = self.h.caclulate(parent_h);
+        let x = self.x.coord(parent_x, parent_w, w);
+        let y = self.y.coord(parent_y, parent_h, h);
+        let gap = self.gap.caclulate(h);
+
+        let style = ctx.theme.style(self.style);
+        ctx.theme.draw_box(x, y, w, h, style, style.background);
+
+        // Working out the heights is cheap, it's drawing everything that isn't
+        let heights: Vec<f32> = self.children.iter().map(|child| child.get_height(h)).collect();
+        let content_h = heights.iter().sum::<f32>() + gap * (heights.len() + 1) as f32;
+        let max_scroll = (content_h - h).max(0.0);
+        let overflowing = max_scroll > 0.0;
+        let content_w = if overflowing { w - SCROLLBAR_WIDTH } else { w };
+        self.scroll = self.scroll.clamp(0.0, max_scroll);
+
+        ctx.push_clip(x, y, content_w, h);
+        let mut child_y = y + gap - self.scroll;
+        for (child, child_h) in self.children.iter_mut().zip(heights.iter()) {
+            if child_y > y + h {
+                break;
+            }
+            if child_y + child_h >= y {
+                child.draw(ctx, x, child_y, content_w, *child_h);
+            }
+            child_y += child_h + gap;
+        }
+        ctx.pop_clip();
+
+        let thumb_h = (h * h / content_h).clamp(MIN_THUMB_HEIGHT.min(h), h);
+        if overflowing {
+            // In the styles text color, faded so it doesn't stand out more than the list
+            let bar_color = ctx.theme.style(self.style).text;
+            let thumb_y = y + (self.scroll / max_scroll) * (h - thumb_h);
+            draw_rectangle(x + content_w, y, SCROLLBAR_WIDTH, h, Color::new(bar_color.r, bar_color.g, bar_color.b, 0.15));
+            draw_rectangle(x + content_w, thumb_y, SCROLLBAR_WIDTH, thumb_h, Color::new(bar_color.r, bar_color.g, bar_color.b, 0.5));
+        }
+
+        // Input is handled after the children so grabbing a card in the list drags the card, not the list
+        let hovered = ctx.mouse_over(x, y, w, h);
+        let (mouse_x, mouse_y) = mouse_position();
+        let wheel = mouse_wheel().1;
+        if hovered && wheel != 0.0 {
+            // Different platforms give wildly different amounts per notch, so only the direction is used
+            self.scroll -= wheel.signum() * SCROLL_STEP;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) && hovered && overflowing && ctx.drag.is_none() {
+            self.dragging_bar = mouse_x >= x + content_w;
+            self.drag_start = Some((mouse_y, self.scroll));
+        }
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.drag_start = None;
+        }
+        if let Some((start_y, start_scroll)) = self.drag_start {
+            let moved = mouse_y - start_y;
+            self.scroll = if self.dragging_bar {
+                // The thumb follows the mouse, so move the list as much as the thumb moved
+                start_scroll + moved * max_scroll / (h - thumb_h).max(1.0)
+            } else {
+                start_scroll - moved
+            };
+        }
+        self.scroll = self.scroll.clamp(0.0, max_scroll);
+    }
+    fn get_width(&self, parent_w: f32) -> f32 {
+        self.w.caclulate(parent_w)
+    }
+    fn get_height(&self, parent_h: f32) -> f32 {
+        self.h.caclulate(parent_h)
+    }
+}
+
+pub struct RefCellScrollContainerWrapper(pub Rc<RefCell<ScrollContainer>>);
+
+impl UIElement for RefCellScrollContainerWrapper {
+    fn draw(&mut self, ctx: &mut UIContext, parent_x: f32, parent_y: f32, parent_w: f32, parent_h: f32) {
+        self.0.borrow_mut().draw(ctx, parent_x, parent_y, parent_w, parent_h);
+    }
+
+    fn get_width(&self, parent_w: f32) -> f32 {
+        self.0.borrow().get_width(parent_w)
+    }
+
+    fn get_height(&self, parent_h: f32) -> f32 {
+        self.0.borrow().get_height(parent_h)
+    }
+}
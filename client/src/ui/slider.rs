@@ -1,6 +1,6 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::ui::{Position, Size, UIContext, UIElement};
+use crate::ui::{theme::StyleRole, Position, Size, UIContext, UIElement};
 use macroquad::prelude::*;
 
 pub struct Slider {
@@ -10,9 +10,6 @@ pub struct Slider {
     w: Size,
     h: Size,
     dragging: bool,
-    bg_color: Color,
-    knob_color: Color,
-    tick_color: Color,
     min: f32,
     max: f32,
     step: f32,
@@ -28,9 +25,6 @@ impl Slider {
         y: Position,
         w: Size,
         h: Size,
-        bg_color: Color,
-        knob_color: Color,
-        tick_color: Color,
         min: f32,
         max: f32,
         step: f32,
@@ -43,9 +37,6 @@ impl Slider {
             w,
             h,
             dragging: false,
-            bg_color,
-            knob_color,
-            tick_color,
             min,
             max,
             step,
@@ -97,13 +88,17 @@ impl UIElement for Slider {
         }
 
 
+        // Track is the styles background, the knob its text color (or hover color while it is being used)
+        let style = ctx.theme.style(StyleRole::Slider);
+        let knob_color = if mouse_over || self.dragging { style.hover } else { style.text };
+
         // Draw track
-        draw_rectangle(x, track_y, w, 4.0, self.bg_color);
+        draw_rectangle(x, track_y, w, 4.0, style.background);
 
         // Draw knob
         let rel_value = (self.value - self.min) / (self.max - self.min);
         let knob_x = x + rel_value * w - 5.0;
-        draw_rectangle(knob_x, knob_y, 10.0, slider_h, self.knob_color);
+        draw_rectangle(knob_x, knob_y, 10.0, slider_h, knob_color);
 
         // Draw ticks + labels
         if self.show_ticks {
@@ -116,18 +111,18 @@ impl UIElement for Slider {
                 let is_major = i % self.label_every == 0;
                 let tick_height = if is_major { self.major_tick_height } else { self.minor_tick_height };
 
-                draw_line(tick_x, tick_y, tick_x, tick_y + tick_height, 1.0, self.tick_color);
+                draw_line(tick_x, tick_y, tick_x, tick_y + tick_height, 1.0, style.text);
 
                 if is_major {
                     let val = self.min + i as f32 * self.step;
                     let label = format!("{}", val.round() as i32);
-                    let label_size = measure_text(&label, None, 16, 1.0).width;
-                    draw_text(
+                    let label_size = ctx.theme.measure_text(&label, 16).width;
+                    ctx.theme.draw_text(
                         &label,
                         tick_x - label_size / 2.0,
                         tick_y + tick_height + 14.0,
-                        16.0,
-                        self.tick_color,
+                        16,
+                        style.text,
                     );
                 }
             }
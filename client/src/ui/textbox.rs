@@ -1,98 +1,310 @@
 use macroquad::prelude::*;
+use macroquad::miniquad::window::{clipboard_get, clipboard_set};
 use std::{cell::RefCell, rc::Rc};
 
-use crate::ui::{Position, Size, UIContext, UIElement};
+use crate::ui::{focus::FocusId, theme::{StyleRole, Theme}, Position, Size, UIContext, UIElement, UIMessage};
+
+// Keys that keep going when held down, like they would in any other text box
+const REPEAT_KEYS: [KeyCode; 4] = [KeyCode::Left, KeyCode::Right, KeyCode::Backspace, KeyCode::Delete];
+const REPEAT_DELAY: f64 = 0.4;
+const REPEAT_RATE: f64 = 0.04;
+
+const FONT_SIZE: f32 = 20.0;
 
 pub struct TextBox {
     text: String,
-    is_focused: bool,
+    cursor: usize, // In chars, not bytes
+    selection_anchor: Option<usize>, // Where the selection started, the cursor is the other end
+    scroll_x: f32, // How far the text has been moved left so the cursor stays in view
+    held_key: Option<(KeyCode, f64)>, // Key being held and when it next repeats
+    max_length: Option<usize>,
+    on_submit: Option<UIMessage>,
     cursor_visible: bool,
     last_cursor_blink: f64,
     x: Position,
     y: Position,
     w: Size,
     h: Size,
-    bg_color: Color,
-    border_color: Color,
-    text_color: Color,
     padding: f32,
+    focus_id: FocusId,
 }
 
 impl TextBox {
-    pub fn new(x: Position, y: Position, w: Size, h: Size, bg_color: Color, border_color: Color, text_color: Color) -> Self {
+    pub fn new(x: Position, y: Position, w: Size, h: Size) -> Self {
         Self {
             text: String::new(),
-            is_focused: false,
+            cursor: 0,
+            selection_anchor: None,
+            scroll_x: 0.0,
+            held_key: None,
+            max_length: None,
+            on_submit: None,
             cursor_visible: true,
-            last_cursor_blink: macroquad::time::get_time(),
+            last_cursor_blink: 0.0, // Starts blinking from when it gets clicked
             x,
             y,
             w,
             h,
-            bg_color: bg_color,
-            border_color: border_color,
-            text_color: text_color,
             padding: 5.0,
+            focus_id: FocusId::next(),
         }
     }
     pub fn get_text(&self) -> String {
         self.text.clone()
     }
+    pub fn set_text(&mut self, text: String) {
+        self.text = match self.max_length {
+            Some(max) => text.chars().take(max).collect(),
+            None => text,
+        };
+        self.cursor = self.char_count();
+        self.selection_anchor = None;
+    }
+
+    /// Stop typing (or pasting) once the text is this many characters long
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = Some(max_length);
+        let text = self.text.clone();
+        self.set_text(text);
+    }
+
+    /// Sent when Enter is pressed while the text box has focus
+    pub fn set_on_submit(&mut self, msg: UIMessage) {
+        self.on_submit = Some(msg);
+    }
+
+    fn char_count(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text.char_indices().nth(char_index).map(|(i, _)| i).unwrap_or(self.text.len())
+    }
+
+    fn text_width(&self, theme: &Theme, char_index: usize) -> f32 {
+        theme.measure_text(&self.text[..self.byte_index(char_index)], FONT_SIZE as u16).width
+    }
+
+    // Start and end of the selection, if anything is selected
+    fn selection(&self) -> Option<(usize, usize)> {
+        match self.selection_anchor {
+            Some(anchor) if anchor != self.cursor => Some((anchor.min(self.cursor), anchor.max(self.cursor))),
+            _ => None,
+        }
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        self.selection().map(|(start, end)| self.text[self.byte_index(start)..self.byte_index(end)].to_string())
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            return false;
+        };
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.text.replace_range(range, "");
+        self.cursor = start;
+        self.selection_anchor = None;
+        true
+    }
+
+    // Backspace and delete take out the selection if there is one, otherwise the char either side of the cursor
+    fn delete_char(&mut self, forwards: bool) {
+        if self.delete_selection() {
+            return;
+        }
+        let to = if forwards { self.cursor + 1 } else { self.cursor.saturating_sub(1) };
+        self.move_cursor(to, true);
+        self.delete_selection();
+    }
+
+    // Typing or pasting replaces whatever is selected, and stops at the max length
+    fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let room = self.max_length.map_or(usize::MAX, |max| max.saturating_sub(self.char_count()));
+        let text: String = text.chars().filter(|c| !c.is_control()).take(room).collect();
+        let index = self.byte_index(self.cursor);
+        self.text.insert_str(index, &text);
+        self.cursor += text.chars().count();
+    }
+
+    // Move the cursor, dragging the selection along with it if shift is held
+    fn move_cursor(&mut self, to: usize, selecting: bool) {
+        if selecting {
+            self.selection_anchor.get_or_insert(self.cursor);
+        } else {
+            self.selection_anchor = None;
+        }
+        self.cursor = to.min(self.char_count());
+    }
+
+    // The char closest to an x position on screen
+    fn char_at(&self, theme: &Theme, x: f32) -> usize {
+        (0..=self.char_count())
+            .min_by(|a, b| (self.text_width(theme, *a) - x).abs().total_cmp(&(self.text_width(theme, *b) - x).abs()))
+            .unwrap_or(0)
+    }
+
+    fn repeating_key(&mut self) -> Option<KeyCode> {
+        let now = get_time();
+        if let Some(key) = REPEAT_KEYS.into_iter().find(|key| is_key_pressed(*key)) {
+            self.held_key = Some((key, now + REPEAT_DELAY));
+            return Some(key);
+        }
+        match self.held_key {
+            Some((key, _)) if !is_key_down(key) => {
+                self.held_key = None;
+                None
+            },
+            Some((key, next)) if now >= next => {
+                self.held_key = Some((key, next + REPEAT_RATE));
+                Some(key)
+            },
+            _ => None,
+        }
+    }
+
+    fn handle_keys(&mut self, ctx: &mut UIContext) {
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)
+            || is_key_down(KeyCode::LeftSuper) || is_key_down(KeyCode::RightSuper);
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+
+        // Chars typed while ctrl is down are shortcuts, not text
+        while let Some(c) = get_char_pressed() {
+            if !ctrl && !c.is_control() {
+                self.insert(&c.to_string());
+            }
+        }
+
+        match self.repeating_key() {
+            Some(KeyCode::Left) => match self.selection() {
+                Some((start, _)) if !shift => self.move_cursor(start, false),
+                _ => self.move_cursor(self.cursor.saturating_sub(1), shift),
+            },
+            Some(KeyCode::Right) => match self.selection() {
+                Some((_, end)) if !shift => self.move_cursor(end, false),
+                _ => self.move_cursor(self.cursor + 1, shift),
+            },
+            Some(KeyCode::Backspace) => self.delete_char(false),
+            Some(KeyCode::Delete) => self.delete_char(true),
+            _ => {},
+        }
+
+        if is_key_pressed(KeyCode::Home) {
+            self.move_cursor(0, shift);
+        }
+        if is_key_pressed(KeyCode::End) {
+            self.move_cursor(self.char_count(), shift);
+        }
+
+        if ctrl && is_key_pressed(KeyCode::A) {
+            self.selection_anchor = Some(0);
+            self.cursor = self.char_count();
+        }
+        if ctrl && (is_key_pressed(KeyCode::C) || is_key_pressed(KeyCode::X)) && let Some(selected) = self.selected_text() {
+            clipboard_set(&selected);
+            if is_key_pressed(KeyCode::X) {
+                self.delete_selection();
+            }
+        }
+        // Only the first line of whatever was copied, a text box only has the one
+        if ctrl && is_key_pressed(KeyCode::V) && let Some(pasted) = clipboard_get() {
+            self.insert(pasted.lines().next().unwrap_or(""));
+        }
+
+        if (is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter)) && let Some(ref msg) = self.on_submit {
+            ctx.message_queue.push(msg.clone());
+        }
+    }
 }
 
 impl UIElement for TextBox {
-    fn draw(&mut self, _ctx: &mut UIContext, parent_x: f32, parent_y: f32, parent_w: f32, parent_h: f32) {
+    fn draw(&mut self, ctx: &mut UIContext, parent_x: f32, parent_y: f32, parent_w: f32, parent_h: f32) {
         let w = self.w.caclulate(parent_w);
         let h = self.h.caclulate(parent_h);
         let x = self.x.coord(parent_x, parent_w, w);
         let y = self.y.coord(parent_y, parent_h, h);
+        ctx.focus.register(self.focus_id);
+
+        let text_x = x + self.padding;
+        let text_y = y + h / 2.0 - FONT_SIZE / 2.0;
 
-        // Handle mouse focus
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let (mx, my) = mouse_position();
-            self.is_focused = mx >= x && mx <= x + w && my >= y && my <= y + h;
+        // Clicking puts the cursor where the mouse is, dragging selects
+        let (mx, _) = mouse_position();
+        if is_mouse_button_pressed(MouseButton::Left) && ctx.mouse_over(x, y, w, h) {
+            ctx.focus.focus(self.focus_id);
+            let index = self.char_at(&ctx.theme, mx - text_x + self.scroll_x);
+            let selecting = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            self.move_cursor(index, selecting);
+            if !selecting {
+                self.selection_anchor = Some(index);
+            }
+        } else if ctx.focus.is_focused(self.focus_id) && is_mouse_button_down(MouseButton::Left) && self.selection_anchor.is_some() {
+            self.cursor = self.char_at(&ctx.theme, mx - text_x + self.scroll_x);
         }
 
+        let is_focused = ctx.focus.is_focused(self.focus_id);
+        if is_focused {
+            let cursor_before = self.cursor;
+            let text_before = self.text.len();
+            self.handle_keys(ctx);
+            // Keep the cursor showing while it is being moved
+            if self.cursor != cursor_before || self.text.len() != text_before {
+                self.cursor_visible = true;
+                self.last_cursor_blink = macroquad::time::get_time();
+            }
+        } else {
+            self.selection_anchor = None;
+        }
+
+        // Slide the text along so the cursor is always inside the box
+        let visible_w = w - self.padding * 2.0;
+        let cursor_x = self.text_width(&ctx.theme, self.cursor);
+        if cursor_x - self.scroll_x > visible_w {
+            self.scroll_x = cursor_x - visible_w;
+        } else if cursor_x < self.scroll_x {
+            self.scroll_x = cursor_x;
+        }
+        self.scroll_x = self.scroll_x.min((self.text_width(&ctx.theme, self.char_count()) - visible_w).max(0.0)).max(0.0);
+
         // Draw background
-        draw_rectangle(x, y, w, h, self.bg_color);
-        draw_rectangle_lines(x, y, w, h, 2.0, self.border_color);
+        let style = ctx.theme.style(StyleRole::Input).clone();
+        ctx.theme.draw_box(x, y, w, h, &style, style.background);
+        if is_focused {
+            ctx.theme.draw_focus(x, y, w, h, style.corner_radius);
+        }
+
+        ctx.push_clip(x + self.padding, y, visible_w, h);
+        let draw_x = text_x - self.scroll_x;
+
+        if let Some((start, end)) = self.selection() {
+            let start_x = draw_x + self.text_width(&ctx.theme, start);
+            draw_rectangle(start_x, text_y, draw_x + self.text_width(&ctx.theme, end) - start_x, FONT_SIZE, Color::new(0.2, 0.4, 1.0, 0.4));
+        }
 
         // Draw text
-        let font_size = 20.0;
-        let text_x = x + self.padding;
-        let text_y = y + h / 2.0 - font_size / 2.0;
-        draw_text(&self.text, text_x, text_y + font_size, font_size, self.text_color);
+        ctx.theme.draw_text(&self.text, draw_x, text_y + FONT_SIZE, FONT_SIZE as u16, style.text);
 
         // Cursor blinking
-        if self.is_focused {
+        if is_focused {
             if macroquad::time::get_time() - self.last_cursor_blink > 0.5 {
                 self.cursor_visible = !self.cursor_visible;
                 self.last_cursor_blink = macroquad::time::get_time();
             }
 
             if self.cursor_visible {
-                let text_width = measure_text(&self.text, None, font_size as u16, 1.0).width;
+                let cursor_draw_x = draw_x + cursor_x + 1.0;
                 draw_line(
-                    text_x + text_width + 2.0,
+                    cursor_draw_x,
                     text_y,
-                    text_x + text_width + 2.0,
-                    text_y + font_size,
+                    cursor_draw_x,
+                    text_y + FONT_SIZE,
                     2.0,
-                    self.text_color,
+                    style.text,
                 );
             }
-
-            // Handle text input
-            if let Some(c) = get_char_pressed() {
-                if !c.is_control() {
-                    self.text.push(c);
-                }
-            }
-
-            if is_key_pressed(KeyCode::Backspace) {
-                self.text.pop(); // backspace
-            }
         }
+        ctx.pop_clip();
     }
 
     fn get_width(&self, parent_w: f32) -> f32 {
@@ -121,4 +333,113 @@ impl UIElement for TextBoxWrapper {
     fn get_height(&self, parent_h: f32) -> f32 {
         self.0.borrow().get_height(parent_h)
     }
-}
\ No newline at end of file
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_box(text: &str) -> TextBox {
+        let mut text_box = TextBox::new(Position::Abs(0.0), Position::Abs(0.0), Size::Abs(100.0), Size::Abs(30.0));
+        text_box.set_text(text.to_string());
+        text_box
+    }
+
+    #[test]
+    fn typing_goes_in_at_the_cursor() {
+        let mut text_box = text_box("helo");
+        text_box.move_cursor(3, false);
+        text_box.insert("l");
+        assert_eq!(text_box.get_text(), "hello");
+        assert_eq!(text_box.cursor, 4);
+
+        // Control chars (e.g. a pasted tab) are left out
+        text_box.insert("\t!");
+        assert_eq!(text_box.get_text(), "hell!o");
+    }
+
+    #[test]
+    fn backspace_and_delete_take_the_char_either_side() {
+        let mut text_box = text_box("abcd");
+        text_box.move_cursor(2, false);
+        text_box.delete_char(false);
+        assert_eq!(text_box.get_text(), "acd");
+        assert_eq!(text_box.cursor, 1);
+
+        text_box.delete_char(true);
+        assert_eq!(text_box.get_text(), "ad");
+        assert_eq!(text_box.cursor, 1);
+
+        // Nothing to take at either end
+        text_box.move_cursor(0, false);
+        text_box.delete_char(false);
+        text_box.move_cursor(2, false);
+        text_box.delete_char(true);
+        assert_eq!(text_box.get_text(), "ad");
+    }
+
+    #[test]
+    fn shift_moves_select_and_typing_replaces_the_selection() {
+        let mut text_box = text_box("hello world");
+        text_box.move_cursor(0, false);
+        text_box.move_cursor(5, true);
+        assert_eq!(text_box.selection(), Some((0, 5)));
+        assert_eq!(text_box.selected_text().as_deref(), Some("hello"));
+
+        text_box.insert("goodbye");
+        assert_eq!(text_box.get_text(), "goodbye world");
+        assert_eq!(text_box.selection(), None);
+        assert_eq!(text_box.cursor, 7);
+    }
+
+    #[test]
+    fn selecting_backwards_works_the_same() {
+        let mut text_box = text_box("hello world");
+        text_box.move_cursor(3, true);
+        assert_eq!(text_box.selected_text().as_deref(), Some("lo world"));
+
+        text_box.delete_char(false);
+        assert_eq!(text_box.get_text(), "hel");
+        assert_eq!(text_box.cursor, 3);
+    }
+
+    #[test]
+    fn moving_without_shift_drops_the_selection() {
+        let mut text_box = text_box("hello");
+        text_box.move_cursor(0, true);
+        text_box.move_cursor(2, false);
+        assert_eq!(text_box.selection(), None);
+        assert!(!text_box.delete_selection());
+    }
+
+    #[test]
+    fn max_length_cuts_off_set_text_and_typing() {
+        let mut text_box = text_box("abcdef");
+        text_box.set_max_length(4);
+        assert_eq!(text_box.get_text(), "abcd");
+
+        text_box.insert("xyz");
+        assert_eq!(text_box.get_text(), "abcd");
+
+        // Replacing a selection frees up room first
+        text_box.move_cursor(2, true);
+        text_box.insert("xyz");
+        assert_eq!(text_box.get_text(), "abxy");
+    }
+
+    #[test]
+    fn the_cursor_counts_chars_not_bytes() {
+        let mut text_box = text_box("café");
+        assert_eq!(text_box.cursor, 4);
+        text_box.delete_char(false);
+        assert_eq!(text_box.get_text(), "caf");
+
+        text_box.insert("é!");
+        text_box.move_cursor(3, true);
+        assert_eq!(text_box.selected_text().as_deref(), Some("é!"));
+        text_box.move_cursor(10, false);
+        assert_eq!(text_box.cursor, 5);
+    }
+}
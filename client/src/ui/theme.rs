@@ -0,0 +1,237 @@
+// How everything looks - widgets are given a style role instead of colors, and the theme says what each role looks like
+// So restyling the client only means changing the theme
+
+use std::{collections::HashMap, f32::consts::FRAC_PI_2};
+
+use macroquad::{color::Color, math::vec2, shapes::{draw_line, draw_rectangle, draw_rectangle_lines, draw_triangle}, text::{draw_text_ex, load_ttf_font, measure_text, Font, TextDimensions, TextParams}};
+use serde::Deserialize;
+
+// How many straight pieces make up each rounded corner
+const CORNER_SEGMENTS: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum StyleRole {
+    /// Fills a whole view
+    Background,
+    /// A box sitting on the background (lists, sidebars)
+    Panel,
+    /// A section inside a panel
+    Section,
+    /// One entry in a list (a room, a player)
+    ListItem,
+    /// A list entry that needs to stand out (e.g. whose turn it is)
+    Highlight,
+    /// Only there for layout, draws nothing
+    Transparent,
+    /// The in-game table
+    Board,
+    /// Bars along the edge of a view
+    Toolbar,
+    PrimaryButton,
+    SecondaryButton,
+    DangerButton,
+    /// Text on panels and list items
+    Text,
+    /// Text on the darker parts of the screen (background, board, toolbars)
+    TextLight,
+    /// Text that needs to catch the eye
+    TextAccent,
+    Input,
+    Slider,
+    Checkbox,
+    /// Modal prompts and popups
+    Dialog,
+}
+
+#[derive(Clone)]
+pub struct Style {
+    pub background: Color,
+    pub hover: Color, // Background while the mouse is over it (only used by things that can be clicked)
+    pub pressed: Color, // Background while it is being clicked
+    pub text: Color,
+    pub border: Color,
+    pub border_width: f32, // 0 for no border
+    pub corner_radius: f32,
+}
+
+impl Style {
+    pub fn new(background: Color, text: Color) -> Self {
+        Self {
+            background,
+            hover: background,
+            pressed: background,
+            text,
+            border: text,
+            border_width: 0.0,
+            corner_radius: 0.0,
+        }
+    }
+
+    /// Give it hover and pressed colors, for anything that can be clicked
+    pub fn interactive(mut self, hover: Color, pressed: Color) -> Self {
+        self.hover = hover;
+        self.pressed = pressed;
+        self
+    }
+
+    pub fn border(mut self, color: Color, width: f32) -> Self {
+        self.border = color;
+        self.border_width = width;
+        self
+    }
+
+    pub fn rounded(mut self, radius: f32) -> Self {
+        self.corner_radius = radius;
+        self
+    }
+
+    /// Which background to use given what the mouse is doing
+    pub fn background_for(&self, hovered: bool, pressed: bool) -> Color {
+        match (hovered, pressed) {
+            (true, true) => self.pressed,
+            (true, false) => self.hover,
+            _ => self.background,
+        }
+    }
+}
+
+pub struct Theme {
+    styles: HashMap<StyleRole, Style>,
+    fallback: Style,
+    font: Option<Font>, // The default macroquad font is used if there isn't one
+}
+
+impl Theme {
+    pub fn new() -> Self {
+        let hex = Color::from_hex;
+        let clear = Color::new(0.0, 0.0, 0.0, 0.0);
+        let dark_text = hex(0x1e1e24);
+        let light_text = hex(0xf4f1ea);
+
+        let styles = HashMap::from([
+            (StyleRole::Background, Style::new(hex(0x2b3a42), light_text)),
+            (StyleRole::Panel, Style::new(hex(0xe8e2d4), dark_text).rounded(10.0)),
+            (StyleRole::Section, Style::new(hex(0xd6cfbe), dark_text).rounded(8.0)),
+            (StyleRole::ListItem, Style::new(hex(0xfaf8f3), dark_text).border(hex(0xbdb5a2), 1.0).rounded(8.0)),
+            (StyleRole::Highlight, Style::new(hex(0x6b4f2a), light_text).rounded(8.0)),
+            (StyleRole::Transparent, Style::new(clear, dark_text)),
+            (StyleRole::Board, Style::new(hex(0x2f5d45), light_text)),
+            (StyleRole::Toolbar, Style::new(hex(0x1f2a30), light_text)),
+            (StyleRole::PrimaryButton, Style::new(hex(0x5bb462), dark_text).interactive(hex(0x72c978), hex(0x46934c)).rounded(8.0)),
+            (StyleRole::SecondaryButton, Style::new(hex(0x8a96a3), dark_text).interactive(hex(0xa3aebb), hex(0x6f7a86)).rounded(8.0)),
+            (StyleRole::DangerButton, Style::new(hex(0xd0533f), light_text).interactive(hex(0xe06a56), hex(0xa84232)).rounded(8.0)),
+            (StyleRole::Text, Style::new(clear, dark_text)),
+            (StyleRole::TextLight, Style::new(clear, light_text)),
+            (StyleRole::TextAccent, Style::new(clear, hex(0xf2c14e))),
+            (StyleRole::Input, Style::new(hex(0xffffff), dark_text).border(hex(0x5d6b75), 2.0).rounded(4.0)),
+            (StyleRole::Slider, Style::new(hex(0x5d6b75), dark_text).interactive(hex(0x2b3a42), hex(0x2b3a42))),
+            (StyleRole::Checkbox, Style::new(hex(0xffffff), dark_text).border(hex(0x5d6b75), 2.0).rounded(3.0)),
+            (StyleRole::Dialog, Style::new(hex(0x37474f), light_text).border(light_text, 2.0).rounded(12.0)),
+        ]);
+
+        Self {
+            styles,
+            fallback: Style::new(Color::from_hex(0xff00ff), dark_text),
+            font: None,
+        }
+    }
+
+    pub fn style(&self, role: StyleRole) -> &Style {
+        self.styles.get(&role).unwrap_or(&self.fallback)
+    }
+
+    /// Use a ttf font for all text
+    pub async fn load_font(&mut self, path: &str) -> Result<(), String> {
+        let font = load_ttf_font(path).await.map_err(|e| format!("{}: {}", path, e))?;
+        self.font = Some(font);
+        Ok(())
+    }
+
+    pub fn measure_text(&self, text: &str, font_size: u16) -> TextDimensions {
+        measure_text(text, self.font.as_ref(), font_size, 1.0)
+    }
+
+    /// Same as macroquad's draw_text (y is the baseline) but in the theme's font
+    pub fn draw_text(&self, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+        draw_text_ex(text, x, y, TextParams {
+            font: self.font.as_ref(),
+            font_size,
+            color,
+            ..Default::default()
+        });
+    }
+
+    /// Draw the background and border of a style
+    pub fn draw_box(&self, x: f32, y: f32, w: f32, h: f32, style: &Style, background: Color) {
+        let radius = style.corner_radius.min(w / 2.0).min(h / 2.0);
+        if background.a > 0.0 {
+            draw_rounded_rectangle(x, y, w, h, radius, background);
+        }
+        if style.border_width > 0.0 {
+            draw_rounded_rectangle_lines(x, y, w, h, radius, style.border_width, style.border);
+        }
+    }
+
+    /// Outline round whichever widget has keyboard focus
+    pub fn draw_focus(&self, x: f32, y: f32, w: f32, h: f32, corner_radius: f32) {
+        let radius = if corner_radius > 0.0 { corner_radius + 3.0 } else { 0.0 };
+        draw_rounded_rectangle_lines(x - 3.0, y - 3.0, w + 6.0, h + 6.0, radius, 2.0, self.style(StyleRole::TextAccent).text);
+    }
+}
+
+// Corner centres, and the angle each corner's arc starts at, going clockwise from the top left
+fn corners(x: f32, y: f32, w: f32, h: f32, radius: f32) -> [(f32, f32, f32); 4] {
+    [
+        (x + radius, y + radius, 2.0 * FRAC_PI_2),
+        (x + w - radius, y + radius, 3.0 * FRAC_PI_2),
+        (x + w - radius, y + h - radius, 0.0),
+        (x + radius, y + h - radius, FRAC_PI_2),
+    ]
+}
+
+fn arc_point(cx: f32, cy: f32, radius: f32, angle: f32) -> (f32, f32) {
+    (cx + radius * angle.cos(), cy + radius * angle.sin())
+}
+
+fn draw_rounded_rectangle(x: f32, y: f32, w: f32, h: f32, radius: f32, color: Color) {
+    if radius <= 0.0 {
+        draw_rectangle(x, y, w, h, color);
+        return;
+    }
+
+    // A cross shape, then fill the corners in - nothing overlaps so see through colors stay even
+    draw_rectangle(x + radius, y, w - radius * 2.0, h, color);
+    draw_rectangle(x, y + radius, radius, h - radius * 2.0, color);
+    draw_rectangle(x + w - radius, y + radius, radius, h - radius * 2.0, color);
+    for (cx, cy, start) in corners(x, y, w, h, radius) {
+        for i in 0..CORNER_SEGMENTS {
+            let a = start + FRAC_PI_2 * i as f32 / CORNER_SEGMENTS as f32;
+            let b = start + FRAC_PI_2 * (i + 1) as f32 / CORNER_SEGMENTS as f32;
+            let (ax, ay) = arc_point(cx, cy, radius, a);
+            let (bx, by) = arc_point(cx, cy, radius, b);
+            draw_triangle(vec2(cx, cy), vec2(ax, ay), vec2(bx, by), color);
+        }
+    }
+}
+
+fn draw_rounded_rectangle_lines(x: f32, y: f32, w: f32, h: f32, radius: f32, thickness: f32, color: Color) {
+    if radius <= 0.0 {
+        draw_rectangle_lines(x, y, w, h, thickness, color);
+        return;
+    }
+
+    // Keep the line inside the box like draw_rectangle_lines does
+    let inset = thickness / 2.0;
+    let (x, y, w, h, radius) = (x + inset, y + inset, w - thickness, h - thickness, (radius - inset).max(0.0));
+    draw_line(x + radius, y, x + w - radius, y, thickness, color);
+    draw_line(x + radius, y + h, x + w - radius, y + h, thickness, color);
+    draw_line(x, y + radius, x, y + h - radius, thickness, color);
+    draw_line(x + w, y + radius, x + w, y + h - radius, thickness, color);
+    for (cx, cy, start) in corners(x, y, w, h, radius) {
+        for i in 0..CORNER_SEGMENTS {
+            let (ax, ay) = arc_point(cx, cy, radius, start + FRAC_PI_2 * i as f32 / CORNER_SEGMENTS as f32);
+            let (bx, by) = arc_point(cx, cy, radius, start + FRAC_PI_2 * (i + 1) as f32 / CORNER_SEGMENTS as f32);
+            draw_line(ax, ay, bx, by, thickness, color);
+        }
+    }
+}
@@ -1,65 +1,56 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{ui::{self, button::Button, container::Container, label::Label, Alignment, Position, Size, UIMessage}, views::MenuState};
-
-use macroquad::prelude::*;
+use crate::ui::{button::Button, container::Container, label::Label, layout_file::LayoutFile, theme::StyleRole, Alignment, Position, Size, UIMessage};
 
 pub struct ConnectionError {
     pub container: Rc<RefCell<Container>>,
+    message: Rc<RefCell<Label>>,
+    retry_container: Rc<RefCell<Container>>,
+    shown: Option<(String, Option<UIMessage>)>, // The error and retry being shown, so the widgets are only changed when they do
 }
 
 impl ConnectionError {
-    pub fn new(error: String) -> Self {
-        let mut root = Container::new(
-            Position::Align(Alignment::LeTop), 
-            Position::Align(Alignment::LeTop), 
-            Size::Rel(1.0), 
-            Size::Rel(1.0),
-            BEIGE,
-            ui::Layout::None,
-            Size::Rel(0.1),
-        );
-
-        // let error_btn = Button::new(
-        //     Position::Align(Alignment::Centre), 
-        //     Position::Align(Alignment::Centre), 
-        //     Size::Abs(400.0), 
-        //     Size::Abs(100.0), 
-        //     RED, 
-        //     BLACK, 
-        //     "Retry Connection".to_string(), 
-        //     32, 
-        //     Some(UIMessage::TryConnectToMatchmaking),
-        // );
-        let error_msg = Label::new(
-                Position::Align(Alignment::Centre),
-                Position::Align(Alignment::Centre),
-                32, 
-                error, 
-                BLACK
-            );
-
-        let back_btn = Button::new(
-            Position::Align(Alignment::LeTop), 
-            Position::Align(Alignment::LeTop), 
-            Size::Abs(300.0), 
-            Size::Abs(100.0), 
-            LIME, 
-            BLACK, 
-            "Back to Main menu".to_string(), 
-            32, 
-            Some(UIMessage::SwitchView(MenuState::MainMenu)),
-        );
+    pub const LAYOUT: LayoutFile = LayoutFile {
+        path: "res/layouts/connection_error.json",
+        built_in: include_str!("../../../res/layouts/connection_error.json"),
+    };
 
-        
+    pub fn new() -> Self {
+        Self::load().expect("Couldn't load the connection error layout")
+    }
 
-        root.add_child(Box::new(back_btn));
-        root.add_child(Box::new(error_msg));
-        // root.add_child(Box::new(error_btn));
+    /// Build the view from its layout file
+    pub fn load() -> Result<Self, String> {
+        let (container, widgets) = Self::LAYOUT.build()?;
+        Ok(Self {
+            container,
+            message: widgets.label("message")?,
+            retry_container: widgets.container("retry")?,
+            shown: None,
+        })
+    }
 
-        Self {
-            container: Rc::new(RefCell::new(root))
+    /// Show the error, with a button to try again if there is something to retry
+    pub fn set_error(&mut self, error: &str, retry: &Option<UIMessage>) {
+        if self.shown.as_ref().is_some_and(|(shown_error, shown_retry)| shown_error == error && shown_retry == retry) {
+            return;
         }
+        self.shown = Some((error.to_string(), retry.clone()));
+        self.message.borrow_mut().set_text(error.to_string());
 
+        let mut retry_container = self.retry_container.borrow_mut();
+        retry_container.clear_children();
+        if retry.is_some() {
+            retry_container.add_child(Box::new(Button::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::Centre),
+                Size::Abs(400.0),
+                Size::Abs(100.0),
+                StyleRole::PrimaryButton,
+                "Retry Connection".to_string(),
+                32,
+                retry.clone(),
+            )));
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,43 +1,67 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{ui::{self, button::Button, container::Container, Alignment, Position, Size, UIMessage}, views::MenuState};
-
-use macroquad::prelude::*;
-
+use crate::ui::{button::Button, container::Container, label::Label, layout_file::LayoutFile, scroll_container::ScrollContainer, textbox::TextBox, theme::StyleRole, Alignment, Position, Size, UIMessage};
 
+/// Joining a room server straight from its address, without going through matchmaking
 pub struct DirectConnect {
     pub container: Rc<RefCell<Container>>,
+    pub address_text_box: Rc<RefCell<TextBox>>,
+    pub player_name_text_box: Rc<RefCell<TextBox>>,
+    recent_servers: Rc<RefCell<ScrollContainer>>,
 }
 
 impl DirectConnect {
-    pub fn new() -> Self {
-        let mut root = Container::new(
-            Position::Align(Alignment::LeTop), 
-            Position::Align(Alignment::LeTop), 
-            Size::Rel(1.0), 
-            Size::Rel(1.0),
-            RED,
-            ui::Layout::None,
-            Size::Rel(0.1),
-        );
-
-        let back_btn = Button::new(
-            Position::Align(Alignment::LeTop), 
-            Position::Align(Alignment::LeTop), 
-            Size::Abs(300.0), 
-            Size::Abs(100.0), 
-            LIME, 
-            BLACK, 
-            "Back to Main menu".to_string(), 
-            32, 
-            Some(UIMessage::SwitchView(MenuState::MainMenu)),
-        );
+    pub const LAYOUT: LayoutFile = LayoutFile {
+        path: "res/layouts/direct_connect.json",
+        built_in: include_str!("../../../res/layouts/direct_connect.json"),
+    };
 
-        root.add_child(Box::new(back_btn));
+    pub fn new(recent_servers: &[String]) -> Self {
+        Self::load(recent_servers).expect("Couldn't load the direct connect layout")
+    }
 
-        Self {
-            container: Rc::new(RefCell::new(root))
+    /// Build the view from its layout file, starting with the last server connected to in the address box
+    pub fn load(recent_servers: &[String]) -> Result<Self, String> {
+        let (container, widgets) = Self::LAYOUT.build()?;
+        let view = Self {
+            container,
+            address_text_box: widgets.text_box("address")?,
+            player_name_text_box: widgets.text_box("player_name")?,
+            recent_servers: widgets.scroll_container("recent_servers")?,
+        };
+        if let Some(last) = recent_servers.first() {
+            view.address_text_box.borrow_mut().set_text(last.clone());
         }
+        view.set_recent_servers(recent_servers);
+        Ok(view)
+    }
 
+    /// List the servers, clicking one connects to it again
+    pub fn set_recent_servers(&self, servers: &[String]) {
+        let mut list = self.recent_servers.borrow_mut();
+        list.clear_children();
+        list.scroll_to_top();
+
+        if servers.is_empty() {
+            list.add_child(Box::new(Label::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::LeTop),
+                20,
+                "Servers you connect to will show up here".to_string(),
+                StyleRole::Text
+            )));
+        }
+        for server in servers {
+            list.add_child(Box::new(Button::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::LeTop),
+                Size::Rel(0.95),
+                Size::Abs(35.0),
+                StyleRole::SecondaryButton,
+                server.clone(),
+                20,
+                Some(UIMessage::ConnectToServer(server.clone())),
+            )));
+        }
     }
-}
\ No newline at end of file
+}
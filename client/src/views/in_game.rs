@@ -0,0 +1,410 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{textures::{CardTexture, TextureCache}, ui::{self, button::Button, cardui::CardUi, choice_prompt::{ChoiceItem, ChoicePrompt}, drag::DropTarget, container::{Container, RefCellContainerWrapper}, label::Label, theme::StyleRole, Alignment, Padding, Position, Size, UIContext, UIElement, UIMessage}, views::MenuState};
+
+use common::game::{choice::{ChoiceOption, ChoiceRequest}, game_state::Phase, game_view::{CardView, GameView, PlayerView}};
+use macroquad::prelude::*;
+
+// Where the decks (and the card images in them) are, relative to where the client is run from
+const DECK_DIR: &str = "res/decks";
+
+
+pub struct InGame {
+    pub container: Rc<RefCell<Container>>,
+    pub status_container: Rc<RefCell<Container>>, // Round, phase, whose turn it is and the pile sizes
+    pub players_container: Rc<RefCell<Container>>, // What every player has in play
+    pub hand_container: Rc<RefCell<Container>>, // Our own hand
+    pub prompt: Option<ChoicePrompt>, // Something the server is waiting on us to pick
+}
+
+impl InGame {
+    pub fn new() -> Self {
+        let mut root = Container::new(
+            Position::Align(Alignment::LeTop),
+            Position::Align(Alignment::LeTop),
+            Size::Rel(1.0),
+            Size::Rel(1.0),
+            StyleRole::Board,
+            ui::Layout::None,
+            Size::Rel(0.1),
+        );
+
+        let status_container = Rc::new(RefCell::new(Container::new(
+            Position::Align(Alignment::LeTop),
+            Position::Align(Alignment::LeTop),
+            Size::Rel(1.0),
+            Size::Abs(40.0),
+            StyleRole::Toolbar,
+            ui::Layout::RowCentre,
+            Size::Abs(20.0),
+        )));
+
+        let mut players_container = Container::new(
+            Position::Align(Alignment::Centre),
+            Position::Abs(45.0),
+            Size::Rel(0.98),
+            Size::Rel(0.62),
+            StyleRole::Transparent,
+            ui::Layout::ColumnTop,
+            Size::Abs(5.0),
+        );
+        // Cards dropped anywhere on the table that isn't a character
+        players_container.set_drop_target(DropTarget::PlayArea);
+        let players_container = Rc::new(RefCell::new(players_container));
+
+        let hand_container = Rc::new(RefCell::new(Container::new(
+            Position::Align(Alignment::Centre),
+            Position::Align(Alignment::RiBot),
+            Size::Rel(1.0),
+            Size::Rel(0.28),
+            StyleRole::Toolbar,
+            ui::Layout::RowCentre,
+            Size::Abs(10.0),
+        )));
+
+        root.add_child(Box::new(RefCellContainerWrapper(Rc::clone(&status_container))));
+        root.add_child(Box::new(RefCellContainerWrapper(Rc::clone(&players_container))));
+        root.add_child(Box::new(RefCellContainerWrapper(Rc::clone(&hand_container))));
+
+        Self {
+            container: Rc::new(RefCell::new(root)),
+            status_container,
+            players_container,
+            hand_container,
+            prompt: None,
+        }
+    }
+
+    pub fn draw(&mut self, ctx: &mut UIContext) {
+        let Some(prompt) = &mut self.prompt else {
+            self.container.borrow_mut().draw(ctx, 0.0, 0.0, screen_width(), screen_height());
+            return;
+        };
+
+        // The board can't be used while a choice is open - it is still drawn with the real context so it looks the same,
+        // but anything done to it gets thrown away and only the prompt can take the keyboard focus
+        let queued = ctx.message_queue.len();
+        ctx.focus.set_blocked(true);
+        self.container.borrow_mut().draw(ctx, 0.0, 0.0, screen_width(), screen_height());
+        ctx.focus.set_blocked(false);
+        ctx.message_queue.truncate(queued);
+        ctx.drag = None;
+        ctx.drop_target = None;
+        ctx.hovered_card = None;
+        prompt.draw(ctx, 0.0, 0.0, screen_width(), screen_height());
+    }
+
+    /// Open a prompt for something the server wants us to pick, replacing any that was already open
+    pub fn show_choice(&mut self, request: &ChoiceRequest, deck: &str, textures: &mut TextureCache) {
+        let mut cards = CardImages { deck, textures };
+        let items = request.options.iter().map(|option| ChoiceItem {
+            id: option.id(),
+            label: match option {
+                ChoiceOption::Card(card) => card.name.clone(),
+                ChoiceOption::Player { name, .. } => name.clone(),
+                ChoiceOption::Text { label, .. } => label.clone(),
+            },
+            img: match option {
+                ChoiceOption::Card(card) => Some(cards.texture(card)),
+                _ => None,
+            },
+        }).collect();
+
+        self.prompt = Some(ChoicePrompt::new(request.choice_id, request.title.clone(), items, request.min, request.max, request.timeout_secs));
+    }
+
+    /// Close the prompt once it has been answered
+    pub fn close_choice(&mut self, choice_id: uuid::Uuid) {
+        if self.prompt.as_ref().is_some_and(|prompt| prompt.choice_id() == choice_id) {
+            self.prompt = None;
+        }
+    }
+
+    /// Rebuild the board from the latest game view the server sent
+    /// Card images that haven't loaded yet show as placeholders and fill in on their own
+    pub fn update_game(&mut self, view: &GameView, deck: &str, textures: &mut TextureCache) {
+        let mut cards = CardImages { deck, textures };
+        self.update_status(view);
+        self.update_players(view, &mut cards);
+        self.update_hand(view, &mut cards);
+    }
+
+    fn update_status(&mut self, view: &GameView) {
+        let mut status = self.status_container.borrow_mut();
+        status.clear_children();
+
+        status.add_child(Box::new(Button::new(
+            Position::Align(Alignment::Centre),
+            Position::Align(Alignment::Centre),
+            Size::Abs(80.0),
+            Size::Abs(30.0),
+            StyleRole::DangerButton,
+            "Leave".to_string(),
+            20,
+            Some(UIMessage::SwitchView(MenuState::MainMenu)),
+        )));
+
+        let phase = match view.phase {
+            Phase::Round => "Round",
+            Phase::Battle => "Battle",
+            Phase::GameOver => "Game over",
+        };
+        let turn = if view.is_my_turn() {
+            "Your turn".to_string()
+        } else {
+            match view.current_player.and_then(|id| view.get_player(&id)) {
+                Some(player) => format!("{}'s turn", player.name),
+                None if view.phase == Phase::GameOver => "No more turns".to_string(),
+                None => "Waiting".to_string(),
+            }
+        };
+        let info = [
+            (format!("Round {} - {}", view.round, phase), StyleRole::TextLight),
+            (turn, if view.is_my_turn() { StyleRole::TextAccent } else { StyleRole::TextLight }),
+            (format!("Draw: {}", view.draw_pile_size), StyleRole::TextLight),
+            (format!("Characters: {}", view.character_pile_size), StyleRole::TextLight),
+            (format!("Discard: {}", view.discard_pile_size), StyleRole::TextLight),
+        ];
+        for (text, style) in info {
+            status.add_child(Box::new(Label::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::Centre),
+                24,
+                text,
+                style
+            )));
+        }
+
+        // Only worth showing when it would do something
+        if view.is_my_turn() {
+            status.add_child(Box::new(Button::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::Centre),
+                Size::Abs(100.0),
+                Size::Abs(30.0),
+                StyleRole::PrimaryButton,
+                "End turn".to_string(),
+                20,
+                Some(UIMessage::EndTurn),
+            )));
+        }
+    }
+
+    fn update_players(&mut self, view: &GameView, cards: &mut CardImages) {
+        let mut players = self.players_container.borrow_mut();
+        players.clear_children();
+
+        for player in &view.players {
+            players.add_child(Box::new(player_row(view, player, cards)));
+        }
+    }
+
+    fn update_hand(&mut self, view: &GameView, cards: &mut CardImages) {
+        let mut hand = self.hand_container.borrow_mut();
+        hand.clear_children();
+
+        // Cards can only be played on our turn, the server will say no otherwise anyway
+        // Clicking plays them wherever they go, dragging puts them on something
+        for card in &view.hand {
+            let on_click = view.is_my_turn().then_some(UIMessage::PlayCard(card.instance_id));
+            let mut card_ui = cards.card(card, 100.0, on_click);
+            if view.is_my_turn() {
+                card_ui.set_draggable(card.instance_id);
+            }
+            hand.add_child(Box::new(card_ui));
+        }
+    }
+}
+
+
+/// A players name and hand count, then their characters (with whatever is on them) and any other cards in play
+fn player_row(view: &GameView, player: &PlayerView, cards: &mut CardImages) -> Container {
+    let is_current = view.current_player == Some(player.player_id);
+    let mut row = Container::new(
+        Position::Align(Alignment::Centre),
+        Position::Align(Alignment::LeTop),
+        Size::Rel(1.0),
+        Size::Abs(125.0),
+        if is_current { StyleRole::Highlight } else { StyleRole::Toolbar },
+        ui::Layout::RowCentre,
+        Size::Abs(10.0),
+    );
+    row.add_padding(Padding::uniform(5.0));
+
+    let mut name = player.name.clone();
+    if player.player_id == view.player_id {
+        name.push_str(" (you)");
+    }
+    if !player.is_connected {
+        name.push_str(" (disconnected)");
+    }
+    let mut name_container = Container::new(
+        Position::Align(Alignment::LeTop),
+        Position::Align(Alignment::Centre),
+        Size::Abs(150.0),
+        Size::Abs(60.0),
+        StyleRole::Transparent,
+        ui::Layout::ColumnCentre,
+        Size::Abs(5.0),
+    );
+    name_container.add_child(Box::new(Label::new(
+        Position::Align(Alignment::LeTop),
+        Position::Align(Alignment::Centre),
+        24,
+        name,
+        if is_current { StyleRole::TextAccent } else { StyleRole::TextLight }
+    )));
+    name_container.add_child(Box::new(Label::new(
+        Position::Align(Alignment::LeTop),
+        Position::Align(Alignment::Centre),
+        18,
+        format!("{} cards in hand", player.hand_count),
+        StyleRole::TextLight
+    )));
+    row.add_child(Box::new(name_container));
+
+    let (characters, loose) = group_table(&player.table);
+    for (character, attached) in characters {
+        let mut stack = Container::new(
+            Position::Align(Alignment::Centre),
+            Position::Align(Alignment::LeTop),
+            Size::Abs(90.0),
+            Size::Abs(115.0),
+            StyleRole::Transparent,
+            ui::Layout::ColumnTop,
+            Size::Abs(2.0),
+        );
+        // Cards can be played onto anyone's characters, the server decides what the card actually does there
+        stack.set_drop_target(DropTarget::Character { player_id: player.player_id, instance_id: character.instance_id });
+        stack.add_child(Box::new(cards.card(character, 60.0, None)));
+        for card in attached {
+            stack.add_child(Box::new(Label::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::LeTop),
+                14,
+                format!("+ {}", card.name),
+                StyleRole::TextLight
+            )));
+        }
+        row.add_child(Box::new(stack));
+    }
+    for card in loose {
+        row.add_child(Box::new(cards.card(card, 60.0, None)));
+    }
+
+    row
+}
+
+// Where the board gets its card images from
+struct CardImages<'a> {
+    deck: &'a str,
+    textures: &'a mut TextureCache,
+}
+
+impl CardImages<'_> {
+    fn texture(&mut self, card: &CardView) -> CardTexture {
+        self.textures.get(self.deck, &card.card_id, &format!("{}/{}/{}", DECK_DIR, self.deck, card.img_path))
+    }
+
+    /// A card at the given width, keeping the 160x224 shape
+    fn card(&mut self, card: &CardView, w: f32, on_click: Option<UIMessage>) -> CardUi {
+        let texture = self.texture(card);
+        let mut card_ui = CardUi::new(
+            Position::Align(Alignment::Centre),
+            Position::Align(Alignment::Centre),
+            texture,
+            on_click,
+        );
+        card_ui.set_size(Size::Abs(w), Size::Abs(w * 1.4));
+        card_ui.set_description(card.name.clone(), card.description.clone());
+        card_ui
+    }
+}
+
+
+// The server keeps each table in the order cards were played, so anything that goes on a character
+// (addons, weapons and supers) belongs to the last character played before it
+// Returns each character with what is on it, then everything else
+fn group_table(table: &[CardView]) -> (Vec<(&CardView, Vec<&CardView>)>, Vec<&CardView>) {
+    let mut characters: Vec<(&CardView, Vec<&CardView>)> = vec![];
+    let mut loose = vec![];
+
+    for card in table {
+        match card.card_type.as_str() {
+            "Character" => characters.push((card, vec![])),
+            "Addon" | "Weapon" | "SuperCharacter" => match characters.last_mut() {
+                Some((_, attached)) => attached.push(card),
+                None => loose.push(card),
+            },
+            _ => loose.push(card),
+        }
+    }
+
+    (characters, loose)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn card(card_id: &str, card_type: &str) -> CardView {
+        CardView {
+            instance_id: Uuid::new_v4(),
+            card_id: card_id.to_string(),
+            card_type: card_type.to_string(),
+            name: card_id.to_string(),
+            description: String::new(),
+            img_path: format!("{}.png", card_id),
+            damage: 0,
+            synergy_card_id: None,
+            synergy_damage: 0,
+        }
+    }
+
+    fn ids(cards: &[&CardView]) -> Vec<String> {
+        cards.iter().map(|card| card.card_id.clone()).collect()
+    }
+
+    #[test]
+    fn cards_go_with_the_character_played_before_them() {
+        let table = [
+            card("knight", "Character"),
+            card("booster", "Addon"),
+            card("sword", "Weapon"),
+            card("dog", "Character"),
+            card("super_dog", "SuperCharacter"),
+        ];
+        let (characters, loose) = group_table(&table);
+
+        assert_eq!(characters.len(), 2);
+        assert_eq!(characters[0].0.card_id, "knight");
+        assert_eq!(ids(&characters[0].1), vec!["booster", "sword"]);
+        assert_eq!(characters[1].0.card_id, "dog");
+        assert_eq!(ids(&characters[1].1), vec!["super_dog"]);
+        assert!(loose.is_empty());
+    }
+
+    #[test]
+    fn cards_with_no_character_to_go_on_are_loose() {
+        let table = [
+            card("booster", "Addon"),
+            card("knight", "Character"),
+            card("health_potion", "Item"),
+        ];
+        let (characters, loose) = group_table(&table);
+
+        assert_eq!(characters.len(), 1);
+        assert!(characters[0].1.is_empty());
+        assert_eq!(ids(&loose), vec!["booster", "health_potion"]);
+    }
+
+    #[test]
+    fn an_empty_table_has_nothing() {
+        let (characters, loose) = group_table(&[]);
+        assert!(characters.is_empty());
+        assert!(loose.is_empty());
+    }
+}
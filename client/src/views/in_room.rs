@@ -1,67 +1,201 @@
 use std::{cell::{RefCell}, rc::Rc};
 
-use crate::{ui::{self, button::Button, container::{Container, RefCellContainerWrapper}, label::Label, Alignment, Position, Size, UIMessage}, views::MenuState};
+use crate::ui::{self, button::Button, container::Container, label::Label, layout_file::LayoutFile, scroll_container::ScrollContainer, theme::StyleRole, Alignment, Padding, Position, Size, UIMessage};
+
+use common::server::lobby_info::LobbyInfo;
+use uuid::Uuid;
 
-use macroquad::prelude::*;
 
 
 pub struct InRoom {
     pub container: Rc<RefCell<Container>>,
-    pub player_container: Rc<RefCell<Container>>, // For the players that are currently in the room
+    pub player_container: Rc<RefCell<ScrollContainer>>, // For the players that are currently in the room
+    pub controls_container: Rc<RefCell<Container>>, // Ready / deck / start buttons
+    lobby: Option<(LobbyInfo, Uuid)>, // The last lobby shown and who we are in it
 }
 
 impl InRoom {
+    pub const LAYOUT: LayoutFile = LayoutFile {
+        path: "res/layouts/in_room.json",
+        built_in: include_str!("../../../res/layouts/in_room.json"),
+    };
+
     pub fn new() -> Self {
-        let mut root = Container::new(
-            Position::Align(Alignment::LeTop), 
-            Position::Align(Alignment::LeTop), 
-            Size::Rel(1.0), 
-            Size::Rel(1.0),
-            PURPLE,
-            ui::Layout::None,
-            Size::Rel(0.1),
-        );
+        Self::load().expect("Couldn't load the room layout")
+    }
 
+    /// Build the view from its layout file, it stays empty until the first lobby update
+    pub fn load() -> Result<Self, String> {
+        let (container, widgets) = Self::LAYOUT.build()?;
+        Ok(Self {
+            container,
+            player_container: widgets.scroll_container("players")?,
+            controls_container: widgets.container("controls")?,
+            lobby: None,
+        })
+    }
 
-        let back_btn = Button::new(
-            Position::Align(Alignment::LeTop),
+    /// Build the view again from its layout file, keeping the lobby it was showing
+    pub fn reload(&mut self) -> Result<(), String> {
+        let lobby = self.lobby.take();
+        *self = Self::load()?;
+        if let Some((lobby, own_id)) = lobby {
+            self.update_lobby(&lobby, own_id);
+        }
+        Ok(())
+    }
+
+    /// Rebuild the player list and lobby controls from the latest lobby info
+    pub fn update_lobby(&mut self, lobby: &LobbyInfo, own_id: Uuid) {
+        self.lobby = Some((lobby.clone(), own_id));
+        let is_host = lobby.host_id == Some(own_id);
+
+        let mut player_container = self.player_container.borrow_mut();
+        player_container.clear_children();
+
+        let player_container_label = Label::new(
+            Position::Align(Alignment::Centre),
             Position::Align(Alignment::LeTop),
-            Size::Rel(0.3),
-            Size::Rel(0.1),
-            LIME,
-            BLACK,
-            "Back to Main menu".to_string(),
-            24,
-            Some(UIMessage::SwitchView(MenuState::MainMenu)),
+            32,
+            "Players".to_string(),
+            StyleRole::Text
         );
+        player_container.add_child(Box::new(player_container_label));
+
+        for player in &lobby.players {
+            let mut row = Container::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::LeTop),
+                Size::Rel(0.95),
+                Size::Abs(40.0),
+                if player.is_ready { StyleRole::Highlight } else { StyleRole::ListItem },
+                ui::Layout::None,
+                Size::Abs(5.0),
+            );
+            row.add_padding(Padding::new(0.0, 0.0, 10.0, 5.0));
+
+            let mut name = player.player_name.clone();
+            if Some(player.player_id) == lobby.host_id {
+                name.push_str(" (host)");
+            }
+            if player.player_id == own_id {
+                name.push_str(" (self)");
+            }
+            if !player.is_connected {
+                name.push_str(" (disconnected)");
+            }
+            let name_label = Label::new(
+                Position::Align(Alignment::LeTop),
+                Position::Align(Alignment::Centre),
+                24,
+                name,
+                if player.is_ready { StyleRole::TextLight } else { StyleRole::Text }
+            );
+            row.add_child(Box::new(name_label));
+
+            // The host gets buttons to manage everyone else
+            if is_host && player.player_id != own_id {
+                let mut host_buttons = Container::new(
+                    Position::Align(Alignment::RiBot),
+                    Position::Align(Alignment::Centre),
+                    Size::Abs(if player.is_bot { 70.0 } else { 175.0 }),
+                    Size::Abs(30.0),
+                    StyleRole::Transparent,
+                    ui::Layout::RowCentre,
+                    Size::Abs(5.0),
+                );
+                host_buttons.add_child(Box::new(Button::new(
+                    Position::Align(Alignment::Centre),
+                    Position::Align(Alignment::Centre),
+                    Size::Abs(70.0),
+                    Size::Abs(30.0),
+                    StyleRole::DangerButton,
+                    "Kick".to_string(),
+                    20,
+                    Some(UIMessage::KickPlayer(player.player_id)),
+                )));
+                if !player.is_bot {
+                    host_buttons.add_child(Box::new(Button::new(
+                        Position::Align(Alignment::Centre),
+                        Position::Align(Alignment::Centre),
+                        Size::Abs(100.0),
+                        Size::Abs(30.0),
+                        StyleRole::SecondaryButton,
+                        "Make host".to_string(),
+                        20,
+                        Some(UIMessage::TransferHost(player.player_id)),
+                    )));
+                }
+                row.add_child(Box::new(host_buttons));
+            }
 
-        let player_container = Rc::new(RefCell::new(Container::new(
-            Position::Rel(0.025),
+            player_container.add_child(Box::new(row));
+        }
+
+        let mut controls = self.controls_container.borrow_mut();
+        controls.clear_children();
+
+        let is_ready = lobby.players.iter().any(|p| p.player_id == own_id && p.is_ready);
+        controls.add_child(Box::new(Button::new(
+            Position::Align(Alignment::Centre),
             Position::Align(Alignment::Centre),
-            Size::Rel(0.3),
-            Size::Rel(0.7),
-            BEIGE,
-            ui::Layout::ColumnTop,
-            Size::Rel(0.05),
+            Size::Rel(0.8),
+            Size::Abs(50.0),
+            if is_ready { StyleRole::PrimaryButton } else { StyleRole::SecondaryButton },
+            if is_ready { "Ready!".to_string() } else { "Ready up".to_string() },
+            24,
+            Some(UIMessage::SetReady(!is_ready)),
         )));
 
-        let player_container_label = Label::new(
+        // Share this with friends so they can join a private room
+        controls.add_child(Box::new(Label::new(
             Position::Align(Alignment::Centre),
-            Position::Align(Alignment::LeTop), 
-            32, 
-            "Players".to_string(), 
-            BLACK
-        );
+            Position::Align(Alignment::Centre),
+            24,
+            format!("Room code: {}", lobby.room_code),
+            StyleRole::Text
+        )));
 
+        controls.add_child(Box::new(Label::new(
+            Position::Align(Alignment::Centre),
+            Position::Align(Alignment::Centre),
+            24,
+            format!("Deck: {}", lobby.deck),
+            StyleRole::Text
+        )));
 
-        player_container.borrow_mut().add_child(Box::new(player_container_label));
-        root.add_child(Box::new(RefCellContainerWrapper(Rc::clone(&player_container))));
-        root.add_child(Box::new(back_btn));
+        if is_host {
+            for deck in &lobby.available_decks {
+                controls.add_child(Box::new(Button::new(
+                    Position::Align(Alignment::Centre),
+                    Position::Align(Alignment::Centre),
+                    Size::Rel(0.7),
+                    Size::Abs(35.0),
+                    if *deck == lobby.deck { StyleRole::PrimaryButton } else { StyleRole::SecondaryButton },
+                    deck.clone(),
+                    20,
+                    Some(UIMessage::SelectDeck(deck.clone())),
+                )));
+            }
 
-        Self {
-            container: Rc::new(RefCell::new(root)),
-            player_container,
+            controls.add_child(Box::new(Button::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::Centre),
+                Size::Rel(0.8),
+                Size::Abs(50.0),
+                StyleRole::PrimaryButton,
+                "Start Game".to_string(),
+                32,
+                Some(UIMessage::StartGame),
+            )));
+        } else {
+            controls.add_child(Box::new(Label::new(
+                Position::Align(Alignment::Centre),
+                Position::Align(Alignment::Centre),
+                20,
+                "Waiting for the host to start".to_string(),
+                StyleRole::Text
+            )));
         }
-
     }
-}
\ No newline at end of file
+}
@@ -1,57 +1,24 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{ui::{self, button::Button, container::Container, Alignment, Position, Size, UIMessage}, views::MenuState};
-
-use macroquad::prelude::*;
+use crate::ui::{container::Container, layout_file::LayoutFile};
 
 pub struct MainMenu {
     pub container: Rc<RefCell<Container>>,
 }
 
 impl MainMenu {
-    pub fn new() -> Self {
-        let mut root = Container::new(
-            Position::Align(Alignment::LeTop), 
-            Position::Align(Alignment::LeTop), 
-            Size::Rel(1.0), 
-            Size::Rel(1.0),
-            GREEN,
-            ui::Layout::ColumnCentre,
-            Size::Rel(0.05),
-        );
-
-        let room_browser_btn = Button::new(
-            Position::Align(Alignment::Centre), 
-            Position::Align(Alignment::Centre), 
-            Size::Abs(400.0), 
-            Size::Abs(100.0), 
-            LIME, 
-            BLACK, 
-            "Room Browser".to_string(), 
-            32, 
-            Some(UIMessage::TryConnectToMatchmaking),
-        );
-
-        let direct_connect_btn = Button::new(
-            Position::Align(Alignment::Centre), 
-            Position::Align(Alignment::Centre), 
-            Size::Abs(400.0), 
-            Size::Abs(100.0), 
-            LIME, 
-            BLACK, 
-            "Direct Connect".to_string(), 
-            32, 
-            Some(UIMessage::SwitchView(MenuState::DirectConnect)),
-        );
-
-        root.add_child(Box::new(room_browser_btn));
-        root.add_child(Box::new(direct_connect_btn));
+    pub const LAYOUT: LayoutFile = LayoutFile {
+        path: "res/layouts/main_menu.json",
+        built_in: include_str!("../../../res/layouts/main_menu.json"),
+    };
 
+    pub fn new() -> Self {
+        Self::load().expect("Couldn't load the main menu layout")
+    }
 
-
-        Self {
-            container: Rc::new(RefCell::new(root))
-        }
-
+    /// Build the view from its layout file
+    pub fn load() -> Result<Self, String> {
+        let (container, _) = Self::LAYOUT.build()?;
+        Ok(Self { container })
     }
-}
\ No newline at end of file
+}
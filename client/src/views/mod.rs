@@ -1,17 +1,23 @@
+use serde::Deserialize;
+
 pub mod main_menu;
 pub mod room_browser;
 pub mod direct_connect;
 pub mod connection_error;
 pub mod in_room;
+pub mod in_game;
+pub mod settings;
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Deserialize)]
 pub enum MenuState {
     MainMenu,
     RoomBrowser,
     DirectConnect,
+    Connecting(String), // Connected to a room server and waiting for it to give us a seat
     InGame,
     ConnectionError(String),
     InRoom,
+    Settings,
 }
 
 
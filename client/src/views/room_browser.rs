@@ -1,279 +1,125 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{ui::{self, button::Button, checkbox::{Checkbox, CheckboxWrapper}, container::{Container, RefCellContainerWrapper}, label::Label, slider::{Slider, SliderWrapper}, textbox::{TextBox, TextBoxWrapper}, Alignment, Padding, Position, Size, UIMessage}, views::MenuState};
+use crate::ui::{self, button::Button, checkbox::Checkbox, container::Container, label::{Label, LabelWrapper}, layout_file::LayoutFile, scroll_container::ScrollContainer, slider::Slider, textbox::TextBox, theme::StyleRole, Alignment, Padding, Position, Size, UIMessage};
 
 use common::server::room_info::RoomInfo;
-use macroquad::prelude::*;
+use uuid::Uuid;
 
 
 pub struct RoomBrowser {
     pub container: Rc<RefCell<Container>>, // For draw() and input
-    pub room_container: Rc<RefCell<Container>>, // For modifying the room list
+    pub room_container: Rc<RefCell<ScrollContainer>>, // For modifying the room list
     pub room_name_text_box: Rc<RefCell<TextBox>>,
     pub max_player_slider: Rc<RefCell<Slider>>,
     pub private_checkbox: Rc<RefCell<Checkbox>>,
     pub player_name_text_box: Rc<RefCell<TextBox>>,
+    pub room_code_text_box: Rc<RefCell<TextBox>>,
+    room_rows: Vec<(Uuid, Rc<RefCell<Label>>)>, // Each listed room and its player count, in the order they are shown
 }
 
 impl RoomBrowser {
-    pub fn new() -> Self {
-        let root =  Rc::new(RefCell::new(Container::new(
-            Position::Align(Alignment::LeTop),
-            Position::Align(Alignment::LeTop),
-            Size::Rel(1.0),
-            Size::Rel(1.0),
-            BLUE,
-            ui::Layout::None,
-            Size::Rel(0.1),
-        )));
+    pub const LAYOUT: LayoutFile = LayoutFile {
+        path: "res/layouts/room_browser.json",
+        built_in: include_str!("../../../res/layouts/room_browser.json"),
+    };
 
-        let room_container = Rc::new(RefCell::new(Container::new(
-            Position::Rel(0.3),
-            Position::Align(Alignment::Centre),
-            Size::Rel(0.6),
-            Size::Rel(0.8),
-            BEIGE,
-            ui::Layout::ColumnCentre,
-            Size::Rel(0.05),
-        )));
-
-        let back_btn = Button::new(
-            Position::Align(Alignment::LeTop),
-            Position::Align(Alignment::LeTop),
-            Size::Rel(0.3),
-            Size::Rel(0.1),
-            LIME,
-            BLACK,
-            "Back to Main menu".to_string(),
-            24,
-            Some(UIMessage::SwitchView(MenuState::MainMenu)),
-        );
-
-        let mut left_bar = Container::new(
-            Position::Align(Alignment::LeTop), 
-            Position::Align(Alignment::Centre), 
-            Size::Rel(0.3), 
-            Size::Rel(0.8),
-            PURPLE,
-            ui::Layout::None,
-            Size::Rel(0.05),
-        );
-
-        let mut player_fields = Container::new(
-            Position::Align(Alignment::Centre), 
-            Position::Rel(0.05), 
-            Size::Rel(0.95), 
-            Size::Rel(0.2),
-            RED,
-            ui::Layout::ColumnCentre,
-            Size::Abs(10.0),
-        );
-
-        let player_name_label = Label::new(
-            Position::Align(Alignment::Centre),
-            Position::Align(Alignment::Centre),
-            24, 
-            "Player Name:".to_string(), 
-            BLACK
-        );
-
-        let player_name_text_box =  Rc::new(RefCell::new(TextBox::new(
-            Position::Align(Alignment::Centre), 
-            Position::Align(Alignment::Centre), 
-            Size::Rel(0.9), 
-            Size::Abs(40.0), 
-            WHITE, 
-            BLACK, 
-            BLACK
-        )));
+    pub fn new() -> Self {
+        Self::load().expect("Couldn't load the room browser layout")
+    }
 
-        let mut room_fields = Container::new(
-            Position::Align(Alignment::Centre), 
-            Position::Rel(0.30), 
-            Size::Rel(0.95), 
-            Size::Rel(0.65),
-            GREEN,
-            ui::Layout::ColumnCentre,
-            Size::Abs(10.0),
-        );
+    /// Build the view from its layout file, the room list starts off empty
+    pub fn load() -> Result<Self, String> {
+        let (container, widgets) = Self::LAYOUT.build()?;
+        Ok(Self {
+            container,
+            room_container: widgets.scroll_container("rooms")?,
+            room_name_text_box: widgets.text_box("room_name")?,
+            max_player_slider: widgets.slider("max_players")?,
+            private_checkbox: widgets.checkbox("private")?,
+            player_name_text_box: widgets.text_box("player_name")?,
+            room_code_text_box: widgets.text_box("room_code")?,
+            room_rows: Vec::new(),
+        })
+    }
 
-        let mut room_name_container = Container::new(
-            Position::Align(Alignment::Centre), 
-            Position::Align(Alignment::RiBot), 
-            Size::Rel(1.0), 
-            Size::Abs(75.0),
-            PINK,
-            ui::Layout::ColumnCentre,
-            Size::Abs(5.0),
-        );
+    /// Replace the whole room list, e.g. when the directory is first received
+    pub fn update_rooms(&mut self, rooms: &[RoomInfo]) {
+        self.room_container.borrow_mut().clear_children();
+        self.room_container.borrow_mut().scroll_to_top();
+        self.room_rows.clear();
 
-        let room_name_label = Label::new(
-            Position::Align(Alignment::Centre),
-            Position::Align(Alignment::Centre),
-            24, 
-            "Room Name:".to_string(), 
-            BLACK
-        );
+        let mut rooms: Vec<&RoomInfo> = rooms.iter().collect();
+        rooms.sort_by(|a, b| a.name.cmp(&b.name));
+        for room in rooms {
+            self.add_room(room);
+        }
+    }
 
-        let room_name_text_box =  Rc::new(RefCell::new(TextBox::new(
-            Position::Align(Alignment::Centre), 
-            Position::Align(Alignment::Centre), 
-            Size::Rel(0.9), 
-            Size::Abs(40.0), 
-            WHITE, 
-            BLACK, 
-            BLACK
-        )));
+    /// Add a room to the bottom of the list
+    pub fn add_room(&mut self, room: &RoomInfo) {
+        // Shouldn't happen, but don't list the same room twice
+        if self.room_rows.iter().any(|(id, _)| *id == room.id) {
+            self.update_room(room);
+            return;
+        }
 
-        let mut max_players_container = Container::new(
+        // Create a room object that has the name, and player count and a join button
+        let mut individual_room = Container::new(
             Position::Align(Alignment::Centre), 
             Position::Align(Alignment::Centre), 
-            Size::Rel(1.0), 
-            Size::Abs(90.0),
-            GOLD,
-            ui::Layout::ColumnCentre,
-            Size::Abs(5.0),
+            Size::Rel(0.85),
+            Size::Abs(100.0),
+            StyleRole::ListItem,
+            ui::Layout::None,
+            Size::Rel(0.2),
         );
-        
-        let max_players_label = Label::new(
+        individual_room.add_padding(Padding::uniform(20.0));
+        let player_count = Rc::new(RefCell::new(Label::new(
             Position::Align(Alignment::Centre),
             Position::Align(Alignment::Centre),
-            24, 
-            "Max Players:".to_string(), 
-            BLACK
-        );
-
-        let max_player_slider = Rc::new(RefCell::new(Slider::new(
-            Position::Align(Alignment::Centre), 
-            Position::Align(Alignment::Centre), 
-            Size::Rel(0.9), 
-            Size::Abs(40.0), 
-            WHITE,
-            BLACK,
-            BLACK,
-            2.0,
-            12.0,
-            1.0,
-            true
+            32, 
+            format!("{}/{}", room.player_count, room.max_players), 
+            StyleRole::Text
         )));
-
-        let mut private_container = Container::new(
-            Position::Align(Alignment::Centre), 
-            Position::Align(Alignment::Centre), 
-            Size::Rel(1.0), 
-            Size::Abs(40.0),
-            BROWN,
-            ui::Layout::RowCentre,
-            Size::Abs(20.0),
-        );
-
-        let private_label = Label::new(
-            Position::Align(Alignment::Centre),
+        let room_name = Label::new(
+            Position::Align(Alignment::LeTop),
             Position::Align(Alignment::Centre),
-            24, 
-            "Private?".to_string(), 
-            BLACK
+            32, 
+            room.name.clone(), 
+            StyleRole::Text
         );
-
-        let private_checkbox = Rc::new(RefCell::new(Checkbox::new(
-            Position::Align(Alignment::Centre),
-            Position::Align(Alignment::Centre),
-            Size::Abs(20.0), 
-            Size::Abs(20.0),
-            WHITE,
-            PURPLE,
-        )));
-
-
-        let create_room_btn = Button::new(
-            Position::Align(Alignment::Centre),
+        let button = Button::new(
+            Position::Align(Alignment::RiBot),
             Position::Align(Alignment::Centre),
-            Size::Rel(0.8),
-            Size::Abs(50.0),
-            LIME,
-            BLACK,
-            "Create Room".to_string(),
+            Size::Abs(100.0),
+            Size::Abs(75.0),
+            StyleRole::PrimaryButton,
+            "Join".to_string(),
             32,
-            Some(UIMessage::CreateRoom),
+            Some(UIMessage::JoinRoom(room.id)),
         );
 
-        room_name_container.add_child(Box::new(room_name_label));
-        room_name_container.add_child(Box::new(TextBoxWrapper(room_name_text_box.clone())));
-        room_fields.add_child(Box::new(room_name_container));
-        max_players_container.add_child(Box::new(max_players_label));
-        max_players_container.add_child(Box::new(SliderWrapper(max_player_slider.clone())));
-        room_fields.add_child(Box::new(max_players_container));
-        private_container.add_child(Box::new(private_label));
-        private_container.add_child(Box::new(CheckboxWrapper(private_checkbox.clone())));
-        room_fields.add_child(Box::new(private_container));
-        room_fields.add_child(Box::new(create_room_btn));
+        individual_room.add_child(Box::new(room_name));
+        individual_room.add_child(Box::new(LabelWrapper(Rc::clone(&player_count))));
+        individual_room.add_child(Box::new(button));
 
-        player_fields.add_child(Box::new(player_name_label));
-        player_fields.add_child(Box::new(TextBoxWrapper(player_name_text_box.clone())));
-
-        left_bar.add_child(Box::new(player_fields));
-        left_bar.add_child(Box::new(room_fields));
-        root.borrow_mut().add_child(Box::new(left_bar));
-        root.borrow_mut().add_child(Box::new(back_btn));
-        root.borrow_mut().add_child(Box::new(RefCellContainerWrapper(Rc::clone(&room_container))));
+        self.room_container.borrow_mut().add_child(Box::new(individual_room));
+        self.room_rows.push((room.id, player_count));
+    }
 
-        Self {
-            container: root,
-            room_container,
-            room_name_text_box,
-            max_player_slider,
-            private_checkbox,
-            player_name_text_box,
+    /// Update a listed room where it is, so the list doesn't jump around
+    pub fn update_room(&mut self, room: &RoomInfo) {
+        match self.room_rows.iter().find(|(id, _)| *id == room.id) {
+            Some((_, player_count)) => player_count.borrow_mut().set_text(format!("{}/{}", room.player_count, room.max_players)),
+            None => self.add_room(room),
         }
     }
 
-    pub fn update_rooms(&mut self, rooms: &[RoomInfo]) {
-        let mut container = self.room_container.borrow_mut();
-        container.clear_children(); // Optional: clear existing buttons
-
-        for room in rooms {
-            // Create a room object that has the name, and player count and a join button
-            let mut individual_room = Container::new(
-                Position::Align(Alignment::Centre), 
-                Position::Align(Alignment::Centre), 
-                Size::Rel(0.85),
-                Size::Abs(100.0),
-                WHITE,
-                ui::Layout::None,
-                Size::Rel(0.2),
-            );
-            individual_room.add_padding(Padding::uniform(20.0));
-            let player_count = Label::new(
-                Position::Align(Alignment::Centre),
-                Position::Align(Alignment::Centre),
-                32, 
-                format!("{}/{}", room.player_count.clone(), room.max_players.clone()), 
-                BLACK
-            );
-            let room_name = Label::new(
-                Position::Align(Alignment::LeTop),
-                Position::Align(Alignment::Centre),
-                32, 
-                room.name.clone(), 
-                BLACK
-            );
-            let button = Button::new(
-                Position::Align(Alignment::RiBot),
-                Position::Align(Alignment::Centre),
-                Size::Abs(100.0),
-                Size::Abs(75.0),
-                LIME,
-                BLACK,
-                "Join".to_string(),
-                32,
-                Some(UIMessage::JoinRoom(room.id)),
-            );
-
-            individual_room.add_child(Box::new(room_name));
-            individual_room.add_child(Box::new(player_count));
-            individual_room.add_child(Box::new(button));
-            
-            container.add_child(Box::new(individual_room));
+    pub fn remove_room(&mut self, room_id: &Uuid) {
+        // The rows are in the same order as the containers children
+        if let Some(index) = self.room_rows.iter().position(|(id, _)| id == room_id) {
+            self.room_rows.remove(index);
+            self.room_container.borrow_mut().remove_child(index);
         }
     }
 }
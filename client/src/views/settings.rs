@@ -0,0 +1,31 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{settings::ClientSettings, ui::{container::Container, layout_file::LayoutFile, textbox::TextBox}};
+
+pub struct Settings {
+    pub container: Rc<RefCell<Container>>,
+    pub matchmaking_url_text_box: Rc<RefCell<TextBox>>,
+}
+
+impl Settings {
+    pub const LAYOUT: LayoutFile = LayoutFile {
+        path: "res/layouts/settings.json",
+        built_in: include_str!("../../../res/layouts/settings.json"),
+    };
+
+    pub fn new(settings: &ClientSettings) -> Self {
+        Self::load(settings).expect("Couldn't load the settings layout")
+    }
+
+    /// Build the view from its layout file, filled in with the current settings
+    pub fn load(settings: &ClientSettings) -> Result<Self, String> {
+        let (container, widgets) = Self::LAYOUT.build()?;
+        let matchmaking_url_text_box = widgets.text_box("matchmaking_url")?;
+        matchmaking_url_text_box.borrow_mut().set_text(settings.matchmaking_url.clone());
+
+        Ok(Self {
+            container,
+            matchmaking_url_text_box,
+        })
+    }
+}
@@ -29,9 +29,10 @@ impl CardManager {
         }
     }
 
-    pub fn register_card(&mut self, card_path: &str) {
-        let loaded_card = Card::load_from_file(card_path);
+    pub fn register_card(&mut self, card_path: &str) -> Result<(), String> {
+        let loaded_card = Card::load_from_file(card_path)?;
         self.card_registry.insert(loaded_card.get_card_id().clone(), loaded_card);
+        Ok(())
     }
 
     pub fn instansiate_card(&mut self, instance_id: &Uuid, card: Card) {
@@ -104,6 +105,14 @@ impl BaseCard {
     fn get_img_path(&self) -> &String {
         &self.img_path
     }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_description(&self) -> &String {
+        &self.description
+    }
 }
 
 
@@ -243,40 +252,34 @@ fn remove_filename(path: &str) -> &str {
 }
 
 impl Card {
-    pub fn load_from_file(path: &str) -> Self {
-        let data = fs::read_to_string(path).expect("Failed to read file");
-        let mut card: Self = serde_json::from_str(&data).expect("Failed to parse JSON");
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read card {}: {}", path, e))?;
+        let mut card: Self = serde_json::from_str(&data).map_err(|e| format!("Failed to parse card {}: {}", path, e))?;
 
         let base_path = remove_filename(path).to_owned();
 
         match &mut card {
             Card::Addon(c) => {
                 c.base.img_path = format!("{}/{}", base_path, c.base().img_path);
-                println!("Loaded card {}", c.base().name);
             },
             Card::BattleItem(c) => {
                 c.base.img_path = format!("{}/{}", base_path, c.base().img_path);
-                println!("Loaded card {}", c.base().name);
             },
             Card::Character(c) => {
                 c.base.img_path = format!("{}/{}", base_path, c.base().img_path);
-                println!("Loaded card {}", c.base().name);
             },
             Card::Item(c) => {
                 c.base.img_path = format!("{}/{}", base_path, c.base().img_path);
-                println!("Loaded card {}", c.base().name);
             },
             Card::SuperCharacter(c) => {
                 c.base.img_path = format!("{}/{}", base_path, c.base().img_path);
-                println!("Loaded card {}", c.base().name);
             },
             Card::Weapon(c) => {
                 c.base.img_path = format!("{}/{}", base_path, c.base().img_path);
-                println!("Loaded card {}", c.base().name);
             },
         }
 
-        card
+        Ok(card)
     }
 
     pub fn get_card_id(&self) -> &String {
@@ -323,6 +326,65 @@ impl Card {
         }
     }
 
+    /// Just the image's file name, img_path is where it is on this machine which clients shouldn't need to know
+    pub fn get_img_file_name(&self) -> &str {
+        let img_path = self.get_img_path();
+        img_path.rsplit('/').next().unwrap_or(img_path)
+    }
+
+    pub fn get_name(&self) -> &String {
+        match self {
+            Card::Addon(c) => c.base().get_name(),
+            Card::BattleItem(c) => c.base().get_name(),
+            Card::Character(c) => c.base().get_name(),
+            Card::Item(c) => c.base().get_name(),
+            Card::SuperCharacter(c) => c.base().get_name(),
+            Card::Weapon(c) => c.base().get_name(),
+        }
+    }
+
+    pub fn get_description(&self) -> &String {
+        match self {
+            Card::Addon(c) => c.base().get_description(),
+            Card::BattleItem(c) => c.base().get_description(),
+            Card::Character(c) => c.base().get_description(),
+            Card::Item(c) => c.base().get_description(),
+            Card::SuperCharacter(c) => c.base().get_description(),
+            Card::Weapon(c) => c.base().get_description(),
+        }
+    }
+
+    pub fn get_card_type(&self) -> &'static str {
+        match self {
+            Card::Addon(_) => "Addon",
+            Card::BattleItem(_) => "BattleItem",
+            Card::Character(_) => "Character",
+            Card::Item(_) => "Item",
+            Card::SuperCharacter(_) => "SuperCharacter",
+            Card::Weapon(_) => "Weapon",
+        }
+    }
+
+    /// Base damage of the card (0 for cards that don't deal damage)
+    pub fn get_damage(&self) -> i32 {
+        match self {
+            Card::Character(c) => c.damage.unwrap_or(0),
+            Card::SuperCharacter(c) => c.additional_damage.unwrap_or(0),
+            Card::Weapon(c) => c.damage.unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// The character a weapon gives bonus damage to and how much
+    pub fn get_synergy(&self) -> Option<(&String, i32)> {
+        match self {
+            Card::Weapon(c) => {
+                c.synergy_card_id.as_ref().map(|id| (id, c.synergy_damage.unwrap_or(0)))
+            },
+            _ => None,
+        }
+    }
+
     pub fn get_ability(&self) -> &Option<Ability> {
         match self {
             Card::Addon(c) => &c.ability,
@@ -0,0 +1,131 @@
+// When a card needs the player to pick something (a target, a card from a pile, what to do next)
+// the server sends a ChoiceRequest and waits for the ids they picked
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::game_view::CardView;
+
+/// How long a player gets to answer a choice a card asks them to make
+pub const CHOICE_TIMEOUT_SECS: f32 = 30.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChoiceRequest {
+    /// Sent back with the answer so it can be matched up
+    pub choice_id: Uuid,
+    /// What is being asked, e.g. "Pick a card from the discard pile"
+    pub title: String,
+    pub options: Vec<ChoiceOption>,
+    /// How many options have to be picked
+    pub min: usize,
+    pub max: usize,
+    /// Seconds to answer in, after which the client answers on its own
+    pub timeout_secs: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ChoiceOption {
+    /// A card - from someones table, a pile, etc
+    Card(CardView),
+    Player { player_id: Uuid, name: String },
+    /// Anything else, e.g. "Show your hand" or "Reveal an addon"
+    Text { option_id: Uuid, label: String },
+}
+
+impl ChoiceOption {
+    /// The id that gets sent back when this option is picked
+    pub fn id(&self) -> Uuid {
+        match self {
+            ChoiceOption::Card(card) => card.instance_id,
+            ChoiceOption::Player { player_id, .. } => *player_id,
+            ChoiceOption::Text { option_id, .. } => *option_id,
+        }
+    }
+}
+
+/// What the game does with the options picked once a choice is answered
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ChoiceAction {
+    /// Throw the picked cards on the table away (Addon Remover)
+    DiscardFromTable,
+}
+
+impl ChoiceRequest {
+    /// Make sure an answer is one the request allows
+    pub fn check(&self, selected: &[Uuid]) -> Result<(), String> {
+        if selected.len() < self.min || selected.len() > self.max {
+            return Err(format!("Pick between {} and {} options", self.min, self.max));
+        }
+        let unique: HashSet<&Uuid> = selected.iter().collect();
+        if unique.len() != selected.len() {
+            return Err("Options can only be picked once".to_string());
+        }
+        if !selected.iter().all(|id| self.options.iter().any(|option| option.id() == *id)) {
+            return Err("That isn't one of the options".to_string());
+        }
+        Ok(())
+    }
+
+    /// What to answer with when there's no time left - the first options, as few as allowed
+    pub fn default_answer(&self) -> Vec<Uuid> {
+        self.options.iter().take(self.min).map(|option| option.id()).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_option(label: &str) -> ChoiceOption {
+        ChoiceOption::Text { option_id: Uuid::new_v4(), label: label.to_string() }
+    }
+
+    fn request(options: usize, min: usize, max: usize) -> ChoiceRequest {
+        ChoiceRequest {
+            choice_id: Uuid::new_v4(),
+            title: "Pick".to_string(),
+            options: (0..options).map(|i| text_option(&i.to_string())).collect(),
+            min,
+            max,
+            timeout_secs: None,
+        }
+    }
+
+    #[test]
+    fn check_accepts_answers_within_the_limits() {
+        let request = request(3, 1, 2);
+        let ids: Vec<Uuid> = request.options.iter().map(|option| option.id()).collect();
+        assert!(request.check(&ids[..1]).is_ok());
+        assert!(request.check(&ids[1..]).is_ok());
+    }
+
+    #[test]
+    fn check_rejects_the_wrong_number_of_options() {
+        let request = request(3, 1, 2);
+        let ids: Vec<Uuid> = request.options.iter().map(|option| option.id()).collect();
+        assert!(request.check(&[]).is_err());
+        assert!(request.check(&ids).is_err());
+    }
+
+    #[test]
+    fn check_rejects_repeats_and_unknown_ids() {
+        let request = request(3, 1, 2);
+        let first = request.options[0].id();
+        assert!(request.check(&[first, first]).is_err());
+        assert!(request.check(&[Uuid::new_v4()]).is_err());
+    }
+
+    #[test]
+    fn default_answer_is_the_first_options_and_passes_check() {
+        let two_needed = request(3, 2, 3);
+        let answer = two_needed.default_answer();
+        assert_eq!(answer, vec![two_needed.options[0].id(), two_needed.options[1].id()]);
+        assert!(two_needed.check(&answer).is_ok());
+
+        // Nothing has to be picked, so nothing is
+        assert!(request(3, 0, 1).default_answer().is_empty());
+    }
+}
@@ -5,7 +5,8 @@
 // Character draw pile 
 // Deck manager - 
 
-use macroquad::prelude::rand;
+use macroquad::prelude::rand::RandGenerator;
+use uuid::{Builder, Uuid};
 
 
 
@@ -15,6 +16,8 @@ pub struct DeckManager {
     round_discard_pile: Vec<String>,
     character_draw_pile: Vec<String>,
     super_character_draw_pile: Vec<String>,
+    // Every random choice in a game goes through this so a game can be replayed from its seed
+    rng: RandGenerator,
 }
 
 impl DeckManager {
@@ -25,8 +28,22 @@ impl DeckManager {
             round_discard_pile: vec![],
             character_draw_pile: vec![],
             super_character_draw_pile: vec![],
+            rng: RandGenerator::new(),
         }
     }
+
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.srand(seed);
+    }
+
+    /// Create a new card instance id from the seeded generator (so replays give cards the same ids)
+    pub fn gen_instance_id(&mut self) -> Uuid {
+        let mut bytes = [0u8; 16];
+        for chunk in bytes.chunks_mut(4) {
+            chunk.copy_from_slice(&self.rng.rand().to_le_bytes());
+        }
+        Builder::from_random_bytes(bytes).into_uuid()
+    }
     
     pub fn add_item_draw_pile(&mut self, card_id: String) {
         self.item_draw_pile.push(card_id);
@@ -48,9 +65,17 @@ impl DeckManager {
         &self.item_draw_pile
     }
 
+    pub fn get_character_draw_pile(&self) -> &Vec<String> {
+        &self.character_draw_pile
+    }
+
+    pub fn get_discard_pile(&self) -> &Vec<String> {
+        &self.discard_pile
+    }
+
     pub fn get_random_card_and_remove_item_pile(&mut self) -> Option<String> {
         if self.item_draw_pile.len() != 0 {
-            let card_index = rand::gen_range(0, self.item_draw_pile.len());
+            let card_index = self.rng.gen_range(0, self.item_draw_pile.len());
             let card_id = &self.item_draw_pile[card_index].clone();
             self.item_draw_pile.remove(card_index);
 
@@ -60,5 +85,13 @@ impl DeckManager {
         }
     }
 
+    pub fn get_random_card_and_remove_character_pile(&mut self) -> Option<String> {
+        if self.character_draw_pile.is_empty() {
+            return None;
+        }
+        let card_index = self.rng.gen_range(0, self.character_draw_pile.len());
+        Some(self.character_draw_pile.remove(card_index))
+    }
+
 }
 
@@ -1,24 +1,38 @@
 use macroquad::{prelude::rand};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{card::CardManager, deck::{self, DeckManager}, game_state::GameState, player::{self, PlayerManager}};
+use super::{card::{Card, CardManager}, deck::{self, DeckManager}, game_state::GameState, player::{self, PlayerManager}};
 
 
+pub const STARTING_HAND_SIZE: usize = 6;
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum Event {
     CardPlayed(CardEvent),
     RoundStart(RoundEvent),
-    DrawCard { player_id: Uuid, pile: String, selector: DrawSelector }
+    DrawCard { player_id: Uuid, pile: String, selector: DrawSelector },
+    DiscardHand { player_id: Uuid },
+    ClearTable { player_id: Uuid },
+    DiscardFromTable { card_instance_id: Uuid },
+    StartGame { turn_order: Vec<Uuid> },
+    EndTurn { player_id: Uuid },
+    RemovePlayer { player_id: Uuid },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum DrawSelector {
     Random,
     CardId(String),
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CardEvent {
     pub card_instance_id: Uuid,
     pub player_id: Uuid,
+    /// The character on the table the card was played onto, if any
+    #[serde(default)]
+    pub target: Option<Uuid>,
 }
 
 impl CardEvent {
@@ -30,6 +44,7 @@ impl CardEvent {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RoundEvent {
     pub round_number: usize,
 }
@@ -46,7 +61,7 @@ impl EventManager {
         }
     }
     
-    pub fn handle_event(&mut self, event: Event, player_manager: &mut PlayerManager, game_state: &GameState, card_manager: &mut CardManager, deck_manager: &mut DeckManager) {
+    pub fn handle_event(&mut self, event: Event, player_manager: &mut PlayerManager, game_state: &mut GameState, card_manager: &mut CardManager, deck_manager: &mut DeckManager) {
         match event {
             Event::CardPlayed(ce) => {
 
@@ -69,6 +84,33 @@ impl EventManager {
                 for (player_id, card_instance_id, ability) in to_process {
                     ability.process_effects(&player_id, &card_instance_id, player_manager, card_manager);
                 }
+
+                // Items are used up when played, everything else stays on the table
+                let is_consumed = matches!(card_manager.get_card_from_instance_id(ce.card_instance_id()), Some(Card::Item(_)) | Some(Card::BattleItem(_)));
+                let attaches = matches!(card_manager.get_card_from_instance_id(ce.card_instance_id()), Some(Card::Addon(_)) | Some(Card::Weapon(_)) | Some(Card::SuperCharacter(_)));
+                // Cards played onto a character go with it on the table, whoever's character it is
+                let attach_to = ce.target.filter(|_| attaches).and_then(|target| {
+                    player_manager.get_player_ids().iter()
+                        .filter_map(|id| player_manager.get_player_by_id(id))
+                        .find_map(|owner| attach_index(owner.get_table(), &target, card_manager).map(|index| (*owner.get_id(), index)))
+                });
+                if is_consumed && let Some(player) = player_manager.get_player_by_id_mut(ce.player_id()) {
+                    if player.remove_from_hand(ce.card_instance_id()) {
+                        if let Some(card) = card_manager.get_card_from_instance_id(ce.card_instance_id()) {
+                            deck_manager.add_discard_pile(card.get_card_id().to_owned());
+                        }
+                        card_manager.deinstansiate_card(ce.card_instance_id());
+                    }
+                } else if let Some((owner_id, index)) = attach_to {
+                    let played = player_manager.get_player_by_id_mut(ce.player_id())
+                        .is_some_and(|player| player.remove_from_hand(ce.card_instance_id()));
+                    if played && let Some(owner) = player_manager.get_player_by_id_mut(&owner_id) {
+                        owner.add_to_table_at(*ce.card_instance_id(), index);
+                    }
+                } else if let Some(player) = player_manager.get_player_by_id_mut(ce.player_id()) {
+                    // The rest just go on the end
+                    player.move_to_table(ce.card_instance_id());
+                }
                 
             },
             Event::RoundStart(re) => {
@@ -84,7 +126,7 @@ impl EventManager {
                                 // Draw a random card from the standard draw pile
                                 if let Some(card_id) = deck_manager.get_random_card_and_remove_item_pile() {
                                     if let Some(mut card_to_inst) = card_manager.get_card_from_id_clone(&card_id) {
-                                        let instance_id = Uuid::new_v4();
+                                        let instance_id = deck_manager.gen_instance_id();
                                         card_to_inst.set_instance_id(instance_id);
                                         // Add the card to the instansitated card hashmap
                                         card_manager.instansiate_card(&instance_id, card_to_inst);
@@ -106,6 +148,15 @@ impl EventManager {
                         match selector {
                             DrawSelector::Random => {
                                 // Pick a random character from the character pile
+                                if let Some(card_id) = deck_manager.get_random_card_and_remove_character_pile()
+                                    && let Some(mut card_to_inst) = card_manager.get_card_from_id_clone(&card_id) {
+                                    let instance_id = deck_manager.gen_instance_id();
+                                    card_to_inst.set_instance_id(instance_id);
+                                    card_manager.instansiate_card(&instance_id, card_to_inst);
+                                    if let Some(player) = player_manager.get_player_by_id_mut(&player_id) {
+                                        player.add_card_instance_id_to_hand(instance_id);
+                                    }
+                                }
                             },
                             DrawSelector::CardId(cid) => {
                                 // Pick a character from the character pile (people picker)
@@ -124,7 +175,94 @@ impl EventManager {
                     _ => ()
                 }
             },
+            Event::DiscardHand { player_id } => {
+                // Move every card in the players hand to the discard pile
+                if let Some(player) = player_manager.get_player_by_id_mut(&player_id) {
+                    for instance_id in player.take_hand() {
+                        if let Some(card) = card_manager.get_card_from_instance_id(&instance_id) {
+                            deck_manager.add_discard_pile(card.get_card_id().to_owned());
+                        }
+                        card_manager.deinstansiate_card(&instance_id);
+                    }
+                }
+            },
+            Event::ClearTable { player_id } => {
+                // Move every card the player has in play to the discard pile
+                if let Some(player) = player_manager.get_player_by_id_mut(&player_id) {
+                    for instance_id in player.take_table() {
+                        if let Some(card) = card_manager.get_card_from_instance_id(&instance_id) {
+                            deck_manager.add_discard_pile(card.get_card_id().to_owned());
+                        }
+                        card_manager.deinstansiate_card(&instance_id);
+                    }
+                }
+            },
+            Event::DiscardFromTable { card_instance_id } => {
+                // Take the card off whichever table it is on and throw it away
+                let owner = player_manager.get_player_ids().into_iter()
+                    .find(|id| player_manager.get_player_by_id(id).is_some_and(|player| player.get_table().contains(&card_instance_id)));
+                if let Some(player) = owner.and_then(|id| player_manager.get_player_by_id_mut(&id))
+                    && player.remove_from_table(&card_instance_id) {
+                    if let Some(card) = card_manager.get_card_from_instance_id(&card_instance_id) {
+                        deck_manager.add_discard_pile(card.get_card_id().to_owned());
+                    }
+                    card_manager.deinstansiate_card(&card_instance_id);
+                }
+            },
+            Event::StartGame { turn_order } => {
+                // Everyone gets a character and a starting hand, then the first player in the order goes
+                for player_id in &turn_order {
+                    self.handle_event(Event::DrawCard { player_id: *player_id, pile: "character".to_owned(), selector: DrawSelector::Random }, player_manager, game_state, card_manager, deck_manager);
+                    for _ in 0..STARTING_HAND_SIZE {
+                        self.handle_event(Event::DrawCard { player_id: *player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, player_manager, game_state, card_manager, deck_manager);
+                    }
+                }
+                game_state.set_turn_order(turn_order);
+            },
+            Event::RemovePlayer { player_id } => {
+                // Throw their cards away and take them out of the game
+                self.handle_event(Event::DiscardHand { player_id }, player_manager, game_state, card_manager, deck_manager);
+                self.handle_event(Event::ClearTable { player_id }, player_manager, game_state, card_manager, deck_manager);
+                let was_playing = game_state.get_turn_order().len() >= 2;
+                let was_their_turn = game_state.get_current_player() == Some(&player_id);
+                game_state.remove_from_turn_order(&player_id);
+                player_manager.remove_player(&player_id);
+                // Nobody left to play against
+                if was_playing && game_state.get_turn_order().len() < 2 {
+                    game_state.end_game();
+                }
+                // Their turn is over, so it goes the same way as if they had ended it
+                if was_their_turn && !game_state.is_game_over() {
+                    if deck_manager.get_item_draw_pile().is_empty() {
+                        game_state.end_game();
+                    } else if let Some(next_player_id) = game_state.get_current_player().cloned() {
+                        self.handle_event(Event::DrawCard { player_id: next_player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, player_manager, game_state, card_manager, deck_manager);
+                    }
+                }
+            },
+            Event::EndTurn { player_id } if game_state.get_current_player() == Some(&player_id) && !game_state.is_game_over() => {
+                // The game ends once there is nothing left to draw
+                if deck_manager.get_item_draw_pile().is_empty() {
+                    game_state.end_game();
+                    return;
+                }
+                game_state.next_turn();
+                // The next player draws a card at the start of their turn
+                if let Some(next_player_id) = game_state.get_current_player().cloned() {
+                    self.handle_event(Event::DrawCard { player_id: next_player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, player_manager, game_state, card_manager, deck_manager);
+                }
+            },
             _ => ()
         }
     }
-}
\ No newline at end of file
+}
+
+// Where a card attached to a character goes on the table - after the character and anything already attached to it
+fn attach_index(table: &[Uuid], character_instance_id: &Uuid, card_manager: &CardManager) -> Option<usize> {
+    let position = table.iter().position(|id| id == character_instance_id)?;
+    let next_character = table[position + 1..].iter()
+        .position(|id| matches!(card_manager.get_card_from_instance_id(id), Some(Card::Character(_))))
+        .map(|offset| position + 1 + offset)
+        .unwrap_or(table.len());
+    Some(next_character)
+}
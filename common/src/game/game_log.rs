@@ -0,0 +1,268 @@
+// Records everything that changes a game so it can be rebuilt later
+// The log holds the seed, the deck and every action in the order it was applied
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::GameManger;
+
+use super::{choice::{ChoiceAction, ChoiceRequest}, event_manager::Event};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameLog {
+    seed: u64,
+    deck_path: Option<String>,
+    entries: Vec<LogEntry>,
+    // Turned off for games nobody will replay (e.g. in the simulator) so the log doesn't grow with every action
+    #[serde(skip)]
+    paused: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogEntry {
+    pub sequence: usize,
+    pub action: LogAction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum LogAction {
+    /// A player was added to the game
+    PlayerCreated { player_id: Uuid, player_name: String },
+
+    /// An event that went through the event manager
+    Event(Event),
+
+    /// A player was asked to pick something (targets, cards, etc)
+    ChoiceAsked { player_id: Uuid, request: ChoiceRequest, action: ChoiceAction },
+
+    /// A player picked something when asked, what happens because of it comes from the matching ChoiceAsked
+    PlayerChoice { player_id: Uuid, choice_id: Uuid, selected: Vec<Uuid> },
+}
+
+impl GameLog {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            deck_path: None,
+            entries: vec![],
+            paused: false,
+        }
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read log: {}", e))?;
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse log: {}", e))
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_deck_path(&self) -> &Option<String> {
+        &self.deck_path
+    }
+
+    pub fn set_deck_path(&mut self, deck_path: String) {
+        self.deck_path = Some(deck_path);
+    }
+
+    pub fn get_entries(&self) -> &Vec<LogEntry> {
+        &self.entries
+    }
+
+    /// Stop (or start again) adding actions to the log
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Add an action to the end of the log and give it the next sequence number
+    pub fn record(&mut self, action: LogAction) {
+        if self.paused {
+            return;
+        }
+        let sequence = self.entries.len();
+        self.entries.push(LogEntry { sequence, action });
+    }
+}
+
+
+// Rebuilds a game one log entry at a time
+pub struct Replay {
+    game: GameManger,
+    log: GameLog,
+    position: usize,
+}
+
+impl Replay {
+    pub fn new(log: GameLog) -> Result<Self, String> {
+        let mut game = GameManger::with_seed(log.get_seed());
+        if let Some(deck_path) = log.get_deck_path() {
+            game.load_deck(deck_path)?;
+        }
+
+        Ok(Self {
+            game,
+            log,
+            position: 0,
+        })
+    }
+
+    pub fn get_game(&self) -> &GameManger {
+        &self.game
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.log.get_entries().len()
+    }
+
+    /// Apply the next entry in the log, returns None once the end has been reached
+    pub fn step(&mut self) -> Option<&LogEntry> {
+        let entry = self.log.get_entries().get(self.position)?;
+
+        match entry.action.clone() {
+            LogAction::PlayerCreated { player_id, player_name } => {
+                self.game.add_player(player_id, player_name);
+            },
+            LogAction::Event(event) => {
+                self.game.apply_event(event);
+            },
+            LogAction::ChoiceAsked { player_id, request, action } => {
+                self.game.ask_choice(player_id, request, action);
+            },
+            LogAction::PlayerChoice { player_id, choice_id, selected } => {
+                if let Err(e) = self.game.answer_choice(player_id, choice_id, selected) {
+                    println!("Replay: choice {} at {} couldn't be answered: {}", choice_id, entry.sequence, e);
+                }
+            }
+        }
+
+        self.position += 1;
+        self.log.get_entries().get(self.position - 1)
+    }
+
+    /// Apply every remaining entry
+    pub fn run_to_end(&mut self) {
+        while self.step().is_some() {}
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_DECK: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/decks/base_deck");
+    // Picked so that Addon Remover gets played onto an addon in the first few rounds
+    const SEED: u64 = 7;
+
+    // Everyone plays whatever they can each turn and takes the first option when asked to pick
+    // Gives back the game and how many choices were answered
+    fn play_game(turns: usize) -> (GameManger, usize) {
+        let mut game = GameManger::with_seed(SEED);
+        game.load_deck(BASE_DECK).unwrap();
+        // Fixed ids so two games played the same way can be compared
+        let players: Vec<Uuid> = (1..=3).map(Uuid::from_u128).collect();
+        for player_id in &players {
+            game.add_player(*player_id, player_id.to_string());
+        }
+        game.start_game(players).unwrap();
+
+        let mut choices = 0;
+        for _ in 0..turns {
+            let player_id = *game.get_game_state().get_current_player().unwrap();
+            let hand = game.get_player_manager().get_player_by_id(&player_id).unwrap().get_hand().clone();
+            for card_instance_id in hand {
+                if let Ok(Some(request)) = game.play_card(player_id, card_instance_id, None) {
+                    game.answer_choice(player_id, request.choice_id, request.default_answer()).unwrap();
+                    choices += 1;
+                }
+            }
+            game.end_turn(player_id).unwrap();
+        }
+        (game, choices)
+    }
+
+    // Everything about the state that a replay has to get right
+    fn snapshot(game: &GameManger) -> Vec<String> {
+        let mut snapshot = vec![];
+        for player_id in game.get_game_state().get_turn_order() {
+            let player = game.get_player_manager().get_player_by_id(player_id).unwrap();
+            snapshot.push(format!("{} hand {:?} table {:?}", player_id, player.get_hand(), player.get_table()));
+        }
+        let deck = game.get_deck_manager();
+        snapshot.push(format!("items {:?}", deck.get_item_draw_pile()));
+        snapshot.push(format!("characters {:?}", deck.get_character_draw_pile()));
+        snapshot.push(format!("discard {:?}", deck.get_discard_pile()));
+        snapshot.push(format!("turn {:?} round {}", game.get_game_state().get_current_player(), game.get_game_state().get_round()));
+        snapshot
+    }
+
+    #[test]
+    fn replaying_a_log_ends_up_with_the_same_game() {
+        let (game, choices) = play_game(30);
+        assert!(choices > 0, "no choices were made, pick another seed");
+
+        // Through json like a saved log would be
+        let json = serde_json::to_string(game.get_game_log()).unwrap();
+        let mut replay = Replay::new(serde_json::from_str(&json).unwrap()).unwrap();
+        replay.run_to_end();
+
+        assert!(replay.is_finished());
+        assert_eq!(snapshot(replay.get_game()), snapshot(&game));
+        // The replay keeps its own log, which should be the same as the one it came from
+        assert_eq!(replay.get_game().get_game_log().get_entries().len(), game.get_game_log().get_entries().len());
+    }
+
+    #[test]
+    fn replayed_choices_wait_for_their_answer() {
+        let (game, _) = play_game(30);
+        let entries = game.get_game_log().get_entries();
+        let (asked_at, choice_id) = entries.iter()
+            .find_map(|entry| match &entry.action {
+                LogAction::ChoiceAsked { request, .. } => Some((entry.sequence, request.choice_id)),
+                _ => None,
+            })
+            .unwrap();
+
+        let mut replay = Replay::new(game.get_game_log().clone()).unwrap();
+        while replay.get_position() <= asked_at {
+            replay.step();
+        }
+        assert!(replay.get_game().get_pending_choice(&choice_id).is_some());
+
+        // The answer comes straight after in these games
+        assert!(matches!(replay.step().map(|entry| &entry.action), Some(LogAction::PlayerChoice { .. })));
+        assert!(replay.get_game().get_pending_choice(&choice_id).is_none());
+    }
+
+    #[test]
+    fn replaying_part_way_matches_the_game_at_that_point() {
+        let (short_game, _) = play_game(5);
+        let (long_game, _) = play_game(30);
+
+        let mut replay = Replay::new(long_game.get_game_log().clone()).unwrap();
+        for _ in 0..short_game.get_game_log().get_entries().len() {
+            replay.step().unwrap();
+        }
+        assert_eq!(snapshot(replay.get_game()), snapshot(&short_game));
+    }
+
+    #[test]
+    fn paused_log_records_nothing() {
+        let mut log = GameLog::new(1);
+        log.record(LogAction::PlayerChoice { player_id: Uuid::nil(), choice_id: Uuid::nil(), selected: vec![] });
+        log.set_paused(true);
+        log.record(LogAction::PlayerChoice { player_id: Uuid::nil(), choice_id: Uuid::nil(), selected: vec![] });
+        assert_eq!(log.get_entries().len(), 1);
+
+        log.set_paused(false);
+        log.record(LogAction::PlayerChoice { player_id: Uuid::nil(), choice_id: Uuid::nil(), selected: vec![] });
+        assert_eq!(log.get_entries().iter().map(|entry| entry.sequence).collect::<Vec<_>>(), vec![0, 1]);
+    }
+}
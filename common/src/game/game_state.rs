@@ -1,15 +1,19 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GameState {
     round: i32,
-    phase: Phase
+    phase: Phase,
+    turn_order: Vec<Uuid>,
+    current_turn: usize,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Phase {
     Round,
-    Battle
+    Battle,
+    GameOver,
 }
 
 
@@ -18,6 +22,165 @@ impl GameState {
         Self {
             round: 1,
             phase: Phase::Round,
+            turn_order: vec![],
+            current_turn: 0,
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_round(&self) -> i32 {
+        self.round
+    }
+
+    pub fn get_phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    pub fn get_turn_order(&self) -> &Vec<Uuid> {
+        &self.turn_order
+    }
+
+    pub fn set_turn_order(&mut self, turn_order: Vec<Uuid>) {
+        self.turn_order = turn_order;
+        self.current_turn = 0;
+    }
+
+    /// Nothing more can happen once the game is over, whatever phase it was in
+    pub fn end_game(&mut self) {
+        self.phase = Phase::GameOver;
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.phase == Phase::GameOver
+    }
+
+    /// The player whose turn it is (None before the game has started)
+    pub fn get_current_player(&self) -> Option<&Uuid> {
+        self.turn_order.get(self.current_turn)
+    }
+
+    /// Move on to the next player, starting a new round once everyone has had a go
+    pub fn next_turn(&mut self) {
+        if self.turn_order.is_empty() {
+            return;
+        }
+        self.current_turn += 1;
+        if self.current_turn >= self.turn_order.len() {
+            self.current_turn = 0;
+            self.round += 1;
+        }
+    }
+
+    /// Take a player out of the turn order without skipping whoever is next
+    pub fn remove_from_turn_order(&mut self, player_id: &Uuid) {
+        if let Some(index) = self.turn_order.iter().position(|id| id == player_id) {
+            self.turn_order.remove(index);
+            if index < self.current_turn {
+                self.current_turn -= 1;
+            }
+            // It was the last players turn, so it goes back round to the start like next_turn does
+            if self.current_turn >= self.turn_order.len() {
+                self.current_turn = 0;
+                if !self.turn_order.is_empty() {
+                    self.round += 1;
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: u128) -> Vec<Uuid> {
+        (1..=count).map(Uuid::from_u128).collect()
+    }
+
+    fn state_with(turn_order: &[Uuid], turns_taken: usize) -> GameState {
+        let mut state = GameState::new();
+        state.set_turn_order(turn_order.to_vec());
+        for _ in 0..turns_taken {
+            state.next_turn();
+        }
+        state
+    }
+
+    #[test]
+    fn nobody_has_a_turn_before_the_game_starts() {
+        let mut state = GameState::new();
+        assert_eq!(state.get_current_player(), None);
+        state.next_turn();
+        assert_eq!(state.get_current_player(), None);
+        assert_eq!(state.get_round(), 1);
+    }
+
+    #[test]
+    fn end_game_is_game_over() {
+        let mut state = state_with(&ids(2), 1);
+        assert!(!state.is_game_over());
+        state.end_game();
+        assert!(state.is_game_over());
+        assert_eq!(state.get_phase(), &Phase::GameOver);
+    }
+
+    #[test]
+    fn next_turn_goes_round_the_order_and_counts_rounds() {
+        let players = ids(3);
+        let mut state = state_with(&players, 0);
+        assert_eq!(state.get_current_player(), Some(&players[0]));
+
+        state.next_turn();
+        state.next_turn();
+        assert_eq!(state.get_current_player(), Some(&players[2]));
+        assert_eq!(state.get_round(), 1);
+
+        state.next_turn();
+        assert_eq!(state.get_current_player(), Some(&players[0]));
+        assert_eq!(state.get_round(), 2);
+    }
+
+    #[test]
+    fn removing_someone_before_the_current_player_keeps_the_turn() {
+        let players = ids(3);
+        let mut state = state_with(&players, 2);
+        state.remove_from_turn_order(&players[0]);
+        assert_eq!(state.get_current_player(), Some(&players[2]));
+    }
+
+    #[test]
+    fn removing_the_current_player_passes_the_turn_on() {
+        let players = ids(3);
+        let mut state = state_with(&players, 1);
+        state.remove_from_turn_order(&players[1]);
+        assert_eq!(state.get_current_player(), Some(&players[2]));
+    }
+
+    #[test]
+    fn removing_the_last_player_in_the_order_on_their_turn_wraps_round() {
+        let players = ids(3);
+        let mut state = state_with(&players, 2);
+        state.remove_from_turn_order(&players[2]);
+        assert_eq!(state.get_current_player(), Some(&players[0]));
+        assert_eq!(state.get_round(), 2);
+    }
+
+    #[test]
+    fn removing_someone_not_in_the_order_changes_nothing() {
+        let players = ids(2);
+        let mut state = state_with(&players, 1);
+        state.remove_from_turn_order(&Uuid::from_u128(99));
+        assert_eq!(state.get_turn_order(), &players);
+        assert_eq!(state.get_current_player(), Some(&players[1]));
+    }
+
+    #[test]
+    fn removing_everyone_leaves_no_current_player() {
+        let players = ids(2);
+        let mut state = state_with(&players, 1);
+        for player_id in &players {
+            state.remove_from_turn_order(player_id);
+        }
+        assert_eq!(state.get_current_player(), None);
+    }
+}
@@ -0,0 +1,56 @@
+// What one player is allowed to see of the game
+// Sent to each player (and bot) separately so nobody gets to see the other hands
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::game_state::Phase;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameView {
+    pub player_id: Uuid,
+    pub hand: Vec<CardView>,
+    /// Every player in turn order, including the one this view is for
+    pub players: Vec<PlayerView>,
+    pub draw_pile_size: usize,
+    pub character_pile_size: usize,
+    pub discard_pile_size: usize,
+    pub round: i32,
+    pub phase: Phase,
+    pub current_player: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerView {
+    pub player_id: Uuid,
+    pub name: String,
+    pub hand_count: usize,
+    /// Cards this player has in play
+    pub table: Vec<CardView>,
+    /// False while their connection is lost and their seat is being kept for them
+    pub is_connected: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CardView {
+    pub instance_id: Uuid,
+    pub card_id: String,
+    pub card_type: String,
+    pub name: String,
+    pub description: String,
+    /// File name of the image inside the deck's folder
+    pub img_path: String,
+    pub damage: i32,
+    pub synergy_card_id: Option<String>,
+    pub synergy_damage: i32,
+}
+
+impl GameView {
+    pub fn is_my_turn(&self) -> bool {
+        self.current_player == Some(self.player_id)
+    }
+
+    pub fn get_player(&self, player_id: &Uuid) -> Option<&PlayerView> {
+        self.players.iter().find(|p| &p.player_id == player_id)
+    }
+}
@@ -1,5 +1,8 @@
 pub mod card;
+pub mod choice;
 pub mod deck;
 pub mod event_manager;
+pub mod game_log;
 pub mod game_state;
+pub mod game_view;
 pub mod player;
\ No newline at end of file
@@ -29,10 +29,63 @@ impl Player {
         self.hand.push(card_intance_id);
     }
     
+    pub fn get_table(&self) -> &Vec<Uuid> {
+        &self.table
+    }
+
+    /// Remove a card from the hand, returns false if it wasn't there
+    pub fn remove_from_hand(&mut self, card_instance_id: &Uuid) -> bool {
+        if let Some(index) = self.hand.iter().position(|id| id == card_instance_id) {
+            self.hand.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove a card from the table, returns false if it wasn't there
+    pub fn remove_from_table(&mut self, card_instance_id: &Uuid) -> bool {
+        if let Some(index) = self.table.iter().position(|id| id == card_instance_id) {
+            self.table.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move a card from the hand onto the table in front of the player
+    pub fn move_to_table(&mut self, card_instance_id: &Uuid) -> bool {
+        if self.remove_from_hand(card_instance_id) {
+            self.table.push(*card_instance_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Put a card on the table at a given spot (e.g. next to the character it was played onto, which might not be ours)
+    pub fn add_to_table_at(&mut self, card_instance_id: Uuid, index: usize) {
+        self.table.insert(index.min(self.table.len()), card_instance_id);
+    }
+
+    /// Empty the players table, returning the instance ids that were on it
+    pub fn take_table(&mut self) -> Vec<Uuid> {
+        std::mem::take(&mut self.table)
+    }
+
+    /// Empty the players hand, returning the instance ids that were in it
+    pub fn take_hand(&mut self) -> Vec<Uuid> {
+        std::mem::take(&mut self.hand)
+    }
+    
     pub fn get_id(&self) -> &Uuid {
         &self.id
     }
 
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
     pub fn add_status_effect(&mut self, effect: Status) {
         &self.status_effects.push(effect);
     }
@@ -49,7 +102,12 @@ impl PlayerManager {
 
     pub fn create_player(&mut self, name: String) -> Uuid{
         let id = Uuid::new_v4();
-        let id_to_ret = id.clone();
+        self.add_player(id, name);
+        id
+    }
+
+    /// Add a player using an id that already exists (e.g. the one the client registered with)
+    pub fn add_player(&mut self, id: Uuid, name: String) {
         let new_player = Player {
             id: id,
             name: name,
@@ -58,7 +116,6 @@ impl PlayerManager {
             status_effects: vec![]
         };
         self.players.insert(id, new_player);
-        id_to_ret
     }
 
     pub fn get_player_by_id(&self, id: &Uuid) -> Option<&Player> {
@@ -70,6 +127,10 @@ impl PlayerManager {
         self.players.get_mut(id)
     }
 
+    pub fn get_player_ids(&self) -> Vec<Uuid> {
+        self.players.keys().cloned().collect()
+    }
+
     pub fn remove_player(&mut self, id: &Uuid) {
         self.players.remove(id);
     }
@@ -1,9 +1,12 @@
-use std::{collections::HashMap, fs::{self, read_dir}};
+use std::{collections::{BTreeMap, HashMap}, fs::{self, read_dir}};
 
 use game::card::{CardManager, Card};
+use game::choice::{ChoiceAction, ChoiceOption, ChoiceRequest, CHOICE_TIMEOUT_SECS};
 use game::deck::DeckManager;
-use game::event_manager::{DrawSelector, Event, EventManager};
+use game::event_manager::{CardEvent, DrawSelector, Event, EventManager};
+use game::game_log::{GameLog, LogAction};
 use game::game_state::GameState;
+use game::game_view::{CardView, GameView, PlayerView};
 use game::player::PlayerManager;
 use uuid::Uuid;
 
@@ -19,19 +22,41 @@ pub struct GameManger {
     game_state: GameState,
     player_manager: PlayerManager,
     event_manager: EventManager,
+    game_log: GameLog,
+    pending_choices: HashMap<Uuid, (Uuid, ChoiceRequest, ChoiceAction)>, // Choice id, (player id, what they were asked, what to do with the answer)
 }
 
 impl GameManger {
     pub fn new() -> Self {
+        // Pick a seed at random, it gets stored in the log so the game can still be replayed
+        let seed = Uuid::new_v4().as_u64_pair().0;
+        Self::with_seed(seed)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        let mut deck_manager = DeckManager::new();
+        deck_manager.seed(seed);
+
         Self {
-            deck_manager: DeckManager::new(),
+            deck_manager,
             card_manager: CardManager::new(),
             game_state: GameState::new(),
             player_manager: PlayerManager::new(),
             event_manager: EventManager::new(),
+            game_log: GameLog::new(seed),
+            pending_choices: HashMap::new(),
         }
     }
 
+    pub fn get_game_log(&self) -> &GameLog {
+        &self.game_log
+    }
+
+    /// Games that won't be replayed don't need to keep every action
+    pub fn set_logging(&mut self, enabled: bool) {
+        self.game_log.set_paused(!enabled);
+    }
+
     pub fn get_deck_manager(&self) -> &DeckManager {
         &self.deck_manager
     }
@@ -48,26 +73,60 @@ impl GameManger {
         &mut self.deck_manager
     }
 
-    pub fn load_deck(&mut self, deck_path: &str) {
-        // Pass all cards in and register them with the card manager
-        
+    pub fn get_player_manager(&self) -> &PlayerManager {
+        &self.player_manager
+    }
 
-        // Load in the cards - the deck info will be in pack_info.json
-        let paths = read_dir(deck_path).unwrap();
+    pub fn get_game_state(&self) -> &GameState {
+        &self.game_state
+    }
 
-        for path in paths {
-            if path.as_ref().unwrap().path().extension().unwrap() == "json" {
-                if !(path.as_ref().unwrap().path().file_name().unwrap() == "deck_info.json") {
-                    //Register this card with the card Manager
-                    self.card_manager.register_card(path.as_ref().unwrap().path().to_str().unwrap());
-                }
-                
+    pub fn has_started(&self) -> bool {
+        self.game_state.get_current_player().is_some()
+    }
+
+    /// Damage a player would deal if a battle happened now
+    /// Weapons only add their synergy damage when the matching character is also in play
+    pub fn battle_score(&self, player_id: &Uuid) -> i32 {
+        let Some(player) = self.player_manager.get_player_by_id(player_id) else {
+            return 0;
+        };
+
+        let cards: Vec<&Card> = player.get_table().iter()
+            .filter_map(|instance_id| self.card_manager.get_card_from_instance_id(instance_id))
+            .collect();
+
+        let mut score = 0;
+        for card in &cards {
+            score += card.get_damage();
+            if let Some((synergy_card_id, synergy_damage)) = card.get_synergy()
+                && cards.iter().any(|c| c.get_card_id() == synergy_card_id) {
+                score += synergy_damage;
+            }
+        }
+        score
+    }
+
+    /// Register every card in the deck folder and fill the piles from its deck_info.json
+    /// This reads from disk, so servers should call it before taking any locks
+    pub fn load_deck(&mut self, deck_path: &str) -> Result<(), String> {
+        self.game_log.set_deck_path(deck_path.to_owned());
+
+        // Load in the cards - every json file apart from deck_info.json is a card
+        let entries = read_dir(deck_path).map_err(|e| format!("Couldn't open deck {}: {}", deck_path, e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| format!("Couldn't read deck {}: {}", deck_path, e))?.path();
+            let is_card = path.extension().is_some_and(|ext| ext == "json") && path.file_name().is_some_and(|name| name != "deck_info.json");
+            if is_card {
+                //Register this card with the card Manager
+                self.card_manager.register_card(&path.to_string_lossy())?;
             }
         }
 
         // Open the deck_info.json then populate the piles as specified
-        let data = fs::read_to_string(deck_path.to_owned() + "/deck_info.json").expect("Could not read deck_info.json");
-        let deck_info: HashMap<String, usize> = serde_json::from_str(&data).expect("JSON was not well-formatted");
+        let data = fs::read_to_string(format!("{}/deck_info.json", deck_path)).map_err(|e| format!("Could not read deck_info.json in {}: {}", deck_path, e))?;
+        // Sorted so the piles are always built in the same order (needed for replays)
+        let deck_info: BTreeMap<String, usize> = serde_json::from_str(&data).map_err(|e| format!("deck_info.json in {} is not well-formatted: {}", deck_path, e))?;
 
         for (card_id, count) in deck_info {
             for _ in 0..count {
@@ -85,21 +144,254 @@ impl GameManger {
                             self.deck_manager.add_item_draw_pile(card_id.clone());
                         }
                     }
-                    println!("Card ID {} succesfully added to the draw pile!", card_id)
                 } else {
                     println!("Card ID {} not found in registry", card_id)
                 }
             }
         }
+        Ok(())
     }
 
 
+    /// Add a player with a known id to the game
+    pub fn add_player(&mut self, player_id: Uuid, player_name: String) {
+        self.game_log.record(LogAction::PlayerCreated { player_id, player_name: player_name.clone() });
+        self.player_manager.add_player(player_id, player_name);
+    }
+
+    /// The game ends when a turn ends with nothing left to draw, or when only one player is left
+    pub fn is_game_over(&self) -> bool {
+        self.game_state.is_game_over()
+    }
+
+    /// Take a player out of the game (their cards get discarded)
+    pub fn remove_player(&mut self, player_id: Uuid) {
+        if self.player_manager.get_player_by_id(&player_id).is_some() {
+            self.apply_event(Event::RemovePlayer { player_id });
+        }
+    }
+
+    /// Every change to the game should go through here so it ends up in the log
+    pub fn apply_event(&mut self, event: Event) {
+        self.game_log.record(LogAction::Event(event.clone()));
+        self.event_manager.handle_event(event, &mut self.player_manager, &mut self.game_state, &mut self.card_manager, &mut self.deck_manager);
+    }
+
+    /// Deal everyone in and give the first turn to the first player in the order
+    pub fn start_game(&mut self, turn_order: Vec<Uuid>) -> Result<(), String> {
+        if self.has_started() {
+            return Err("Game has already started".to_string());
+        }
+        if turn_order.is_empty() {
+            return Err("No players to start the game with".to_string());
+        }
+        self.apply_event(Event::StartGame { turn_order });
+        Ok(())
+    }
+
+    /// A player wants to play a card from their hand, optionally onto a character on someone's table
+    /// Some cards need the player to pick something once played, that request is handed back to be sent to them
+    pub fn play_card(&mut self, player_id: Uuid, card_instance_id: Uuid, target: Option<Uuid>) -> Result<Option<ChoiceRequest>, String> {
+        if self.is_game_over() {
+            return Err("The game is over".to_string());
+        }
+        if self.game_state.get_current_player() != Some(&player_id) {
+            return Err("It is not your turn".to_string());
+        }
+        let Some(player) = self.player_manager.get_player_by_id(&player_id) else {
+            return Err("You are not in the game".to_string());
+        };
+        if !player.get_hand().contains(&card_instance_id) {
+            return Err("That card is not in your hand".to_string());
+        }
+        // Whether a card does anything to the character it's played onto is up to the card, it just has to be a character in play
+        if let Some(target) = target {
+            let is_character = matches!(self.card_manager.get_card_from_instance_id(&target), Some(Card::Character(_)));
+            let on_a_table = self.game_state.get_turn_order().iter()
+                .filter_map(|id| self.player_manager.get_player_by_id(id))
+                .any(|owner| owner.get_table().contains(&target));
+            if !is_character || !on_a_table {
+                return Err("Cards can only be played onto a character on the table".to_string());
+            }
+        }
+        // Items are gone once played, so get what card it is first
+        let card_id = self.card_manager.get_card_from_instance_id(&card_instance_id)
+            .map(|card| card.get_card_id().clone())
+            .unwrap_or_default();
+        self.apply_event(Event::CardPlayed(CardEvent { card_instance_id, player_id, target }));
+        Ok(self.choice_for_card(player_id, &card_id))
+    }
+
+    // Cards that need the player to pick something after they've been played
+    fn choice_for_card(&mut self, player_id: Uuid, card_id: &str) -> Option<ChoiceRequest> {
+        let (title, options, action) = match card_id {
+            "addon_remover" => {
+                // Any addon on anyone's table
+                let options: Vec<ChoiceOption> = self.game_state.get_turn_order().iter()
+                    .filter_map(|id| self.player_manager.get_player_by_id(id))
+                    .flat_map(|player| player.get_table().iter())
+                    .filter(|instance_id| matches!(self.card_manager.get_card_from_instance_id(instance_id), Some(Card::Addon(_))))
+                    .filter_map(|instance_id| self.card_view(instance_id))
+                    .map(ChoiceOption::Card)
+                    .collect();
+                ("Pick an addon to remove", options, ChoiceAction::DiscardFromTable)
+            },
+            _ => return None,
+        };
+        // Nothing to pick from, so the card just does nothing
+        if options.is_empty() {
+            return None;
+        }
+
+        // Not from the seeded generator, the whole request goes into the log so a replay never has to make the id again
+        let request = ChoiceRequest {
+            choice_id: Uuid::new_v4(),
+            title: title.to_string(),
+            options,
+            min: 1,
+            max: 1,
+            timeout_secs: Some(CHOICE_TIMEOUT_SECS),
+        };
+        self.ask_choice(player_id, request.clone(), action);
+        Some(request)
+    }
+
+    /// Wait on a player to answer a request, what they pick gets used for the action
+    pub fn ask_choice(&mut self, player_id: Uuid, request: ChoiceRequest, action: ChoiceAction) {
+        self.game_log.record(LogAction::ChoiceAsked { player_id, request: request.clone(), action: action.clone() });
+        self.pending_choices.insert(request.choice_id, (player_id, request, action));
+    }
+
+    /// The player and request for a choice that hasn't been answered yet
+    pub fn get_pending_choice(&self, choice_id: &Uuid) -> Option<(&Uuid, &ChoiceRequest)> {
+        self.pending_choices.get(choice_id).map(|(player_id, request, _)| (player_id, request))
+    }
+
+    /// Every choice a player still has to answer, e.g. to send again after they reconnect
+    pub fn pending_choices_for(&self, player_id: &Uuid) -> Vec<&ChoiceRequest> {
+        self.pending_choices.values()
+            .filter(|(asked, _, _)| asked == player_id)
+            .map(|(_, request, _)| request)
+            .collect()
+    }
+
+    /// A player has answered a choice, check it is one they were asked and carry it out
+    pub fn answer_choice(&mut self, player_id: Uuid, choice_id: Uuid, selected: Vec<Uuid>) -> Result<(), String> {
+        let action = match self.pending_choices.get(&choice_id) {
+            Some((asked, request, action)) if *asked == player_id => {
+                request.check(&selected)?;
+                action.clone()
+            },
+            _ => return Err("You weren't asked that".to_string()),
+        };
+        self.pending_choices.remove(&choice_id);
+
+        self.game_log.record(LogAction::PlayerChoice { player_id, choice_id, selected: selected.clone() });
+        // The choice is what goes in the log, replaying it does all of this again
+        match action {
+            ChoiceAction::DiscardFromTable => {
+                for card_instance_id in selected {
+                    self.event_manager.handle_event(Event::DiscardFromTable { card_instance_id }, &mut self.player_manager, &mut self.game_state, &mut self.card_manager, &mut self.deck_manager);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    pub fn end_turn(&mut self, player_id: Uuid) -> Result<(), String> {
+        if self.is_game_over() {
+            return Err("The game is over".to_string());
+        }
+        if self.game_state.get_current_player() != Some(&player_id) {
+            return Err("It is not your turn".to_string());
+        }
+        self.apply_event(Event::EndTurn { player_id });
+        Ok(())
+    }
+
+    /// What a player gets to know about a card in play
+    pub fn card_view(&self, instance_id: &Uuid) -> Option<CardView> {
+        let card = self.card_manager.get_card_from_instance_id(instance_id)?;
+        Some(CardView {
+            instance_id: *instance_id,
+            card_id: card.get_card_id().clone(),
+            card_type: card.get_card_type().to_string(),
+            name: card.get_name().clone(),
+            description: card.get_description().clone(),
+            img_path: card.get_img_file_name().to_owned(),
+            damage: card.get_damage(),
+            synergy_card_id: card.get_synergy().map(|(id, _)| id.clone()),
+            synergy_damage: card.get_synergy().map(|(_, damage)| damage).unwrap_or(0),
+        })
+    }
+
+    /// Everything the given player is allowed to see
+    pub fn player_view(&self, player_id: &Uuid) -> GameView {
+        let card_views = |instance_ids: &Vec<Uuid>| -> Vec<CardView> {
+            instance_ids.iter()
+                .filter_map(|instance_id| self.card_view(instance_id))
+                .collect()
+        };
+
+        // Players are listed in turn order, or in any order if the game hasn't started yet
+        let mut player_ids = self.game_state.get_turn_order().clone();
+        for id in self.player_manager.get_player_ids() {
+            if !player_ids.contains(&id) {
+                player_ids.push(id);
+            }
+        }
+
+        let players = player_ids.iter()
+            .filter_map(|id| self.player_manager.get_player_by_id(id))
+            .map(|player| PlayerView {
+                player_id: *player.get_id(),
+                name: player.get_name().clone(),
+                hand_count: player.get_hand().len(),
+                table: card_views(player.get_table()),
+                // The game doesn't know about connections, the room fills this in
+                is_connected: true,
+            })
+            .collect();
+
+        let hand = self.player_manager.get_player_by_id(player_id)
+            .map(|player| card_views(player.get_hand()))
+            .unwrap_or_default();
+
+        GameView {
+            player_id: *player_id,
+            hand,
+            players,
+            draw_pile_size: self.deck_manager.get_item_draw_pile().len(),
+            character_pile_size: self.deck_manager.get_character_draw_pile().len(),
+            discard_pile_size: self.deck_manager.get_discard_pile().len(),
+            round: self.game_state.get_round(),
+            phase: self.game_state.get_phase().clone(),
+            // Nobody has a turn once the game is over
+            current_player: self.game_state.get_current_player().filter(|_| !self.is_game_over()).cloned(),
+        }
+    }
+
+
+
     pub fn test_create_player(&mut self) -> Uuid {
-        let test_player_id = self.player_manager.create_player("test".to_owned());
+        let test_player_id = Uuid::new_v4();
+        self.add_player(test_player_id, "test".to_owned());
         test_player_id
     }
 
 
+    /// Put a particular card straight into a players hand, skipping the draw pile
+    pub fn test_give_card(&mut self, player_id: Uuid, card_id: &str) -> Option<Uuid> {
+        let mut card = self.card_manager.get_card_from_id_clone(card_id)?;
+        let player = self.player_manager.get_player_by_id_mut(&player_id)?;
+        let instance_id = self.deck_manager.gen_instance_id();
+        card.set_instance_id(instance_id);
+        self.card_manager.instansiate_card(&instance_id, card);
+        player.add_card_instance_id_to_hand(instance_id);
+        Some(instance_id)
+    }
+
+
     pub fn test_draw_pile(&mut self, player_id: Uuid) -> Option<Vec<Uuid>> {
         
         //Add all of the current cards in the players hand to the discard pile and delete them from the card_manager
@@ -115,16 +407,329 @@ impl GameManger {
 
         //Clear the players hand
         
-        self.event_manager.handle_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, &mut self.player_manager, &self.game_state, &mut self.card_manager, &mut self.deck_manager);
-        self.event_manager.handle_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, &mut self.player_manager, &self.game_state, &mut self.card_manager, &mut self.deck_manager);
-        self.event_manager.handle_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, &mut self.player_manager, &self.game_state, &mut self.card_manager, &mut self.deck_manager);
-        self.event_manager.handle_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, &mut self.player_manager, &self.game_state, &mut self.card_manager, &mut self.deck_manager);
-        self.event_manager.handle_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, &mut self.player_manager, &self.game_state, &mut self.card_manager, &mut self.deck_manager);
-        self.event_manager.handle_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random }, &mut self.player_manager, &self.game_state, &mut self.card_manager, &mut self.deck_manager);
+        self.apply_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random });
+        self.apply_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random });
+        self.apply_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random });
+        self.apply_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random });
+        self.apply_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random });
+        self.apply_event(Event::DrawCard { player_id: player_id, pile: "standard".to_owned(), selector: DrawSelector::Random });
 
         if let Some(player) = self.player_manager.get_player_by_id(&player_id) {
             return Some(player.get_hand().to_vec())
         } 
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, fs, path::Path};
+
+    use game::event_manager::STARTING_HAND_SIZE;
+
+    use super::*;
+
+    const BASE_DECK: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/decks/base_deck");
+
+    fn base_deck_ids() -> Vec<String> {
+        let data = fs::read_to_string(format!("{}/deck_info.json", BASE_DECK)).unwrap();
+        let deck_info: BTreeMap<String, usize> = serde_json::from_str(&data).unwrap();
+        deck_info.into_keys().collect()
+    }
+
+    fn give_card(game: &mut GameManger, player_id: Uuid, card_id: &str) -> Uuid {
+        game.test_give_card(player_id, card_id).unwrap()
+    }
+
+    fn started_game(players: usize) -> (GameManger, Vec<Uuid>) {
+        let mut game = GameManger::with_seed(1);
+        game.load_deck(BASE_DECK).unwrap();
+        let player_ids: Vec<Uuid> = (0..players).map(|_| game.test_create_player()).collect();
+        game.start_game(player_ids.clone()).unwrap();
+        // Clear out the dealt cards so the tests only see their own
+        for player_id in &player_ids {
+            game.apply_event(Event::DiscardHand { player_id: *player_id });
+        }
+        (game, player_ids)
+    }
+
+    fn table(game: &GameManger, player_id: &Uuid) -> Vec<Uuid> {
+        game.get_player_manager().get_player_by_id(player_id).unwrap().get_table().clone()
+    }
+
+    fn hand_size(game: &GameManger, player_id: &Uuid) -> usize {
+        game.get_player_manager().get_player_by_id(player_id).unwrap().get_hand().len()
+    }
+
+    #[test]
+    fn start_game_deals_everyone_in() {
+        let mut game = GameManger::with_seed(1);
+        game.load_deck(BASE_DECK).unwrap();
+        let players: Vec<Uuid> = (0..3).map(|_| game.test_create_player()).collect();
+        game.start_game(players.clone()).unwrap();
+
+        for player_id in &players {
+            assert_eq!(hand_size(&game, player_id), STARTING_HAND_SIZE + 1);
+        }
+        assert_eq!(game.get_game_state().get_current_player(), Some(&players[0]));
+        assert!(game.start_game(players).is_err());
+    }
+
+    #[test]
+    fn start_game_needs_players() {
+        let mut game = GameManger::with_seed(1);
+        game.load_deck(BASE_DECK).unwrap();
+        assert!(game.start_game(vec![]).is_err());
+        assert!(!game.has_started());
+    }
+
+    #[test]
+    fn loading_a_missing_deck_is_an_error() {
+        let mut game = GameManger::with_seed(1);
+        assert!(game.load_deck(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/decks/no_such_deck")).is_err());
+    }
+
+    #[test]
+    fn only_the_current_player_can_act_and_the_next_one_draws() {
+        let (mut game, players) = started_game(2);
+        let card = give_card(&mut game, players[1], "dog");
+
+        assert!(game.end_turn(players[1]).is_err());
+        assert!(game.play_card(players[1], card, None).is_err());
+
+        game.end_turn(players[0]).unwrap();
+        assert_eq!(game.get_game_state().get_current_player(), Some(&players[1]));
+        // The dog plus the card drawn at the start of the turn
+        assert_eq!(hand_size(&game, &players[1]), 2);
+    }
+
+    #[test]
+    fn items_are_used_up_and_everything_else_stays_out() {
+        let (mut game, players) = started_game(1);
+        let player_id = players[0];
+        let potion = give_card(&mut game, player_id, "health_potion");
+        let dog = give_card(&mut game, player_id, "dog");
+        let discarded = game.get_deck_manager().get_discard_pile().len();
+
+        game.play_card(player_id, potion, None).unwrap();
+        game.play_card(player_id, dog, None).unwrap();
+
+        assert_eq!(table(&game, &player_id), vec![dog]);
+        assert!(game.get_card_manager().get_card_from_instance_id(&potion).is_none());
+        assert_eq!(game.get_deck_manager().get_discard_pile().len(), discarded + 1);
+        assert!(game.play_card(player_id, potion, None).is_err());
+    }
+
+    #[test]
+    fn removed_players_lose_their_cards_and_their_turn() {
+        let (mut game, players) = started_game(3);
+        let dog = give_card(&mut game, players[0], "dog");
+        game.play_card(players[0], dog, None).unwrap();
+
+        game.remove_player(players[0]);
+        assert!(game.get_player_manager().get_player_by_id(&players[0]).is_none());
+        assert!(game.get_card_manager().get_card_from_instance_id(&dog).is_none());
+        assert_eq!(game.get_game_state().get_current_player(), Some(&players[1]));
+    }
+
+    #[test]
+    fn removing_the_current_player_starts_the_next_turn() {
+        let (mut game, players) = started_game(4);
+
+        // Someone leaving out of turn doesn't make anyone draw
+        game.remove_player(players[3]);
+        assert_eq!(hand_size(&game, &players[1]), 0);
+
+        game.remove_player(players[0]);
+        assert_eq!(game.get_game_state().get_current_player(), Some(&players[1]));
+        assert_eq!(hand_size(&game, &players[1]), 1);
+    }
+
+    #[test]
+    fn removing_the_current_player_with_nothing_left_to_draw_ends_the_game() {
+        let (mut game, players) = started_game(3);
+        while !game.get_deck_manager().get_item_draw_pile().is_empty() {
+            game.apply_event(Event::DrawCard { player_id: players[2], pile: "standard".to_owned(), selector: DrawSelector::Random });
+        }
+        game.remove_player(players[0]);
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn running_out_of_cards_ends_the_game_at_the_end_of_the_turn() {
+        let (mut game, players) = started_game(2);
+        while !game.get_deck_manager().get_item_draw_pile().is_empty() {
+            game.apply_event(Event::DrawCard { player_id: players[1], pile: "standard".to_owned(), selector: DrawSelector::Random });
+        }
+        let dog = give_card(&mut game, players[0], "dog");
+        game.play_card(players[0], dog, None).unwrap();
+        assert!(!game.is_game_over());
+
+        game.end_turn(players[0]).unwrap();
+        assert!(game.is_game_over());
+        assert!(game.end_turn(players[0]).is_err());
+        let cat = give_card(&mut game, players[1], "cat");
+        assert!(game.play_card(players[1], cat, None).is_err());
+        assert!(!game.player_view(&players[1]).is_my_turn());
+    }
+
+    #[test]
+    fn the_game_is_over_when_one_player_is_left() {
+        let (mut game, players) = started_game(3);
+        game.remove_player(players[0]);
+        assert!(!game.is_game_over());
+        game.remove_player(players[1]);
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn player_view_only_shows_your_own_hand() {
+        let (mut game, players) = started_game(2);
+        give_card(&mut game, players[0], "dog");
+        give_card(&mut game, players[1], "knight");
+
+        let view = game.player_view(&players[0]);
+        assert_eq!(view.hand.len(), 1);
+        assert_eq!(view.hand[0].card_id, "dog");
+        assert_eq!(view.players.iter().map(|p| p.player_id).collect::<Vec<_>>(), players);
+        assert_eq!(view.get_player(&players[1]).unwrap().hand_count, 1);
+        assert!(view.is_my_turn());
+    }
+
+    #[test]
+    fn battle_score_adds_synergy_only_with_the_matching_character() {
+        let (mut game, players) = started_game(1);
+        let player_id = players[0];
+        let bone = give_card(&mut game, player_id, "bone");
+        game.play_card(player_id, bone, None).unwrap();
+        let without_dog = game.battle_score(&player_id);
+
+        let dog = give_card(&mut game, player_id, "dog");
+        game.play_card(player_id, dog, None).unwrap();
+        let dog_damage = game.get_card_manager().get_card("dog").unwrap().get_damage();
+        assert_eq!(game.battle_score(&player_id), without_dog + dog_damage + 6);
+
+        assert_eq!(game.battle_score(&Uuid::new_v4()), 0);
+    }
+
+    #[test]
+    fn cards_played_onto_a_character_go_with_it() {
+        let (mut game, players) = started_game(1);
+        let player_id = players[0];
+        let dog = give_card(&mut game, player_id, "dog");
+        let knight = give_card(&mut game, player_id, "knight");
+        let bone = give_card(&mut game, player_id, "bone");
+        let fists = give_card(&mut game, player_id, "fists");
+
+        game.play_card(player_id, dog, None).unwrap();
+        game.play_card(player_id, knight, None).unwrap();
+        game.play_card(player_id, bone, Some(dog)).unwrap();
+        game.play_card(player_id, fists, None).unwrap();
+
+        assert_eq!(table(&game, &player_id), vec![dog, bone, knight, fists]);
+    }
+
+    #[test]
+    fn cards_can_be_played_onto_other_players_characters() {
+        let (mut game, players) = started_game(2);
+        let other_dog = give_card(&mut game, players[1], "dog");
+        game.player_manager.get_player_by_id_mut(&players[1]).unwrap().move_to_table(&other_dog);
+        let bone = give_card(&mut game, players[0], "bone");
+
+        game.play_card(players[0], bone, Some(other_dog)).unwrap();
+        assert!(table(&game, &players[0]).is_empty());
+        assert_eq!(table(&game, &players[1]), vec![other_dog, bone]);
+        assert_eq!(hand_size(&game, &players[0]), 0);
+    }
+
+    #[test]
+    fn cards_can_only_be_played_onto_characters_on_the_table() {
+        let (mut game, players) = started_game(2);
+        let bone = give_card(&mut game, players[0], "bone");
+        let dog_in_hand = give_card(&mut game, players[1], "dog");
+        let fists = give_card(&mut game, players[1], "fists");
+        game.player_manager.get_player_by_id_mut(&players[1]).unwrap().move_to_table(&fists);
+
+        assert!(game.play_card(players[0], bone, Some(bone)).is_err());
+        assert!(game.play_card(players[0], bone, Some(dog_in_hand)).is_err());
+        assert!(game.play_card(players[0], bone, Some(fists)).is_err());
+        assert!(game.play_card(players[0], bone, Some(Uuid::new_v4())).is_err());
+        assert!(table(&game, &players[0]).is_empty());
+        assert_eq!(table(&game, &players[1]), vec![fists]);
+    }
+
+    #[test]
+    fn addon_remover_asks_which_addon_and_discards_it() {
+        let (mut game, players) = started_game(2);
+        let addon = give_card(&mut game, players[1], "booster");
+        game.player_manager.get_player_by_id_mut(&players[1]).unwrap().move_to_table(&addon);
+        let remover = give_card(&mut game, players[0], "addon_remover");
+
+        let request = game.play_card(players[0], remover, None).unwrap().expect("no choice was asked for");
+        assert_eq!(request.options.len(), 1);
+        assert_eq!(request.options[0].id(), addon);
+
+        // Only the player that was asked can answer, and only with what they were offered
+        assert!(game.answer_choice(players[1], request.choice_id, vec![addon]).is_err());
+        assert!(game.answer_choice(players[0], request.choice_id, vec![remover]).is_err());
+
+        game.answer_choice(players[0], request.choice_id, vec![addon]).unwrap();
+        assert!(table(&game, &players[1]).is_empty());
+        assert!(game.get_card_manager().get_card_from_instance_id(&addon).is_none());
+        assert!(game.get_pending_choice(&request.choice_id).is_none());
+
+        // It can't be answered twice
+        assert!(game.answer_choice(players[0], request.choice_id, vec![addon]).is_err());
+    }
+
+    #[test]
+    fn pending_choices_are_listed_for_who_was_asked() {
+        let (mut game, players) = started_game(2);
+        let addon = give_card(&mut game, players[1], "booster");
+        game.player_manager.get_player_by_id_mut(&players[1]).unwrap().move_to_table(&addon);
+        let remover = give_card(&mut game, players[0], "addon_remover");
+        let request = game.play_card(players[0], remover, None).unwrap().unwrap();
+
+        let pending: Vec<Uuid> = game.pending_choices_for(&players[0]).iter().map(|r| r.choice_id).collect();
+        assert_eq!(pending, vec![request.choice_id]);
+        assert!(game.pending_choices_for(&players[1]).is_empty());
+
+        game.answer_choice(players[0], request.choice_id, vec![addon]).unwrap();
+        assert!(game.pending_choices_for(&players[0]).is_empty());
+    }
+
+    #[test]
+    fn addon_remover_with_no_addons_in_play_asks_nothing() {
+        let (mut game, players) = started_game(2);
+        let remover = give_card(&mut game, players[0], "addon_remover");
+        assert!(game.play_card(players[0], remover, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn card_images_are_in_the_deck_folder() {
+        let mut game = GameManger::with_seed(1);
+        game.load_deck(BASE_DECK).unwrap();
+
+        for card_id in base_deck_ids() {
+            let card = game.get_card_manager().get_card(&card_id).unwrap();
+            // This is the path the client builds from the CardView
+            let path = format!("{}/{}", BASE_DECK, card.get_img_file_name());
+            assert!(Path::new(&path).is_file(), "{} has no image at {}", card_id, path);
+        }
+    }
+
+    #[test]
+    fn player_view_only_sends_image_file_names() {
+        let mut game = GameManger::with_seed(1);
+        game.load_deck(BASE_DECK).unwrap();
+        let player_id = game.test_create_player();
+        game.start_game(vec![player_id]).unwrap();
+
+        let view = game.player_view(&player_id);
+        assert!(!view.hand.is_empty());
+        for card in &view.hand {
+            assert!(!card.img_path.contains('/'), "{} leaks the server path", card.img_path);
+            assert!(Path::new(BASE_DECK).join(&card.img_path).is_file());
+        }
+    }
+}
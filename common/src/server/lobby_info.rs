@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Everything the lobby screen needs to show before a game starts
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LobbyInfo {
+    pub host_id: Option<Uuid>,
+    pub room_code: String,
+    pub deck: String,
+    pub available_decks: Vec<String>,
+    pub players: Vec<LobbyPlayer>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LobbyPlayer {
+    pub player_id: Uuid,
+    pub player_name: String,
+    pub is_ready: bool,
+    pub is_bot: bool,
+    pub is_connected: bool,
+}
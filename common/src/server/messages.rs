@@ -6,11 +6,11 @@ use serde::{Serialize, Deserialize};
 
 use uuid::Uuid;
 
-use crate::{game::game_state::GameState, server::room_info::RoomInfo};
+use crate::{game::{choice::ChoiceRequest, game_state::GameState, game_view::GameView}, server::{lobby_info::LobbyInfo, room_info::RoomInfo}};
 
 #[derive(Serialize, Deserialize)]
 pub enum ClientToMatchmakingServer {
-    /// Get a list of public rooms
+    /// Get a list of public rooms, and be kept up to date with any changes to it
     GetRooms, 
 
     /// Create a new public room
@@ -19,24 +19,60 @@ pub enum ClientToMatchmakingServer {
     /// Get a rooms info
     GetRoomInfo {room_id: Uuid},
 
+    /// Find a room (public or private) from its room code
+    JoinByCode {room_code: String},
+
     Disconnect
 }
 
 #[derive(Serialize, Deserialize)]
 pub enum MatchmakingServerToClient {
     /// Return a list of public rooms - no connection info though
+    /// After this the client is sent every change to the list
     RoomDirectory(HashMap<Uuid, RoomInfo>),
 
+    /// A room has shown up in the directory
+    RoomAdded(RoomInfo),
+
+    /// Something about a listed room has changed (e.g. player count)
+    RoomUpdated(RoomInfo),
+
+    /// A room has gone from the directory (closed, started or made private)
+    RoomRemoved {room_id: Uuid},
+
     /// Let the client know that a room has been created 
     RoomCreated {room_id: Uuid},
 
     /// Give the client where to go next
     RoomInfo {server_address: String},
 
+    /// The room can't be joined
+    JoinError(JoinError),
+
     /// If something happens - room full, cant create room, etc
     Error(String)
 }
 
+/// Why a player wasn't let into a room
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum JoinError {
+    RoomNotFound,
+    RoomFull,
+    GameStarted,
+    SessionExpired,
+}
+
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JoinError::RoomNotFound => write!(f, "Room not found"),
+            JoinError::RoomFull => write!(f, "Room is full"),
+            JoinError::GameStarted => write!(f, "The game has already started"),
+            JoinError::SessionExpired => write!(f, "Your seat in the room has been given up"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum ClientToServer {
     /// Register a player to the room 
@@ -45,17 +81,84 @@ pub enum ClientToServer {
         player_id: Uuid,
     },
 
+    /// Take your seat back after losing connection, using the token from PlayerRegistered
+    Reconnect {
+        player_id: Uuid,
+        session_token: Uuid,
+    },
+
+    /// Join the room to watch, works even if the room is full or the game has started
+    RegisterSpectator {
+        player_name: String,
+        player_id: Uuid,
+    },
+
+    /// Toggle whether you are ready to start
+    SetReady {
+        ready: bool,
+    },
+
+    /// Pick which deck to play with (host only)
+    SelectDeck {
+        deck_name: String,
+    },
+
+    /// Start the game once everyone is ready (host only)
+    StartGame,
+
+    /// Remove a player from the room (host only)
+    KickPlayer {
+        player_id: Uuid,
+    },
+
+    /// Make someone else the host (host only)
+    TransferHost {
+        player_id: Uuid,
+    },
+
+    /// Fill an empty seat with a bot (host only)
+    AddBot {
+        difficulty: BotDifficulty,
+    },
+
+    /// Play a card from your hand, target is the character on your table to put it on (for addons and weapons)
+    PlayCard {
+        card_instance_id: Uuid,
+        target: Option<Uuid>,
+    },
+
+    /// Finish your turn
+    EndTurn,
+
+    /// Answer a ChoiceRequest with the ids of the options picked
+    MakeChoice {
+        choice_id: Uuid,
+        selected: Vec<Uuid>,
+    },
+
     // TODO - Add in all of the other possible actions
 
     /// Leave the room
     Disconnect
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BotDifficulty {
+    /// Plays at most one card a turn
+    Easy,
+    /// Plays whatever it feels like
+    Random,
+    /// Always plays the card that adds the most damage
+    Greedy,
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum ServerToClient {
     /// Acknowledgement of successful connection
     PlayerRegistered {
         player_id: Uuid,
+        /// Keep this to get your seat back if you lose connection
+        session_token: Uuid,
     },
 
     /// Another player has joined the room
@@ -69,9 +172,37 @@ pub enum ServerToClient {
         player_id: Uuid,
     },
 
+    /// A player has lost connection, their seat is kept for a while in case they come back
+    PlayerConnectionLost {
+        player_id: Uuid,
+    },
+
+    /// A player has come back after losing connection
+    PlayerReconnected {
+        player_id: Uuid,
+    },
+
+    /// The lobby has changed (someone joined, readied up, the deck changed, etc)
+    LobbyUpdate(LobbyInfo),
+
+    /// The host has started the game
+    GameStarted,
+
+    /// You have been kicked from the room by the host
+    Kicked,
+
+    /// You couldn't join the room
+    JoinRejected(JoinError),
+
     /// Update the entire game state
     GameStateUpdate(GameState),
 
+    /// Everything this player is allowed to see of the game
+    GameView(GameView),
+
+    /// The player has to pick something before the game can carry on, answer with MakeChoice
+    ChoiceRequest(ChoiceRequest),
+
     // TODO - Add in all of the other possible actions
 
 
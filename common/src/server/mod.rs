@@ -1,2 +1,3 @@
+pub mod lobby_info;
 pub mod messages;
 pub mod room_info;
\ No newline at end of file
@@ -1,12 +1,13 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct RoomInfo {
     pub id: Uuid,
     pub name: String,
     pub max_players: usize,
     pub player_count: usize,
+    pub spectator_count: usize,
     pub is_private: bool,
     pub has_started: bool,
 }
\ No newline at end of file
@@ -0,0 +1,213 @@
+// Bots that can take an empty seat in a room
+// They talk to the room through the same messages as a real client so they only ever see their own GameView
+
+use std::{sync::Arc, time::Duration};
+
+use common::{game::game_view::{CardView, GameView}, server::messages::{BotDifficulty, ClientToServer, ServerToClient}};
+use tokio::sync::{mpsc, RwLock};
+use tungstenite::Message;
+use uuid::Uuid;
+
+use crate::common::{handle_client_message, remove_player, Room};
+
+// How long a bot waits before doing something, so people can follow what it did
+const BOT_THINK_TIME: Duration = Duration::from_millis(750);
+
+/// Start a bot and have it join the room, returns the bots player id
+pub fn spawn_bot(room: Arc<RwLock<Room>>, difficulty: BotDifficulty) -> Uuid {
+    let bot_id = Uuid::new_v4();
+    tokio::spawn(run_bot(room, difficulty, bot_id));
+    bot_id
+}
+
+async fn run_bot(room: Arc<RwLock<Room>>, difficulty: BotDifficulty, bot_id: Uuid) {
+    // The room sends messages down this channel just like it would for a websocket
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    let mut local_player_id = None;
+
+    let register = ClientToServer::RegisterPlayer { player_name: format!("{:?} Bot", difficulty), player_id: bot_id };
+    handle_client_message(register, &mut local_player_id, &tx, &room, true).await;
+
+    // Only the room holds on to the sender, so the channel closes when the bot is removed from the room
+    let weak_tx = tx.downgrade();
+    drop(tx);
+
+    let mut played_this_turn = 0;
+    let mut last_action_was_play = false;
+
+    // Stops once the room drops the bots sender (e.g. it got kicked)
+    while let Some(msg) = rx.recv().await {
+        let Message::Text(text) = msg else {
+            continue;
+        };
+
+        let action = match serde_json::from_str::<ServerToClient>(&text) {
+            Ok(ServerToClient::GameView(view)) => {
+                if !view.is_my_turn() {
+                    played_this_turn = 0;
+                    continue;
+                }
+                choose_action(difficulty, &view, played_this_turn)
+            },
+            Ok(ServerToClient::ChoiceRequest(request)) => {
+                // Bots don't think about it, they take whatever is offered first
+                ClientToServer::MakeChoice { choice_id: request.choice_id, selected: request.default_answer() }
+            },
+            Ok(ServerToClient::Error(_)) if last_action_was_play => {
+                // The card couldn't be played, so give up the turn instead of trying again
+                ClientToServer::EndTurn
+            },
+            _ => continue,
+        };
+
+        tokio::time::sleep(BOT_THINK_TIME).await;
+
+        // It may have been kicked (or the room closed) while it was thinking
+        if !room.read().await.players.contains_key(&bot_id) {
+            break;
+        }
+
+        last_action_was_play = matches!(action, ClientToServer::PlayCard { .. });
+        match action {
+            ClientToServer::PlayCard { .. } => played_this_turn += 1,
+            ClientToServer::EndTurn => played_this_turn = 0,
+            _ => {},
+        }
+
+        let Some(tx) = weak_tx.upgrade() else {
+            break;
+        };
+        if !handle_client_message(action, &mut local_player_id, &tx, &room, true).await {
+            break;
+        }
+    }
+
+    // Make sure the bot doesn't stay in the room if it left on its own
+    if room.read().await.players.contains_key(&bot_id) {
+        remove_player(&room, local_player_id).await;
+    }
+}
+
+/// Pick what to do next using only what the bot can see
+fn choose_action(difficulty: BotDifficulty, view: &GameView, played_this_turn: usize) -> ClientToServer {
+    let card = match difficulty {
+        BotDifficulty::Easy => {
+            if played_this_turn == 0 { view.hand.first() } else { None }
+        },
+        BotDifficulty::Random => {
+            if !view.hand.is_empty() && rand::random_bool(0.5) {
+                view.hand.get(rand::random_range(0..view.hand.len()))
+            } else {
+                None
+            }
+        },
+        BotDifficulty::Greedy => {
+            view.hand.iter()
+                .map(|card| (card, card_value(card, view)))
+                .filter(|(_, value)| *value > 0)
+                .max_by_key(|(_, value)| *value)
+                .map(|(card, _)| card)
+        }
+    };
+
+    match card {
+        Some(card) => ClientToServer::PlayCard { card_instance_id: card.instance_id, target: None },
+        None => ClientToServer::EndTurn,
+    }
+}
+
+/// How much playing a card helps right now (0 means don't bother)
+fn card_value(card: &CardView, view: &GameView) -> i32 {
+    let table = view.get_player(&view.player_id).map(|p| p.table.as_slice()).unwrap_or(&[]);
+    let characters: Vec<&CardView> = table.iter().filter(|c| c.card_type == "Character").collect();
+
+    match card.card_type.as_str() {
+        // Only one character is worth having out
+        "Character" if characters.is_empty() => 10 + card.damage,
+        "Weapon" => {
+            let has_synergy = card.synergy_card_id.as_ref()
+                .map(|id| characters.iter().any(|c| &c.card_id == id))
+                .unwrap_or(false);
+            card.damage + if has_synergy { card.synergy_damage } else { 0 }
+        },
+        "Item" => 1,
+        _ => 0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use common::game::{game_state::Phase, game_view::PlayerView};
+
+    use super::*;
+
+    fn card(card_id: &str, card_type: &str, damage: i32, synergy: Option<(&str, i32)>) -> CardView {
+        CardView {
+            instance_id: Uuid::new_v4(),
+            card_id: card_id.to_string(),
+            card_type: card_type.to_string(),
+            name: card_id.to_string(),
+            description: String::new(),
+            img_path: format!("{}.png", card_id),
+            damage,
+            synergy_card_id: synergy.map(|(id, _)| id.to_string()),
+            synergy_damage: synergy.map(|(_, damage)| damage).unwrap_or(0),
+        }
+    }
+
+    fn view(hand: Vec<CardView>, table: Vec<CardView>) -> GameView {
+        let player_id = Uuid::new_v4();
+        GameView {
+            player_id,
+            hand,
+            players: vec![PlayerView { player_id, name: "Bot".to_string(), hand_count: 0, table, is_connected: true }],
+            draw_pile_size: 10,
+            character_pile_size: 5,
+            discard_pile_size: 0,
+            round: 1,
+            phase: Phase::Round,
+            current_player: Some(player_id),
+        }
+    }
+
+    fn played(action: ClientToServer) -> Option<Uuid> {
+        match action {
+            ClientToServer::PlayCard { card_instance_id, .. } => Some(card_instance_id),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn easy_bot_plays_one_card_a_turn() {
+        let view = view(vec![card("dog", "Character", 5, None), card("fork", "Weapon", 2, None)], vec![]);
+        assert_eq!(played(choose_action(BotDifficulty::Easy, &view, 0)), Some(view.hand[0].instance_id));
+        assert_eq!(played(choose_action(BotDifficulty::Easy, &view, 1)), None);
+    }
+
+    #[test]
+    fn greedy_bot_plays_the_weapon_that_suits_its_character() {
+        let bone = card("bone", "Weapon", 1, Some(("dog", 6)));
+        let fork = card("fork", "Weapon", 4, None);
+        let view = view(vec![fork, bone.clone()], vec![card("dog", "Character", 5, None)]);
+        assert_eq!(played(choose_action(BotDifficulty::Greedy, &view, 0)), Some(bone.instance_id));
+    }
+
+    #[test]
+    fn greedy_bot_ends_its_turn_when_nothing_helps() {
+        let view = view(vec![card("knight", "Character", 5, None), card("booster", "Addon", 0, None)], vec![card("dog", "Character", 5, None)]);
+        assert_eq!(played(choose_action(BotDifficulty::Greedy, &view, 0)), None);
+    }
+
+    #[test]
+    fn random_bot_only_plays_cards_it_has() {
+        let empty = view(vec![], vec![]);
+        let one_card = view(vec![card("dog", "Character", 5, None)], vec![]);
+        for _ in 0..20 {
+            if let Some(instance_id) = played(choose_action(BotDifficulty::Random, &one_card, 0)) {
+                assert_eq!(instance_id, one_card.hand[0].instance_id);
+            }
+        }
+        assert_eq!(played(choose_action(BotDifficulty::Random, &empty, 0)), None);
+    }
+}
@@ -1,189 +1,316 @@
 // This handles the central rooms that may be created - talks to a matchmaking server
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock};
 
-use common::server::room_info::RoomInfo;
+use common::server::{messages::{JoinError, ServerToClient}, room_info::RoomInfo};
 use futures_util::{SinkExt, StreamExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio_tungstenite::accept_async;
-use tungstenite::Message;
+use tokio_tungstenite::{accept_hdr_async, MaybeTlsStream, WebSocketStream};
+use tungstenite::{handshake::server::{Request, Response}, Message};
 use uuid::Uuid;
 
-use crate::{common::{handle_player_connection, Room}, messages::{GSToMM, MMToGS}};
+use crate::{common::{available_decks, handle_player_connection, send_to_mm, set_mm_sender, to_message, MMSender, Room, DEFAULT_DECK}, config::ServerConfig, heartbeat::Heartbeat, messages::{GSToMM, MMToGS, SyncedRoom}};
 
 //Need to have a room manager that has all of the rooms currently running - although each game will be its own seperate task
 pub struct CentralServer {
+    pub config: ServerConfig,
     pub rooms: HashMap<Uuid, Arc<RwLock<Room>>>,
-    pub avaliable_ports: Vec<u16>,
-    pub mm_sender: Option<mpsc::UnboundedSender<GSToMM>>
+    pub mm_sender: MMSender,
 }
 
 pub type SharedServer = Arc<RwLock<CentralServer>>;
 
-// Create room server and bind it to port 9002
-pub async fn run() {
-    let addr = "127.0.0.1:9002";
-    let listner = TcpListener::bind(&addr).await.expect("Can't bind");
+// How long a room can sit with nobody in it before it gets closed
+const ROOM_EMPTY_GRACE_PERIOD: Duration = Duration::from_secs(30);
+// How long a finished game stays up so everyone can see how it ended
+const GAME_OVER_GRACE_PERIOD: Duration = Duration::from_secs(60);
+// How often rooms get checked to see if they are empty
+const ROOM_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// How long to wait before trying to reach the matchmaking server again
+const MM_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// How often the matchmaking server is told how busy this server is
+const LOAD_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+// Register with the matchmaking server and take players on the player address
+// Every player gets sent to their room by the path they connect to (/room/<uuid>)
+pub async fn run(config: ServerConfig) -> Result<(), String> {
+    let player_listener = TcpListener::bind(&config.player_bind).await
+        .map_err(|e| format!("Can't bind player listener to {}: {}", config.player_bind, e))?;
+
+    println!("Central game server {} starting", config.server_id);
+    println!("Accepting players on ws://{} (advertised as {}/room/<room id>)", config.player_bind, config.public_address);
 
     let server = Arc::new(RwLock::new(CentralServer {
+        config,
         rooms: HashMap::new(),
-        avaliable_ports: vec![9003, 9004, 9005, 9006, 9007, 9008, 9009, 9010],
-        mm_sender: None,
+        mm_sender: MMSender::default(),
     }));
 
-    println!("Central game server running on ws://{}", addr);
+    let server_clone = Arc::clone(&server);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = player_listener.accept().await {
+            let server = Arc::clone(&server_clone);
+            tokio::spawn(async move {
+                // Whatever goes wrong with one player stays with that player
+                let peer = stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_else(|_| "unknown".to_string());
+                if let Err(e) = route_player_connection(stream, server).await {
+                    println!("Player connection from {}: {}", peer, e);
+                }
+            });
+        }
+    });
 
-    while let Ok((stream, _)) = listner.accept().await {
-        let server_clone = Arc::clone(&server);
-        tokio::spawn(handle_matchmaking_connection(stream, server_clone));
+    // Keep trying to (re)connect to the matchmaking server, the rooms keep running in the mean time
+    loop {
+        let mm_addr = server.read().await.config.matchmaking_server.clone();
+        match tokio_tungstenite::connect_async(&mm_addr).await {
+            Ok((ws_stream, _)) => {
+                println!("Connected to matchmaking server at {}", mm_addr);
+                match handle_matchmaking_connection(ws_stream, Arc::clone(&server)).await {
+                    Ok(()) => println!("Lost connection to the matchmaking server"),
+                    Err(e) => println!("Lost connection to the matchmaking server: {}", e),
+                }
+            },
+            Err(e) => println!("Failed to connect to matchmaking server at {}: {}", mm_addr, e),
+        }
+        set_mm_sender(&server.read().await.mm_sender, None);
+        tokio::time::sleep(MM_RECONNECT_DELAY).await;
     }
 }
 
 
 // Handle the connection to the matchmaking server
-async fn handle_matchmaking_connection(stream: TcpStream, server: SharedServer) {
-    let ws_stream = accept_async(stream).await.expect("WebSocket accept error");
-    println!("Match making server connected");
-
-    let (mut write, mut read) = ws_stream.split();
-
-    // Channel for sending messages to matchmaking server (write half)
-    let (to_write_tx, mut to_write_rx) = mpsc::unbounded_channel::<String>();
+async fn handle_matchmaking_connection(ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>, server: SharedServer) -> Result<(), String> {
+    let (mut write, read) = ws_stream.split();
 
     let (mm_tx, mut mm_rx) = mpsc::unbounded_channel::<GSToMM>();
+    let (ping_tx, mut ping_rx) = mpsc::unbounded_channel::<Message>();
 
-    // Save sender to server state
+    // Let the matchmaking server know who we are and how many rooms we can take
     {
-        let mut server_guard = server.write().await;
-        server_guard.mm_sender = Some(mm_tx);
+        let server_guard = server.read().await;
+        let _ = mm_tx.send(GSToMM::Register { server_id: server_guard.config.server_id, max_rooms: server_guard.config.max_rooms });
+        set_mm_sender(&server_guard.mm_sender, Some(mm_tx.clone()));
     }
 
-    // Task to forward messages from game server to matchmaking server
-    let mut write_clone = write;
-    tokio::spawn(async move {
-        while let Some(json) = to_write_rx.recv().await {
-            if write_clone.send(Message::Text(json.into())).await.is_err() {
+    // Task to forward GSToMM messages (and pings) to matchmaking server
+    let writer = tokio::spawn(async move {
+        loop {
+            let msg = tokio::select! {
+                Some(msg) = mm_rx.recv() => match to_message(&msg) {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                },
+                Some(ping) = ping_rx.recv() => ping,
+                else => break,
+            };
+            if write.send(msg).await.is_err() {
                 break;
             }
         }
     });
 
-    // Task to forward GSToMM messages to matchmaking server
-    let to_write_tx_clone = to_write_tx.clone();
-    tokio::spawn(async move {
-        while let Some(msg) = mm_rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&msg) {
-                let _ = to_write_tx_clone.send(json);
+    // Treat the matchmaking server as gone if it stops answering pings
+    let heartbeat = Heartbeat::from_config(&server.read().await.config);
+    let mut read = heartbeat.watch(read, ping_tx);
+
+    // Keep the matchmaking server up to date with how busy this server is, and resync every room while at it
+    let load_server = Arc::clone(&server);
+    let load_tx = mm_tx.clone();
+    let load_reporter = tokio::spawn(async move {
+        loop {
+            // Holding the server lock until both are sent means a room can't be created in between,
+            // so the sync never leaves out a room the matchmaking server has already been told about
+            let sent = {
+                let server_guard = load_server.read().await;
+                let mut rooms = Vec::new();
+                for room in server_guard.rooms.values() {
+                    let room = room.read().await;
+                    let server_address = format!("{}/room/{}", server_guard.config.public_address, room.room_id);
+                    rooms.push(SyncedRoom { room_info: room.room_info(), server_address, room_code: room.room_code.clone() });
+                }
+                let player_count = rooms.iter().map(|room| room.room_info.player_count).sum();
+                let report = GSToMM::LoadReport { room_count: rooms.len(), player_count };
+                load_tx.send(report).is_ok() && load_tx.send(GSToMM::RoomSync { rooms }).is_ok()
+            };
+            if !sent {
+                break;
             }
+            // Straight away the first time, so rooms that kept running while we were reconnecting can be found again
+            tokio::time::sleep(LOAD_REPORT_INTERVAL).await;
         }
     });
-    // ------------------------
 
+    let mut result = Ok(());
     while let Some(msg) = read.next().await {
         match msg {
             Ok(Message::Text(text)) => {
                 match serde_json::from_str::<MMToGS>(&text) {
-                    Ok(MMToGS::CreateRoom{request_id, room_name, is_private, max_players}) => {
+                    Ok(MMToGS::CreateRoom{request_id, room_name, room_code, is_private, max_players}) => {
                         //Create a room and then accept connections to it
 
-                        match spawn_room(room_name.clone(), is_private, max_players, Arc::clone(&server)).await {
-                            Ok((room_info, port)) => {
+                        match spawn_room(room_name.clone(), room_code, is_private, max_players, Arc::clone(&server)).await {
+                            Ok(room_info) => {
                                 // Room created succesfully - return the route to this game server and add it to the room list
-                                let data = GSToMM::CreateRoomSuccess{request_id, room_info, port};
-                                let json = serde_json::to_string(&data).unwrap();
-                                let _ = to_write_tx.send(json);
+                                let server_address = format!("{}/room/{}", server.read().await.config.public_address, room_info.id);
+                                let _ = mm_tx.send(GSToMM::CreateRoomSuccess{request_id, room_info, server_address});
                                 println!("Room: {} created successfully", room_name)
                             },
                             Err(e) => {
-                                println!("Failed to create room: {}", e)
+                                println!("Failed to create room: {}", e);
+                                let _ = mm_tx.send(GSToMM::CreateRoomFailed{request_id, reason: e});
                             }
                             
                         }
 
                     },
                     _ => {
-                        let error = GSToMM::Error("Unknown or unsupported message".to_string());
-                        let json = serde_json::to_string(&error).unwrap();
-                        let _ = to_write_tx.send(json);
+                        let _ = mm_tx.send(GSToMM::Error("Unknown or unsupported message".to_string()));
                     }
                     
                 }
             }
-            _ => {
-                println!("Invalid WebSocket message of the connection has been closed");
+            Ok(Message::Ping(_) | Message::Pong(_)) => {},
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {
+                result = Err("Unexpected non text message".to_string());
+                break;
+            },
+            Err(e) => {
+                result = Err(format!("Connection error: {}", e));
                 break;
             }
         }
     }
+
+    load_reporter.abort();
+    writer.abort();
+    result
+}
+
+
+
+// Work out which room a player is after from the path they connected to, then hand them over to it
+async fn route_player_connection(stream: TcpStream, server: SharedServer) -> Result<(), String> {
+    let mut path = String::new();
+    // The error type is set by tungstenite, this never returns it anyway
+    #[allow(clippy::result_large_err)]
+    let read_path = |request: &Request, response: Response| {
+        path = request.uri().path().to_string();
+        Ok(response)
+    };
+    let mut ws_stream = accept_hdr_async(stream, read_path).await
+        .map_err(|e| format!("WebSocket accept error: {}", e))?;
+
+    let room_id = path.strip_prefix("/room/").and_then(|room_id| Uuid::parse_str(room_id.trim_end_matches('/')).ok());
+    let room = match room_id {
+        Some(room_id) => server.read().await.rooms.get(&room_id).cloned(),
+        None => None,
+    };
+
+    let Some(room) = room else {
+        // Let the client know why before closing
+        let rejection = to_message(&ServerToClient::JoinRejected(JoinError::RoomNotFound))?;
+        let _ = ws_stream.send(rejection).await;
+        let _ = ws_stream.close(None).await;
+        return Err(format!("No room at {}", path));
+    };
+
+    let heartbeat = Heartbeat::from_config(&server.read().await.config);
+    handle_player_connection(ws_stream, room, heartbeat).await
 }
 
 
 
 // Handles the creation of a room 
-async fn spawn_room(room_name: String, is_private: bool, max_players: usize, server: SharedServer) -> Result<(RoomInfo, u16), String> {
-    // Try to get a port and error if not
-    let port = {
+async fn spawn_room(room_name: String, room_code: String, is_private: bool, max_players: usize, server: SharedServer) -> Result<RoomInfo, String> {
+    let room_id = Uuid::new_v4();
+
+    // Rooms share the server's matchmaking sender so they follow it across reconnects
+    let (mm_sender, deck_dir, log_dir) = {
         let server_guard = server.read().await;
-        if let Some(port) = server_guard.avaliable_ports.first() {
-            *port
-        } else {
-            return Err("No more ports avaliable".to_string());
+        if server_guard.rooms.len() >= server_guard.config.max_rooms {
+            return Err("Too many rooms running".to_string());
         }
+        (Arc::clone(&server_guard.mm_sender), server_guard.config.deck_dir.clone(), server_guard.config.log_dir.clone())
+    };
+    let decks_in = deck_dir.clone();
+    let available_decks = tokio::task::spawn_blocking(move || available_decks(&decks_in)).await
+        .map_err(|e| format!("Couldn't list the decks: {}", e))?;
+
+    let room = Room {
+        room_id: room_id,
+        room_name: room_name.clone(),
+        room_code,
+        max_players,
+        is_private,
+        players: HashMap::new(),
+        spectators: HashMap::new(),
+        has_started: false,
+        host_id: None,
+        deck: DEFAULT_DECK.to_string(),
+        deck_dir,
+        available_decks,
+        log_dir,
+        game: None,
+        mm_sender,
+        next_seat: 0,
     };
+    let room_info = room.room_info();
+    let room = Arc::new(RwLock::new(room));
 
-    let addr = format!("127.0.0.1:{}", port);
-    if let Ok(listener) = TcpListener::bind(&addr).await {
-        println!("Room {} listening on {}", room_name, addr);
-
-        let room_id = Uuid::new_v4();
-
-        let room_info = RoomInfo {
-            id: room_id,
-            name: room_name.clone(),
-            max_players: max_players,
-            player_count: 0,
-            is_private: is_private,
-            has_started: false
-        };
-
-        // Get mm_sender (clone) before acquiring write lock
-        let mm_sender = {
-            let server_guard = server.read().await;
-            server_guard.mm_sender.clone()
-        };
-
-        let room = Arc::new(RwLock::new(Room {
-            room_id: room_id,
-            room_name: room_name.clone(),
-            room_code: "GHZX".to_string(),
-            players: HashMap::new(),
-            spectators: HashSet::new(),
-            has_started: false,
-            mm_sender,
-        }));
-
-        // Spawn new room
-        let room_clone = Arc::clone(&room);
-        tokio::spawn(async move {
-            // Listen out for player actions
-            while let Ok((stream, _)) = listener.accept().await {
-                let room_for_player = Arc::clone(&room_clone);
-                tokio::spawn(handle_player_connection(stream, room_for_player));
-            }
-        });
+    // Now the room has been made, add the room to the rooms list so players can be routed to it
+    server.write().await.rooms.insert(room_id, Arc::clone(&room));
+    println!("Room {} created with id {}", room_name, room_id);
+
+    tokio::spawn(watch_room(room_id, room, server));
+
+    Ok(room_info)
+}
+
+
+// Close the room once it has been empty for a while, or a while after its game is over
+async fn watch_room(room_id: Uuid, room: Arc<RwLock<Room>>, server: SharedServer) {
+    let mut empty_since: Option<Instant> = None;
+    let mut game_over_since: Option<Instant> = None;
+    loop {
+        tokio::time::sleep(ROOM_CHECK_INTERVAL).await;
 
-        // Now the room has been made, remove the port from the avaliable ports and add the room to the rooms list
-        let mut server_guard = server.write().await;
+        if game_over_since.is_none() && room.read().await.is_game_over() {
+            // Save it now rather than waiting for the room to close, in case the server goes down first
+            let saving = room.read().await.save_game_log();
+            if let Some(saving) = saving {
+                let _ = saving.await;
+            }
+            println!("Room {} game over", room_id);
+            game_over_since = Some(Instant::now());
+        }
+        if game_over_since.is_some_and(|since| since.elapsed() >= GAME_OVER_GRACE_PERIOD) {
+            break;
+        }
 
-        server_guard.avaliable_ports.remove(0);
-        server_guard.rooms.insert(room_id, room);
+        if !room.read().await.is_empty() {
+            empty_since = None;
+            continue;
+        }
 
-        Ok((room_info, port))
-    } else {
-        Err("Cannot bind address".to_string())
+        let since = *empty_since.get_or_insert_with(Instant::now);
+        if since.elapsed() >= ROOM_EMPTY_GRACE_PERIOD {
+            break;
+        }
     }
-    
 
-}
\ No newline at end of file
+    // Stop routing players to it and kick out anyone left
+    let mut server_guard = server.write().await;
+    server_guard.rooms.remove(&room_id);
+    room.write().await.close();
+
+    send_to_mm(&server_guard.mm_sender, GSToMM::RoomDeleted { room_id });
+    println!("Room {} closed", room_id);
+}
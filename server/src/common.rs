@@ -1,47 +1,308 @@
 // Need to have a 'room' struct that gets instansiated when a room is created
 
-use std::{collections::{HashMap, HashSet}, sync::{Arc}};
+use std::{collections::HashMap, fs, sync::{Arc}, time::{Duration, Instant}};
 use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
 use tokio::sync::{mpsc, RwLock};
 
-use common::{game::game_state::GameState, server::messages::{ClientToServer, ServerToClient}};
+use common::{game::{choice::ChoiceRequest, game_log::GameLog, game_view::GameView}, server::{lobby_info::{LobbyInfo, LobbyPlayer}, messages::{ClientToServer, JoinError, ServerToClient}, room_info::RoomInfo}, GameManger};
 use tokio::net::TcpStream;
-use tokio_tungstenite::accept_async;
+use tokio_tungstenite::WebSocketStream;
 use tungstenite::Message;
 use uuid::Uuid;
 
-use crate::messages::GSToMM;
+use crate::{bot::spawn_bot, heartbeat::Heartbeat, messages::GSToMM};
+
+pub const DEFAULT_DECK: &str = "base_deck";
+
+// How long a player's seat is kept for them after they lose connection
+pub const RECONNECT_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+// Clients answer on their own when a choice runs out, this is how long to wait for that before answering for them
+const CHOICE_TIMEOUT_GRACE: Duration = Duration::from_secs(2);
+
+/// The way to the matchmaking server, shared by the server and all of its rooms
+/// The sender inside gets swapped out whenever the server reconnects, so rooms never hold on to a dead one
+pub type MMSender = Arc<std::sync::RwLock<Option<mpsc::UnboundedSender<GSToMM>>>>;
+
+/// Send to the matchmaking server if we're connected to one right now
+pub fn send_to_mm(mm_sender: &MMSender, msg: GSToMM) {
+    if let Ok(sender) = mm_sender.read() && let Some(sender) = sender.as_ref() {
+        let _ = sender.send(msg);
+    }
+}
+
+/// Swap in the sender of the current matchmaking connection (or None when there isn't one)
+pub fn set_mm_sender(mm_sender: &MMSender, sender: Option<mpsc::UnboundedSender<GSToMM>>) {
+    if let Ok(mut current) = mm_sender.write() {
+        *current = sender;
+    }
+}
 
 pub struct Room {
     pub room_id: Uuid,
     pub room_name: String,
     pub room_code: String,
+    pub max_players: usize,
+    pub is_private: bool,
     pub players: HashMap<Uuid, PlayerInfo>,
-    pub spectators: HashSet<Uuid>,
+    pub spectators: HashMap<Uuid, PlayerInfo>,
     pub has_started: bool,
-    pub mm_sender: Option<mpsc::UnboundedSender<GSToMM>>
+    pub host_id: Option<Uuid>,
+    pub deck: String,
+    pub deck_dir: String,
+    pub available_decks: Vec<String>, // What was in the deck dir when the room was made
+    pub log_dir: String,
+    pub game: Option<GameManger>,
+    pub mm_sender: MMSender,
+    pub next_seat: usize, // Handed out to players as they join so they keep their place
 }
 
 impl Room {
     pub async fn broadcast(&self, msg: ServerToClient) {
-        let json = serde_json::to_string(&msg).unwrap();
-        for player in self.players.values() {
-            let _ = player.sender.send(Message::Text(json.clone().into()));
+        let msg = match to_message(&msg) {
+            Ok(msg) => msg,
+            Err(e) => {
+                println!("Room {}: {}", self.room_id, e);
+                return;
+            }
+        };
+        for player in self.players.values().chain(self.spectators.values()) {
+            let _ = player.sender.send(msg.clone());
+        }
+    }
+
+    /// Check if a new player can take a seat in the room
+    pub fn can_join(&self) -> Result<(), JoinError> {
+        if self.has_started {
+            return Err(JoinError::GameStarted);
+        }
+        if self.players.len() >= self.max_players {
+            return Err(JoinError::RoomFull);
+        }
+        Ok(())
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game.as_ref().is_some_and(|game| game.is_game_over())
+    }
+
+    /// A room with only bots (or nobody) left in it has nobody to play for
+    pub fn is_empty(&self) -> bool {
+        !self.players.values().any(|p| !p.is_bot) && self.spectators.is_empty()
+    }
+
+    /// Disconnect everyone still in the room before it gets thrown away
+    pub fn close(&mut self) {
+        self.save_game_log();
+
+        // Dropping the senders also stops any bots that were left
+        for (_, player) in self.players.drain().chain(self.spectators.drain()) {
+            let _ = player.sender.send(Message::Close(None));
+        }
+    }
+
+    pub fn is_host(&self, player_id: &Option<Uuid>) -> bool {
+        player_id.is_some() && self.host_id == *player_id
+    }
+
+    /// Give out the next place in the join order
+    pub fn take_seat(&mut self) -> usize {
+        self.next_seat += 1;
+        self.next_seat - 1
+    }
+
+    /// The players in the order they joined, which is also the order they take turns in
+    pub fn seated_players(&self) -> Vec<&PlayerInfo> {
+        let mut players: Vec<&PlayerInfo> = self.players.values().collect();
+        players.sort_by_key(|p| p.seat);
+        players
+    }
+
+    pub fn lobby_info(&self) -> LobbyInfo {
+        let players: Vec<LobbyPlayer> = self.seated_players().into_iter().map(|p| LobbyPlayer {
+            player_id: p.player_id,
+            player_name: p.player_name.clone(),
+            is_ready: p.is_ready,
+            is_bot: p.is_bot,
+            is_connected: p.disconnected_at.is_none(),
+        }).collect();
+
+        LobbyInfo {
+            host_id: self.host_id,
+            room_code: self.room_code.clone(),
+            deck: self.deck.clone(),
+            available_decks: self.available_decks.clone(),
+            players,
+        }
+    }
+
+    /// What the matchmaking server needs to know about the room
+    pub fn room_info(&self) -> RoomInfo {
+        RoomInfo {
+            id: self.room_id,
+            name: self.room_name.clone(),
+            max_players: self.max_players,
+            player_count: self.players.len(),
+            spectator_count: self.spectators.len(),
+            is_private: self.is_private,
+            has_started: self.has_started,
+        }
+    }
+
+    /// Let the matchmaking server know how the room looks now
+    pub fn send_room_update(&self) {
+        send_to_mm(&self.mm_sender, GSToMM::RoomUpdated { room_info: self.room_info() });
+    }
+
+    pub async fn broadcast_lobby(&self) {
+        self.broadcast(ServerToClient::LobbyUpdate(self.lobby_info())).await;
+    }
+
+    /// Take a player out of the room (and the game if there is one), returns false if they weren't in it
+    pub async fn remove_player(&mut self, player_id: &Uuid) -> bool {
+        // Spectators aren't part of the game so nobody else needs to know
+        if self.spectators.remove(player_id).is_some() {
+            self.send_room_update();
+            return true;
+        }
+        if self.players.remove(player_id).is_none() {
+            return false;
+        }
+
+        if let Some(game) = &mut self.game {
+            game.remove_player(*player_id);
+        }
+
+        // Hand the room over to someone else if the host left
+        if self.host_id == Some(*player_id) {
+            self.host_id = self.seated_players().into_iter().find(|p| !p.is_bot).map(|p| p.player_id);
+        }
+
+        self.broadcast(ServerToClient::PlayerDisconnect { player_id: *player_id }).await;
+        self.broadcast_lobby().await;
+        self.send_game_views();
+
+        self.send_room_update();
+
+        // Keep a copy of the log on disk in case the room goes away
+        self.save_game_log();
+        true
+    }
+
+    /// Send every player their own view of the game (so nobody sees anyone elses hand)
+    pub fn send_game_views(&self) {
+        for player in self.players.values().chain(self.spectators.values()) {
+            if let Some(view) = self.game_view(&player.player_id) {
+                send_to_player(&player.sender, &ServerToClient::GameView(view));
+            }
+        }
+    }
+
+    /// A players view of the game, with who has lost connection filled in
+    pub fn game_view(&self, player_id: &Uuid) -> Option<GameView> {
+        let mut view = self.game.as_ref()?.player_view(player_id);
+        for player in &mut view.players {
+            player.is_connected = self.players.get(&player.player_id).is_none_or(|p| p.disconnected_at.is_none());
+        }
+        Some(view)
+    }
+
+    /// Ask a player to pick something the game needs, their answer comes back through answer_choice
+    pub fn ask_choice(&self, player_id: Uuid, request: &ChoiceRequest) {
+        if let Some(player) = self.players.get(&player_id) {
+            send_to_player(&player.sender, &ServerToClient::ChoiceRequest(request.clone()));
         }
     }
 
+    /// Check a players answer against what they were asked and carry it out
+    pub fn answer_choice(&mut self, player_id: Uuid, choice_id: Uuid, selected: Vec<Uuid>) -> Result<(), String> {
+        let game = self.game.as_mut().ok_or("The game has not started")?;
+        game.answer_choice(player_id, choice_id, selected)
+    }
+
+    /// Answer for the player if the choice is still waiting on them, returns true if it was
+    pub fn time_out_choice(&mut self, choice_id: Uuid) -> bool {
+        let Some((player_id, request)) = self.game.as_ref().and_then(|game| game.get_pending_choice(&choice_id)) else {
+            return false;
+        };
+        let (player_id, selected) = (*player_id, request.default_answer());
+        match self.answer_choice(player_id, choice_id, selected) {
+            Ok(()) => true,
+            Err(e) => {
+                println!("Room {}: couldn't answer choice {} for {}: {}", self.room_id, choice_id, player_id, e);
+                false
+            }
+        }
+    }
+
+    /// Write the game log to <log_dir>/<room_id>.json so the game can be replayed offline
+    /// The log is copied out and written on the blocking pool, so the room isn't held while the file is written
+    /// Gives back the write so it can be waited on, if there was a game to save
+    pub fn save_game_log(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let log = self.game.as_ref()?.get_game_log().clone();
+        let log_dir = self.log_dir.clone();
+        let room_id = self.room_id;
+        Some(tokio::task::spawn_blocking(move || {
+            if let Err(e) = write_game_log(&log_dir, &room_id, &log) {
+                println!("Failed to save game log for room {}: {}", room_id, e);
+            }
+        }))
+    }
+
 }
 
 pub struct PlayerInfo {
     pub player_id: Uuid,
     pub player_name: String,
-    pub sender: mpsc::UnboundedSender<Message>
+    pub sender: mpsc::UnboundedSender<Message>,
+    pub is_bot: bool,
+    pub is_ready: bool,
+    pub session_token: Uuid,
+    pub disconnected_at: Option<Instant>, // Set while they are gone but their seat is still kept
+    pub seat: usize, // Where they are in the join order, the lobby list and the turn order go by it
+}
+
+fn write_game_log(log_dir: &str, room_id: &Uuid, log: &GameLog) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(log).map_err(|e| e.to_string())?;
+    fs::create_dir_all(log_dir).map_err(|e| e.to_string())?;
+    fs::write(format!("{}/{}.json", log_dir, room_id), json).map_err(|e| e.to_string())
+}
+
+/// The names of all of the decks in the deck directory
+/// This reads the disk, rooms get the list once when they are made
+pub fn available_decks(deck_dir: &str) -> Vec<String> {
+    let mut decks: Vec<String> = fs::read_dir(deck_dir)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("deck_info.json").exists())
+            .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+            .collect())
+        .unwrap_or_default();
+    decks.sort();
+    decks
+}
+
+/// Turn a message into a websocket text frame
+pub fn to_message<T: Serialize>(msg: &T) -> Result<Message, String> {
+    serde_json::to_string(msg)
+        .map(|json| Message::Text(json.into()))
+        .map_err(|e| format!("Failed to serialize message: {}", e))
 }
 
-pub async fn handle_player_connection(stream: TcpStream, room: Arc<RwLock<Room>>) {
+// Send a message down a single players connection
+// A closed connection isn't an error here, the reading side cleans up after it
+pub fn send_to_player(sender: &mpsc::UnboundedSender<Message>, msg: &ServerToClient) {
+    match to_message(msg) {
+        Ok(msg) => { let _ = sender.send(msg); },
+        Err(e) => println!("{}", e),
+    }
+}
+
+/// Run a players connection until it closes, the error is only for the caller to log as the room has already been cleaned up
+pub async fn handle_player_connection(ws_stream: WebSocketStream<TcpStream>, room: Arc<RwLock<Room>>, heartbeat: Heartbeat) -> Result<(), String> {
     // Handles the players connection to a game server
-    let ws_stream = accept_async(stream).await.expect("WebSocket accept error");
-    let (mut write, mut read) = ws_stream.split();
+    let (mut write, read) = ws_stream.split();
 
     let mut local_player_id: Option<Uuid> = None;
 
@@ -55,61 +316,705 @@ pub async fn handle_player_connection(stream: TcpStream, room: Arc<RwLock<Room>>
         }
     });
 
+    // Stops reading if they stop answering pings, which is then treated like any other dropped connection
+    let mut read = heartbeat.watch(read, tx.clone());
+
+    let mut result = Ok(());
     while let Some(msg) = read.next().await {
-        if let Ok(Message::Text(text)) = msg {
-            if let Ok(parsed) = serde_json::from_str::<ClientToServer>(&text) {
-                match parsed {
-                    ClientToServer::RegisterPlayer { player_name, player_id } => {
-                        let mut room = room.write().await;
-                        //Check if player is already in room
-                        if room.players.contains_key(&player_id) {
-                            let err = ServerToClient::Error("Player already connected".to_string());
-                            let json = serde_json::to_string(&err).unwrap();
-                            tx.send(Message::Text(json.into())).unwrap();
-                            continue;
-                        }
-                        
-                        // Add player to room
-                        room.players.insert(player_id, PlayerInfo { player_id: player_id, player_name: player_name.clone(), sender: tx.clone() });
-
-                        // Broadcast the event to all players
-                        room.broadcast(ServerToClient::PlayerJoined { player_id: player_id, player_name: player_name.clone() }).await;
-
-                        // Store the player id for later use
-                        local_player_id = Some(player_id);
-
-                        // Notify the client of a successful connection
-                        let reg = ServerToClient::PlayerRegistered { player_id };
-                        let _ = tx.send(Message::Text(serde_json::to_string(&reg).unwrap().into()));
-
-
-                        // Notify the matchmaking server that a player joined
-                        if let Some(sender) = &room.mm_sender {
-                            let info = GSToMM::ClientJoinedRoom { room_id: room.room_id };
-                            let _ = sender.send(info);
-                        }
-                        println!("Player: {} connected", player_name);
-
-                    },
-                    ClientToServer::Disconnect => {
-                        break;
-                    }
+        let text = match msg {
+            Ok(Message::Text(text)) => text,
+            Ok(_) => continue,
+            Err(e) => {
+                result = Err(format!("Connection error: {}", e));
+                break;
+            }
+        };
+
+        // A message we can't make sense of is the clients problem, not a reason to drop them
+        let parsed = match serde_json::from_str::<ClientToServer>(&text) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                println!("Player {:?} sent an invalid message: {}", local_player_id, e);
+                send_to_player(&tx, &ServerToClient::Error("Invalid message".to_string()));
+                continue;
+            }
+        };
+
+        if !handle_client_message(parsed, &mut local_player_id, &tx, &room, false).await {
+            // They asked to leave (or were turned away), so there is no need to keep their seat
+            let _ = tx.send(Message::Close(None));
+            remove_player(&room, local_player_id).await;
+            return Ok(());
+        }
+    }
+
+    player_connection_lost(&room, local_player_id, &tx).await;
+    result
+}
+
+/// Handle one message from a player, bots also go through here so they can only do what a client can
+/// Returns false when the player wants to leave
+pub async fn handle_client_message(msg: ClientToServer, local_player_id: &mut Option<Uuid>, tx: &mpsc::UnboundedSender<Message>, room: &Arc<RwLock<Room>>, is_bot: bool) -> bool {
+    match msg {
+        ClientToServer::RegisterPlayer { player_name, player_id } => {
+            if already_registered(local_player_id, tx) {
+                return true;
+            }
+            let mut room = room.write().await;
+            //Check if player is already in room
+            if room.players.contains_key(&player_id) || room.spectators.contains_key(&player_id) {
+                send_to_player(tx, &ServerToClient::Error("Player already connected".to_string()));
+                return true;
+            }
+
+            // Turn them away if there is no seat for them
+            if let Err(e) = room.can_join() {
+                println!("Player: {} could not join: {}", player_name, e);
+                send_to_player(tx, &ServerToClient::JoinRejected(e));
+                return false;
+            }
+            
+            // Add player to room
+            let session_token = Uuid::new_v4();
+            let seat = room.take_seat();
+            room.players.insert(player_id, PlayerInfo { player_id: player_id, player_name: player_name.clone(), sender: tx.clone(), is_bot, is_ready: is_bot, session_token, disconnected_at: None, seat });
+
+            // The first person in the room gets to run it
+            if room.host_id.is_none() && !is_bot {
+                room.host_id = Some(player_id);
+            }
+
+            // Broadcast the event to all players
+            room.broadcast(ServerToClient::PlayerJoined { player_id: player_id, player_name: player_name.clone() }).await;
+
+            // Store the player id for later use
+            *local_player_id = Some(player_id);
+
+            // Notify the client of a successful connection
+            send_to_player(tx, &ServerToClient::PlayerRegistered { player_id, session_token });
+            room.broadcast_lobby().await;
+
+            room.send_room_update();
+            println!("Player: {} connected", player_name);
+        },
+        ClientToServer::RegisterSpectator { player_name, player_id } => {
+            if already_registered(local_player_id, tx) {
+                return true;
+            }
+            let mut room = room.write().await;
+            if room.players.contains_key(&player_id) || room.spectators.contains_key(&player_id) {
+                send_to_player(tx, &ServerToClient::Error("Player already connected".to_string()));
+                return true;
+            }
+
+            // Spectators don't keep a seat, so the token can't be used for anything
+            let session_token = Uuid::new_v4();
+            let seat = room.take_seat();
+            room.spectators.insert(player_id, PlayerInfo { player_id, player_name: player_name.clone(), sender: tx.clone(), is_bot: false, is_ready: false, session_token, disconnected_at: None, seat });
+            *local_player_id = Some(player_id);
+
+            // Catch them up on what is going on
+            send_to_player(tx, &ServerToClient::PlayerRegistered { player_id, session_token });
+            send_to_player(tx, &ServerToClient::LobbyUpdate(room.lobby_info()));
+            if let Some(view) = room.game_view(&player_id) {
+                send_to_player(tx, &ServerToClient::GameView(view));
+            }
+            room.send_room_update();
+            println!("Spectator: {} connected", player_name);
+        },
+        ClientToServer::Reconnect { player_id, session_token } => {
+            if already_registered(local_player_id, tx) {
+                return true;
+            }
+            let mut room = room.write().await;
+            let Some(player) = room.players.get_mut(&player_id).filter(|p| p.session_token == session_token) else {
+                send_to_player(tx, &ServerToClient::JoinRejected(JoinError::SessionExpired));
+                return false;
+            };
+
+            // Take over from the old connection, it might not have noticed it is dead yet
+            if !player.sender.same_channel(tx) {
+                let _ = player.sender.send(Message::Close(None));
+            }
+            player.sender = tx.clone();
+            player.disconnected_at = None;
+            *local_player_id = Some(player_id);
+            println!("Player: {} reconnected", player.player_name);
+
+            // Send them everything they need to pick up where they left off
+            send_to_player(tx, &ServerToClient::PlayerRegistered { player_id, session_token });
+            if room.has_started {
+                send_to_player(tx, &ServerToClient::GameStarted);
+            }
+            // Everyone gets a new view so they can see who is back
+            room.send_game_views();
+            // Anything they were asked before the connection dropped is still waiting on them
+            if let Some(game) = &room.game {
+                for request in game.pending_choices_for(&player_id) {
+                    room.ask_choice(player_id, request);
                 }
             }
+            room.broadcast(ServerToClient::PlayerReconnected { player_id }).await;
+            room.broadcast_lobby().await;
+        },
+        ClientToServer::SetReady { ready } => {
+            let mut room = room.write().await;
+            if let Some(player) = local_player_id.and_then(|id| room.players.get_mut(&id)) {
+                player.is_ready = ready;
+            }
+            room.broadcast_lobby().await;
+        },
+        ClientToServer::SelectDeck { deck_name } => {
+            let mut room = room.write().await;
+            if !room.is_host(local_player_id) {
+                send_to_player(tx, &ServerToClient::Error("Only the host can pick the deck".to_string()));
+                return true;
+            }
+            if !room.available_decks.contains(&deck_name) {
+                send_to_player(tx, &ServerToClient::Error(format!("Deck {} does not exist", deck_name)));
+                return true;
+            }
+            room.deck = deck_name;
+            room.broadcast_lobby().await;
+        },
+        ClientToServer::StartGame => {
+            if let Err(e) = start_game(room, local_player_id).await {
+                send_to_player(tx, &ServerToClient::Error(e));
+            }
+        },
+        ClientToServer::KickPlayer { player_id } => {
+            let mut room = room.write().await;
+            if !room.is_host(local_player_id) {
+                send_to_player(tx, &ServerToClient::Error("Only the host can kick players".to_string()));
+                return true;
+            }
+            if Some(player_id) == *local_player_id {
+                send_to_player(tx, &ServerToClient::Error("You can't kick yourself".to_string()));
+                return true;
+            }
+            // Let them know, then close their connection
+            if let Some(player) = room.players.get(&player_id) {
+                send_to_player(&player.sender, &ServerToClient::Kicked);
+                let _ = player.sender.send(Message::Close(None));
+            }
+            room.remove_player(&player_id).await;
+        },
+        ClientToServer::TransferHost { player_id } => {
+            let mut room = room.write().await;
+            if !room.is_host(local_player_id) {
+                send_to_player(tx, &ServerToClient::Error("Only the host can pass on host".to_string()));
+                return true;
+            }
+            match room.players.get(&player_id) {
+                Some(player) if !player.is_bot => {
+                    room.host_id = Some(player_id);
+                    room.broadcast_lobby().await;
+                },
+                _ => send_to_player(tx, &ServerToClient::Error("That player can't be made host".to_string())),
+            }
+        },
+        ClientToServer::AddBot { difficulty } => {
+            let room_guard = room.read().await;
+            if !room_guard.is_host(local_player_id) {
+                send_to_player(tx, &ServerToClient::Error("Only the host can add bots".to_string()));
+                return true;
+            }
+            if let Err(e) = room_guard.can_join() {
+                send_to_player(tx, &ServerToClient::Error(format!("Can't add a bot: {}", e)));
+                return true;
+            }
+            drop(room_guard);
+
+            let bot_id = spawn_bot(Arc::clone(room), difficulty);
+            println!("Added {:?} bot {}", difficulty, bot_id);
+        },
+        ClientToServer::PlayCard { card_instance_id, target } => {
+            let mut room_guard = room.write().await;
+            let result = match (*local_player_id, room_guard.game.as_mut()) {
+                (Some(player_id), Some(game)) => game.play_card(player_id, card_instance_id, target)
+                    .map(|choice| choice.map(|request| (player_id, request))),
+                (None, _) => Err("You are not registered".to_string()),
+                (_, None) => Err("The game has not started".to_string()),
+            };
+            match result {
+                Ok(choice) => {
+                    room_guard.send_game_views();
+                    // The card needs them to pick something before it does anything
+                    if let Some((player_id, request)) = choice {
+                        room_guard.ask_choice(player_id, &request);
+                        time_out_choice_later(room, &request);
+                    }
+                },
+                Err(e) => send_to_player(tx, &ServerToClient::Error(e)),
+            }
+        },
+        ClientToServer::EndTurn => {
+            let mut room = room.write().await;
+            let result = match (*local_player_id, room.game.as_mut()) {
+                (Some(player_id), Some(game)) => game.end_turn(player_id),
+                (None, _) => Err("You are not registered".to_string()),
+                (_, None) => Err("The game has not started".to_string()),
+            };
+            match result {
+                Ok(()) => room.send_game_views(),
+                Err(e) => send_to_player(tx, &ServerToClient::Error(e)),
+            }
+        },
+        ClientToServer::MakeChoice { choice_id, selected } => {
+            let mut room = room.write().await;
+            let result = match *local_player_id {
+                Some(player_id) => room.answer_choice(player_id, choice_id, selected),
+                None => Err("You are not registered".to_string()),
+            };
+            match result {
+                Ok(()) => room.send_game_views(),
+                Err(e) => send_to_player(tx, &ServerToClient::Error(e)),
+            }
+        },
+        ClientToServer::Disconnect => {
+            return false;
         }
     }
+    true
+}
 
-    // Player has disconnected, so remove them from the room and broadcast their disconnect
-    if let Some(player_id) = local_player_id {
+// A connection only ever gets one seat - registering again would take a second one,
+// and being turned away would end the connection and lose the seat it already has
+fn already_registered(local_player_id: &Option<Uuid>, tx: &mpsc::UnboundedSender<Message>) -> bool {
+    if local_player_id.is_some() {
+        send_to_player(tx, &ServerToClient::Error("You are already registered".to_string()));
+    }
+    local_player_id.is_some()
+}
+
+/// The players connection dropped without them leaving, so keep their seat for a while in case they come back
+pub async fn player_connection_lost(room: &Arc<RwLock<Room>>, local_player_id: Option<Uuid>, tx: &mpsc::UnboundedSender<Message>) {
+    let Some(player_id) = local_player_id else {
+        return;
+    };
+
+    let mut room_guard = room.write().await;
+    // Spectators have nothing to come back to
+    if room_guard.spectators.contains_key(&player_id) {
+        room_guard.remove_player(&player_id).await;
+        return;
+    }
+
+    // Ignore connections that have already been replaced by a reconnect
+    let disconnected_at = Instant::now();
+    match room_guard.players.get_mut(&player_id) {
+        Some(player) if player.sender.same_channel(tx) => player.disconnected_at = Some(disconnected_at),
+        _ => return,
+    }
+    room_guard.broadcast(ServerToClient::PlayerConnectionLost { player_id }).await;
+    room_guard.broadcast_lobby().await;
+    room_guard.send_game_views();
+    drop(room_guard);
+    println!("Player {} lost connection, keeping their seat for {:?}", player_id, RECONNECT_GRACE_PERIOD);
+
+    // Give up on them if they haven't come back in time
+    let room = Arc::clone(room);
+    tokio::spawn(async move {
+        tokio::time::sleep(RECONNECT_GRACE_PERIOD).await;
         let mut room = room.write().await;
-        room.players.remove(&player_id);
-        room.broadcast(ServerToClient::PlayerDisconnect { player_id }).await;
+        let still_gone = room.players.get(&player_id).is_some_and(|p| p.disconnected_at == Some(disconnected_at));
+        if still_gone {
+            room.remove_player(&player_id).await;
+            println!("Player {} didn't reconnect in time", player_id);
+        }
+    });
+}
 
-        // Notify the matchmaking server that a player disconnected
-        if let Some(sender) = &room.mm_sender {
-            let info = GSToMM::ClientDisconnectRoom{ room_id: room.room_id };
-            let _ = sender.send(info);
+// Answer a choice for the player if they haven't by the time it runs out (e.g. they lost connection)
+fn time_out_choice_later(room: &Arc<RwLock<Room>>, request: &ChoiceRequest) {
+    let Some(timeout_secs) = request.timeout_secs else {
+        return;
+    };
+    let room = Arc::clone(room);
+    let choice_id = request.choice_id;
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs_f32(timeout_secs) + CHOICE_TIMEOUT_GRACE).await;
+        let mut room = room.write().await;
+        if room.time_out_choice(choice_id) {
+            room.send_game_views();
         }
+    });
+}
+
+/// Player has disconnected, so remove them from the room and broadcast their disconnect
+pub async fn remove_player(room: &Arc<RwLock<Room>>, local_player_id: Option<Uuid>) {
+    if let Some(player_id) = local_player_id {
+        let mut room = room.write().await;
+        room.remove_player(&player_id).await;
     }
     println!("Player {:?} disconnected", local_player_id);
-}
\ No newline at end of file
+}
+
+/// Deal everyone in and let the matchmaking server know the room can't be joined anymore
+async fn start_game(room: &Arc<RwLock<Room>>, local_player_id: &Option<Uuid>) -> Result<(), String> {
+    let deck_path = {
+        let room = room.read().await;
+        check_can_start(&room, local_player_id)?;
+        format!("{}/{}", room.deck_dir, room.deck)
+    };
+
+    // Reading the deck is blocking file work, so it happens off the async threads and without holding the room
+    let loading_path = deck_path.clone();
+    let mut game = tokio::task::spawn_blocking(move || {
+        let mut game = GameManger::new();
+        game.load_deck(&loading_path).map(|()| game)
+    }).await.map_err(|e| format!("Couldn't load the deck: {}", e))?
+        .map_err(|e| format!("Couldn't load the deck: {}", e))?;
+
+    // Things could have changed while the deck was loading
+    let mut room = room.write().await;
+    check_can_start(&room, local_player_id)?;
+    if format!("{}/{}", room.deck_dir, room.deck) != deck_path {
+        return Err("The deck was changed while the game was starting".to_string());
+    }
+
+    // Turns go in the order everyone joined, the same order the lobby showed them in
+    let seated: Vec<(Uuid, String)> = room.seated_players().into_iter().map(|p| (p.player_id, p.player_name.clone())).collect();
+    for (player_id, player_name) in &seated {
+        game.add_player(*player_id, player_name.clone());
+    }
+    game.start_game(seated.into_iter().map(|(player_id, _)| player_id).collect())?;
+    let started = to_message(&ServerToClient::GameStarted)?;
+
+    room.game = Some(game);
+    room.has_started = true;
+
+    for player in room.players.values() {
+        let _ = player.sender.send(started.clone());
+    }
+    room.send_game_views();
+
+    room.send_room_update();
+    println!("Room {} started with deck {}", room.room_name, room.deck);
+    Ok(())
+}
+
+fn check_can_start(room: &Room, local_player_id: &Option<Uuid>) -> Result<(), String> {
+    if !room.is_host(local_player_id) {
+        return Err("Only the host can start the game".to_string());
+    }
+    if room.has_started {
+        return Err("The game has already started".to_string());
+    }
+    if room.players.len() < 2 {
+        return Err("You need at least 2 players to start".to_string());
+    }
+    // The host doesn't have to ready up, starting the game is them saying they're ready
+    if room.players.values().any(|p| !p.is_ready && Some(p.player_id) != room.host_id) {
+        return Err("Not everyone is ready".to_string());
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use common::game::game_log::Replay;
+
+    use super::*;
+
+    const DECK_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/decks");
+
+    fn test_room() -> Room {
+        Room {
+            room_id: Uuid::new_v4(),
+            room_name: "Test".to_string(),
+            room_code: "ABCDE".to_string(),
+            max_players: 4,
+            is_private: false,
+            players: HashMap::new(),
+            spectators: HashMap::new(),
+            has_started: false,
+            host_id: None,
+            deck: DEFAULT_DECK.to_string(),
+            deck_dir: DECK_DIR.to_string(),
+            available_decks: available_decks(DECK_DIR),
+            log_dir: std::env::temp_dir().join("room_logs").to_string_lossy().to_string(),
+            game: None,
+            mm_sender: MMSender::default(),
+            next_seat: 0,
+        }
+    }
+
+    fn add_player(room: &mut Room, player_name: &str) -> (Uuid, mpsc::UnboundedReceiver<Message>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let player_id = Uuid::new_v4();
+        let seat = room.take_seat();
+        room.players.insert(player_id, PlayerInfo { player_id, player_name: player_name.to_string(), sender: tx, is_bot: false, is_ready: true, session_token: Uuid::new_v4(), disconnected_at: None, seat });
+        (player_id, rx)
+    }
+
+    // A started game where the first player has a booster out and has just played Addon Remover
+    // Gives back the room, the first player, what they were asked and the booster
+    fn room_waiting_on_choice() -> (Room, Uuid, ChoiceRequest, Uuid) {
+        let mut room = test_room();
+        let (first, _) = add_player(&mut room, "First");
+        let (second, _) = add_player(&mut room, "Second");
+
+        let mut game = GameManger::with_seed(1);
+        game.load_deck(&format!("{}/{}", DECK_DIR, DEFAULT_DECK)).unwrap();
+        game.add_player(first, "First".to_string());
+        game.add_player(second, "Second".to_string());
+        game.start_game(vec![first, second]).unwrap();
+
+        let booster = game.test_give_card(first, "booster").unwrap();
+        game.play_card(first, booster, None).unwrap();
+        let remover = game.test_give_card(first, "addon_remover").unwrap();
+        let request = game.play_card(first, remover, None).unwrap().expect("no choice was asked for");
+
+        room.game = Some(game);
+        room.has_started = true;
+        (room, first, request, booster)
+    }
+
+    fn on_table(room: &Room, player_id: &Uuid, instance_id: &Uuid) -> bool {
+        let game = room.game.as_ref().unwrap();
+        game.get_player_manager().get_player_by_id(player_id).unwrap().get_table().contains(instance_id)
+    }
+
+    fn register(player_name: &str) -> ClientToServer {
+        ClientToServer::RegisterPlayer { player_name: player_name.to_string(), player_id: Uuid::new_v4() }
+    }
+
+    #[tokio::test]
+    async fn registering_twice_keeps_the_first_seat_only() {
+        let room = Arc::new(RwLock::new(test_room()));
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut local_player_id = None;
+
+        assert!(handle_client_message(register("First"), &mut local_player_id, &tx, &room, false).await);
+        let first = local_player_id.unwrap();
+
+        assert!(handle_client_message(register("Again"), &mut local_player_id, &tx, &room, false).await);
+        let spectate = ClientToServer::RegisterSpectator { player_name: "Watcher".to_string(), player_id: Uuid::new_v4() };
+        assert!(handle_client_message(spectate, &mut local_player_id, &tx, &room, false).await);
+
+        assert_eq!(local_player_id, Some(first));
+        let room = room.read().await;
+        assert_eq!(room.players.len(), 1);
+        assert!(room.players.contains_key(&first));
+        assert!(room.spectators.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reconnecting_when_already_registered_keeps_the_connection() {
+        let room = Arc::new(RwLock::new(test_room()));
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut local_player_id = None;
+        handle_client_message(register("First"), &mut local_player_id, &tx, &room, false).await;
+        let first = local_player_id.unwrap();
+
+        // A bad token would normally turn the connection away, which would cost them the seat they have
+        let reconnect = ClientToServer::Reconnect { player_id: Uuid::new_v4(), session_token: Uuid::new_v4() };
+        assert!(handle_client_message(reconnect, &mut local_player_id, &tx, &room, false).await);
+        assert_eq!(local_player_id, Some(first));
+        assert!(room.read().await.players.contains_key(&first));
+    }
+
+    #[tokio::test]
+    async fn reconnect_with_a_bad_token_is_turned_away() {
+        let room = Arc::new(RwLock::new(test_room()));
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut local_player_id = None;
+        let reconnect = ClientToServer::Reconnect { player_id: Uuid::new_v4(), session_token: Uuid::new_v4() };
+        assert!(!handle_client_message(reconnect, &mut local_player_id, &tx, &room, false).await);
+        assert!(local_player_id.is_none());
+    }
+
+    // Everything sent down a connection so far
+    fn received(rx: &mut mpsc::UnboundedReceiver<Message>) -> Vec<ServerToClient> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|msg| serde_json::from_str(&msg.into_text().ok()?).ok())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reconnecting_asks_any_choice_that_is_still_open_again() {
+        let (room, player_id, request, _) = room_waiting_on_choice();
+        let session_token = room.players[&player_id].session_token;
+        let room = Arc::new(RwLock::new(room));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut local_player_id = None;
+
+        let reconnect = ClientToServer::Reconnect { player_id, session_token };
+        assert!(handle_client_message(reconnect, &mut local_player_id, &tx, &room, false).await);
+        assert!(received(&mut rx).iter().any(|msg| matches!(msg, ServerToClient::ChoiceRequest(asked) if asked.choice_id == request.choice_id)));
+    }
+
+    #[tokio::test]
+    async fn game_views_show_who_has_lost_connection() {
+        let (room, player_id, _, _) = room_waiting_on_choice();
+        let other = *room.players.keys().find(|id| **id != player_id).unwrap();
+        let old_tx = room.players[&player_id].sender.clone();
+        let session_token = room.players[&player_id].session_token;
+        let room = Arc::new(RwLock::new(room));
+
+        player_connection_lost(&room, Some(player_id), &old_tx).await;
+        let view = room.read().await.game_view(&other).unwrap();
+        assert!(!view.get_player(&player_id).unwrap().is_connected);
+        assert!(view.get_player(&other).unwrap().is_connected);
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let reconnect = ClientToServer::Reconnect { player_id, session_token };
+        handle_client_message(reconnect, &mut None, &tx, &room, false).await;
+        let view = room.read().await.game_view(&other).unwrap();
+        assert!(view.get_player(&player_id).unwrap().is_connected);
+    }
+
+    fn host_room(deck: &str) -> (Arc<RwLock<Room>>, Option<Uuid>, mpsc::UnboundedReceiver<Message>) {
+        let mut room = test_room();
+        room.deck = deck.to_string();
+        let (host, host_rx) = add_player(&mut room, "Host");
+        add_player(&mut room, "Guest");
+        room.host_id = Some(host);
+        (Arc::new(RwLock::new(room)), Some(host), host_rx)
+    }
+
+    #[tokio::test]
+    async fn starting_loads_the_deck_and_deals_everyone_in() {
+        let (room, host, _host_rx) = host_room(DEFAULT_DECK);
+        start_game(&room, &host).await.unwrap();
+
+        let room = room.read().await;
+        assert!(room.has_started);
+        assert!(room.game.as_ref().is_some_and(|game| game.has_started()));
+    }
+
+    #[tokio::test]
+    async fn turns_and_the_lobby_go_in_join_order() {
+        let mut room = test_room();
+        // Names that sort the other way, and enough players that a HashMap is unlikely to keep the order by chance
+        let joined: Vec<Uuid> = ["Zed", "Yan", "Xia", "Wes", "Val", "Uma"].iter().map(|name| add_player(&mut room, name).0).collect();
+        room.host_id = Some(joined[0]);
+        let host = room.host_id;
+        let room = Arc::new(RwLock::new(room));
+        start_game(&room, &host).await.unwrap();
+
+        let room = room.read().await;
+        let lobby: Vec<Uuid> = room.lobby_info().players.iter().map(|p| p.player_id).collect();
+        assert_eq!(lobby, joined);
+        assert_eq!(room.game.as_ref().unwrap().get_game_state().get_turn_order(), &joined);
+    }
+
+    #[tokio::test]
+    async fn the_game_is_over_when_everyone_else_leaves() {
+        let (room, host, _host_rx) = host_room(DEFAULT_DECK);
+        start_game(&room, &host).await.unwrap();
+        let mut room = room.write().await;
+        assert!(!room.is_game_over());
+
+        let guest = *room.players.keys().find(|id| Some(**id) != host).unwrap();
+        room.remove_player(&guest).await;
+        assert!(room.is_game_over());
+    }
+
+    #[tokio::test]
+    async fn a_deck_that_wont_load_is_reported_to_the_host() {
+        let (room, host, _host_rx) = host_room("no_such_deck");
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut local_player_id = host;
+        assert!(handle_client_message(ClientToServer::StartGame, &mut local_player_id, &tx, &room, false).await);
+        let reply = rx.try_recv().unwrap().into_text().unwrap();
+        assert!(matches!(serde_json::from_str(&reply).unwrap(), ServerToClient::Error(_)));
+
+        let room = room.read().await;
+        assert!(!room.has_started);
+        assert!(room.game.is_none());
+    }
+
+    #[test]
+    fn room_updates_go_to_the_current_matchmaking_connection() {
+        let room = test_room();
+        // Not connected yet, nothing to send to
+        room.send_room_update();
+
+        let (old_tx, mut old_rx) = mpsc::unbounded_channel();
+        set_mm_sender(&room.mm_sender, Some(old_tx));
+        room.send_room_update();
+        assert!(matches!(old_rx.try_recv(), Ok(GSToMM::RoomUpdated { .. })));
+
+        // After a reconnect the room should be using the new connection without being told
+        let (new_tx, mut new_rx) = mpsc::unbounded_channel();
+        set_mm_sender(&room.mm_sender, Some(new_tx));
+        room.send_room_update();
+        assert!(matches!(new_rx.try_recv(), Ok(GSToMM::RoomUpdated { .. })));
+        assert!(old_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn game_logs_are_saved_to_the_log_dir_and_replay() {
+        let (room, host, _host_rx) = host_room(DEFAULT_DECK);
+        start_game(&room, &host).await.unwrap();
+        let mut room = room.write().await;
+        // Play a turn so there is more in the log than dealing
+        let game = room.game.as_mut().unwrap();
+        let player_id = *game.get_game_state().get_current_player().unwrap();
+        for card_instance_id in game.get_player_manager().get_player_by_id(&player_id).unwrap().get_hand().clone() {
+            let _ = game.play_card(player_id, card_instance_id, None);
+        }
+        game.end_turn(player_id).unwrap();
+
+        room.log_dir = std::env::temp_dir().join(format!("room_logs_{}", room.room_id)).to_string_lossy().to_string();
+        room.save_game_log().unwrap().await.unwrap();
+
+        let log = GameLog::load_from_file(&format!("{}/{}.json", room.log_dir, room.room_id)).unwrap();
+        let _ = fs::remove_dir_all(&room.log_dir);
+        let mut replay = Replay::new(log).unwrap();
+        replay.run_to_end();
+        let game = room.game.as_ref().unwrap();
+        for player_id in room.players.keys() {
+            let player = game.get_player_manager().get_player_by_id(player_id).unwrap();
+            let replayed = replay.get_game().get_player_manager().get_player_by_id(player_id).unwrap();
+            assert_eq!(replayed.get_hand(), player.get_hand());
+            assert_eq!(replayed.get_table(), player.get_table());
+        }
+    }
+
+    #[test]
+    fn answer_choice_carries_out_the_answer_once() {
+        let (mut room, player_id, request, booster) = room_waiting_on_choice();
+
+        room.answer_choice(player_id, request.choice_id, vec![booster]).unwrap();
+        assert!(!on_table(&room, &player_id, &booster));
+        assert!(room.answer_choice(player_id, request.choice_id, vec![booster]).is_err());
+    }
+
+    #[test]
+    fn answer_choice_only_takes_answers_from_who_was_asked() {
+        let (mut room, player_id, request, booster) = room_waiting_on_choice();
+        let someone_else = *room.players.keys().find(|id| **id != player_id).unwrap();
+
+        assert!(room.answer_choice(someone_else, request.choice_id, vec![booster]).is_err());
+        assert!(room.answer_choice(player_id, Uuid::new_v4(), vec![booster]).is_err());
+        assert!(on_table(&room, &player_id, &booster));
+    }
+
+    #[test]
+    fn answer_choice_needs_a_game() {
+        let mut room = test_room();
+        assert!(room.answer_choice(Uuid::new_v4(), Uuid::new_v4(), vec![]).is_err());
+    }
+
+    #[test]
+    fn timed_out_choice_is_answered_with_the_default() {
+        let (mut room, player_id, request, booster) = room_waiting_on_choice();
+        assert_eq!(request.default_answer(), vec![booster]);
+
+        assert!(room.time_out_choice(request.choice_id));
+        assert!(!on_table(&room, &player_id, &booster));
+        // Nothing left to time out
+        assert!(!room.time_out_choice(request.choice_id));
+    }
+
+    #[test]
+    fn time_out_leaves_answered_choices_alone() {
+        let (mut room, player_id, request, booster) = room_waiting_on_choice();
+        room.answer_choice(player_id, request.choice_id, vec![booster]).unwrap();
+        assert!(!room.time_out_choice(request.choice_id));
+    }
+}
@@ -0,0 +1,152 @@
+// Settings for the servers, read from a JSON config file and then overridden by any command line flags
+// Usage: server <local|central|matchmaking> [--config path] [--matchmaking-bind addr] [--game-server-bind addr]
+//        [--matchmaking-server url] [--server-id uuid] [--player-bind addr] [--public-address url] [--max-rooms N] [--deck-dir path]
+//        [--log-dir path] [--ping-interval secs] [--idle-timeout secs]
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Used when no --config flag is given, it is fine for this not to exist
+pub const DEFAULT_CONFIG_PATH: &str = "server_config.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Where the matchmaking server listens for clients
+    pub matchmaking_bind: String,
+    /// Where the matchmaking server listens for central servers to register
+    pub game_server_bind: String,
+    /// The matchmaking server a central server registers with
+    pub matchmaking_server: String,
+    /// How a central server is known to the matchmaking server (a new one is made each run if not set)
+    pub server_id: Uuid,
+    /// Where the central server listens for players (all rooms share this)
+    pub player_bind: String,
+    /// The address players are told to connect to, e.g. when behind a reverse proxy
+    pub public_address: String,
+    /// The most rooms a central server will run at once
+    pub max_rooms: usize,
+    /// Where the decks a host can pick from live
+    pub deck_dir: String,
+    /// Where each room's game log gets written so the game can be replayed
+    pub log_dir: String,
+    /// How often every websocket gets pinged
+    pub ping_interval_secs: u64,
+    /// How long a websocket can go without sending anything before it is treated as disconnected
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            matchmaking_bind: "127.0.0.1:9001".to_string(),
+            game_server_bind: "127.0.0.1:9002".to_string(),
+            matchmaking_server: "ws://127.0.0.1:9002".to_string(),
+            server_id: Uuid::new_v4(),
+            player_bind: "127.0.0.1:9003".to_string(),
+            public_address: "ws://127.0.0.1:9003".to_string(),
+            max_rooms: 64,
+            deck_dir: "res/decks".to_string(),
+            log_dir: "logs".to_string(),
+            ping_interval_secs: 10,
+            idle_timeout_secs: 30,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Build the config from the config file and the command line args (not including the server type)
+    pub fn load(args: &[String]) -> Result<Self, String> {
+        // Find the config file first so the flags can override it
+        let config_path = args.iter()
+            .position(|arg| arg == "--config")
+            .map(|index| args.get(index + 1).cloned().ok_or("Missing value for --config".to_string()))
+            .transpose()?;
+
+        let mut config = match &config_path {
+            Some(path) => Self::load_from_file(path)?,
+            None if fs::metadata(DEFAULT_CONFIG_PATH).is_ok() => Self::load_from_file(DEFAULT_CONFIG_PATH)?,
+            None => Self::default(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().cloned().ok_or(format!("Missing value for {}", name));
+            match arg.as_str() {
+                "--config" => { value("--config")?; },
+                "--matchmaking-bind" => config.matchmaking_bind = value("--matchmaking-bind")?,
+                "--game-server-bind" => config.game_server_bind = value("--game-server-bind")?,
+                "--matchmaking-server" => config.matchmaking_server = value("--matchmaking-server")?,
+                "--server-id" => config.server_id = Uuid::parse_str(&value("--server-id")?).map_err(|_| "--server-id must be a uuid")?,
+                "--player-bind" => config.player_bind = value("--player-bind")?,
+                "--public-address" => config.public_address = value("--public-address")?,
+                "--max-rooms" => config.max_rooms = value("--max-rooms")?.parse().map_err(|_| "--max-rooms must be a number")?,
+                "--deck-dir" => config.deck_dir = value("--deck-dir")?,
+                "--log-dir" => config.log_dir = value("--log-dir")?,
+                "--ping-interval" => config.ping_interval_secs = value("--ping-interval")?.parse().map_err(|_| "--ping-interval must be a number")?,
+                "--idle-timeout" => config.idle_timeout_secs = value("--idle-timeout")?.parse().map_err(|_| "--idle-timeout must be a number")?,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        config.check_heartbeat();
+        Ok(config)
+    }
+
+    // A zero interval would panic the ping timer, and a timeout shorter than the interval drops every connection
+    fn check_heartbeat(&mut self) {
+        if self.ping_interval_secs > 0 && self.idle_timeout_secs > self.ping_interval_secs {
+            return;
+        }
+        let default = Self::default();
+        println!("Ping interval {}s and idle timeout {}s don't work together (the interval has to be above 0 and less than the timeout), using {}s and {}s",
+            self.ping_interval_secs, self.idle_timeout_secs, default.ping_interval_secs, default.idle_timeout_secs);
+        self.ping_interval_secs = default.ping_interval_secs;
+        self.idle_timeout_secs = default.idle_timeout_secs;
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read config {}: {}", path, e))?;
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse config {}: {}", path, e))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(args: &[&str]) -> ServerConfig {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        ServerConfig::load(&args).unwrap()
+    }
+
+    #[test]
+    fn flags_override_the_defaults() {
+        let config = load(&["--max-rooms", "3", "--log-dir", "/tmp/logs", "--ping-interval", "5", "--idle-timeout", "20"]);
+        assert_eq!(config.max_rooms, 3);
+        assert_eq!(config.log_dir, "/tmp/logs");
+        assert_eq!(config.ping_interval_secs, 5);
+        assert_eq!(config.idle_timeout_secs, 20);
+    }
+
+    #[test]
+    fn bad_heartbeat_settings_fall_back_to_the_defaults() {
+        let default = ServerConfig::default();
+        for args in [["--ping-interval", "0", "--idle-timeout", "30"], ["--ping-interval", "10", "--idle-timeout", "10"], ["--ping-interval", "20", "--idle-timeout", "5"]] {
+            let config = load(&args);
+            assert_eq!(config.ping_interval_secs, default.ping_interval_secs);
+            assert_eq!(config.idle_timeout_secs, default.idle_timeout_secs);
+        }
+    }
+
+    #[test]
+    fn bad_flags_are_errors() {
+        let args = ["--max-rooms".to_string(), "lots".to_string()];
+        assert!(ServerConfig::load(&args).is_err());
+        assert!(ServerConfig::load(&["--ping-interval".to_string()]).is_err());
+        assert!(ServerConfig::load(&["--nope".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+// Keeps track of whether the other end of a websocket is still there
+// We ping every interval and end the connection if nothing at all comes back within the timeout
+
+use std::{sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use futures_util::{Stream, StreamExt};
+use tokio::sync::mpsc;
+use tungstenite::Message;
+
+use crate::config::ServerConfig;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Heartbeat {
+    pub fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            interval: Duration::from_secs(config.ping_interval_secs),
+            timeout: Duration::from_secs(config.idle_timeout_secs),
+        }
+    }
+
+    /// Wrap the read half of a websocket so it ends (like a normal disconnect) once the other end goes quiet
+    /// Pings get sent down `pinger`, which should go to the write half
+    pub fn watch<S: Stream + Unpin>(&self, read: S, pinger: mpsc::UnboundedSender<Message>) -> impl Stream<Item = S::Item> + Unpin {
+        let last_seen = Arc::new(Mutex::new(Instant::now()));
+        let seen = Arc::clone(&last_seen);
+        let heartbeat = *self;
+
+        let timed_out = Box::pin(async move {
+            let mut ticker = tokio::time::interval(heartbeat.interval);
+            loop {
+                ticker.tick().await;
+                let quiet_for = last_seen.lock().map(|t| t.elapsed()).unwrap_or_default();
+                if quiet_for >= heartbeat.timeout {
+                    println!("Connection timed out after {:?} without a reply", quiet_for);
+                    break;
+                }
+                if pinger.send(Message::Ping(Vec::new().into())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Anything coming in (pongs included) counts as the connection being alive
+        read.inspect(move |_| {
+            if let Ok(mut t) = seen.lock() {
+                *t = Instant::now();
+            }
+        }).take_until(timed_out)
+    }
+}
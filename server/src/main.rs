@@ -1,7 +1,10 @@
 // Lanch servers
 
+pub mod bot;
 pub mod common;
 pub mod central;
+pub mod config;
+pub mod heartbeat;
 pub mod local;
 pub mod matchmaking;
 pub mod messages;
@@ -11,16 +14,33 @@ async fn main() {
     //Get the server type from the args passed in (default local server)
     let server_type = std::env::args().nth(1).unwrap_or_else(|| "local".to_string());
 
-    match server_type.as_str() {
-        "local" => println!("Starting local server"),
+    // Everything after the server type is config flags
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    let config = match config::ServerConfig::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let result = match server_type.as_str() {
+        "local" => {
+            println!("Starting local server");
+            Ok(())
+        },
         "central" => {
             println!("Starting central server");
-            central::run().await
+            central::run(config).await
         },
         "matchmaking" => {
             println!("Starting matchmaking server");
-            matchmaking::run().await
+            matchmaking::run(config).await
         },
-        _ => println!("Invalid server type. Use: local, central, matchmaking")
+        _ => Err("Invalid server type. Use: local, central, matchmaking".to_string())
+    };
+
+    if let Err(e) = result {
+        println!("{}", e);
     }
 }
\ No newline at end of file
@@ -2,134 +2,372 @@
 
 use std::collections::HashMap;
 
-use common::server::{messages::{ClientToMatchmakingServer, MatchmakingServerToClient}, room_info::RoomInfo};
-use futures_util::{StreamExt, SinkExt};
-use tokio::{net::{TcpListener, TcpStream}, sync::{mpsc::{self, Receiver, Sender}, oneshot}};
+use common::server::{messages::{ClientToMatchmakingServer, JoinError, MatchmakingServerToClient}, room_info::RoomInfo};
+use futures_util::{Stream, StreamExt, SinkExt};
+use tokio::{net::{TcpListener, TcpStream}, sync::{mpsc, oneshot}};
 use tokio_tungstenite::accept_async;
 use tungstenite::Message;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use crate::messages::{GSToMM, MMToGS};
+use crate::{common::to_message, config::ServerConfig, heartbeat::Heartbeat, messages::{GSToMM, MMToGS, SyncedRoom}};
 
 
 pub struct MatchmakingServer {
     rooms: HashMap<Uuid, RoomInfo>,
     room_paths: HashMap<Uuid, String>, // Room uuid, path to server with room
+    room_codes: HashMap<String, Uuid>, // Room code, room uuid
+    room_servers: HashMap<Uuid, Uuid>, // Room uuid, game server running it
 
-    // Tracks room creation requests in progress: request_id → responder
-    room_creation_requests: HashMap<Uuid, oneshot::Sender<(RoomInfo, String)>>,
+    // Clients looking at the room directory, they get sent every change to it
+    browsers: HashMap<Uuid, mpsc::UnboundedSender<Message>>,
+
+    // Every game server that has registered: server_id → how to reach it and how busy it is
+    game_servers: HashMap<Uuid, GameServerNode>,
+
+    // Tracks room creation requests in progress: request_id → (game server asked, responder)
+    room_creation_requests: HashMap<Uuid, (Uuid, oneshot::Sender<(RoomInfo, String)>)>,
+}
+
+pub struct GameServerNode {
+    sender: mpsc::UnboundedSender<MMToGS>,
+    max_rooms: usize,
+    room_count: usize,
+    player_count: usize,
+    // A new one for each connection, so cleaning up after a dead connection can't remove the one that replaced it
+    connection_id: Uuid,
 }
 
 pub type SharedServer = Arc<RwLock<MatchmakingServer>>;
 
-pub async fn run() {
-    let addr = "127.0.0.1:9001";
-    let listner = TcpListener::bind(&addr).await.expect("Can't bind");
+pub async fn run(config: ServerConfig) -> Result<(), String> {
+    let addr = config.matchmaking_bind.clone();
+    let listner = TcpListener::bind(&addr).await
+        .map_err(|e| format!("Can't bind client listener to {}: {}", addr, e))?;
+    let game_server_listener = TcpListener::bind(&config.game_server_bind).await
+        .map_err(|e| format!("Can't bind game server listener to {}: {}", config.game_server_bind, e))?;
 
     let server = Arc::new(RwLock::new(MatchmakingServer {
         rooms: HashMap::new(),
         room_paths: HashMap::new(),
+        room_codes: HashMap::new(),
+        room_servers: HashMap::new(),
+        browsers: HashMap::new(),
+        game_servers: HashMap::new(),
         room_creation_requests: HashMap::new()
     }));
 
     println!("Matchmaking server running on ws://{}", addr);
+    println!("Waiting for game servers on ws://{}", config.game_server_bind);
+
+    let heartbeat = Heartbeat::from_config(&config);
 
-    let (mm_to_gs_tx, mm_to_gs_rx) = mpsc::channel(100); // MM -> GS
-    // let (gs_to_mm_tx, gs_to_mm_rx) = mpsc::channel(100); // GS -> MM
+    // Game servers can come and go, each one gets its own connection handler
     let server_clone = Arc::clone(&server);
-    tokio::spawn(handle_game_server_connection(server_clone, "ws://127.0.0.1:9002", mm_to_gs_rx));
+    tokio::spawn(async move {
+        while let Ok((stream, peer)) = game_server_listener.accept().await {
+            let server = Arc::clone(&server_clone);
+            tokio::spawn(async move {
+                if let Err(e) = handle_game_server_connection(stream, server, heartbeat).await {
+                    println!("Game server connection from {}: {}", peer, e);
+                }
+            });
+        }
+    });
 
-    while let Ok((stream, _)) = listner.accept().await {
+    // One misbehaving client only ever ends its own connection
+    while let Ok((stream, peer)) = listner.accept().await {
         let server_clone = Arc::clone(&server);
-        let tx = mm_to_gs_tx.clone();
-        tokio::spawn(handle_client_connection(stream, server_clone, tx));
+        tokio::spawn(async move {
+            if let Err(e) = handle_client_connection(stream, server_clone, heartbeat).await {
+                println!("Client connection from {}: {}", peer, e);
+            }
+        });
     }
+    Ok(())
 }
 
 
-async fn handle_game_server_connection(server: SharedServer, gs_addr: &str, mut mm_to_gs_rx: Receiver<MMToGS>,) {
-    let (ws_stream, _) = tokio_tungstenite::connect_async(gs_addr).await.expect("Failed to connect to game server");
+async fn handle_game_server_connection(stream: TcpStream, server: SharedServer, heartbeat: Heartbeat) -> Result<(), String> {
+    let ws_stream = accept_async(stream).await.map_err(|e| format!("WebSocket accept error: {}", e))?;
 
     let (mut write, mut read) = ws_stream.split();
 
-    // Send messages to the game server
-    tokio::spawn(async move {
-        while let Some(msg) = mm_to_gs_rx.recv().await {
-            let json = serde_json::to_string(&msg).unwrap();
-            write.send(Message::Text(json.into())).await.unwrap();
+    // The first thing a game server does is say who it is
+    let (server_id, max_rooms) = match read.next().await {
+        Some(Ok(Message::Text(text))) => match serde_json::from_str::<GSToMM>(&text) {
+            Ok(GSToMM::Register { server_id, max_rooms }) => (server_id, max_rooms),
+            _ => {
+                return Err("Game server didn't register, dropping it".to_string());
+            }
+        },
+        _ => return Err("Connection closed before registering".to_string()),
+    };
+
+    let (mm_to_gs_tx, mut mm_to_gs_rx) = mpsc::unbounded_channel::<MMToGS>();
+    let (ping_tx, mut ping_rx) = mpsc::unbounded_channel::<Message>();
+    let connection_id = server.write().await.register_game_server(server_id, max_rooms, mm_to_gs_tx);
+    println!("Game server {} registered with room for {} rooms", server_id, max_rooms);
+
+    // Send messages (and pings) to the game server
+    let writer = tokio::spawn(async move {
+        loop {
+            let msg = tokio::select! {
+                Some(msg) = mm_to_gs_rx.recv() => match to_message(&msg) {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                },
+                Some(ping) = ping_rx.recv() => ping,
+                else => break,
+            };
+            if write.send(msg).await.is_err() {
+                break;
+            }
         }
     });
 
+    // A game server that stops answering pings gets dropped like one that disconnected
+    let mut read = heartbeat.watch(read, ping_tx);
+
     // Handle the messages from the game server
+    let mut result = Ok(());
     while let Some(msg) = read.next().await {
-        if let Ok(Message::Text(text)) = msg {
-            if let Ok(parsed) = serde_json::from_str::<GSToMM>(&text) {
-                match parsed {
-                    GSToMM::CreateRoomSuccess{request_id, room_info, port } => {
-                        // add the room to the mm server
-                        let room_id = room_info.id.clone();
-                        let address = format!("ws://127.0.0.1:{}", port);
+        let text = match msg {
+            Ok(Message::Text(text)) => text,
+            Ok(_) => continue,
+            Err(e) => {
+                result = Err(format!("Connection error: {}", e));
+                break;
+            }
+        };
+        match serde_json::from_str::<GSToMM>(&text) {
+            Ok(parsed) => match parsed {
+                GSToMM::LoadReport { room_count, player_count } => {
+                    let mut server_guard = server.write().await;
+                    if let Some(node) = server_guard.game_servers.get_mut(&server_id) {
+                        node.room_count = room_count;
+                        node.player_count = player_count;
+                    }
+                },
+                GSToMM::CreateRoomSuccess{request_id, room_info, server_address } => {
+                    // add the room to the mm server
+                    let mut server_guard = server.write().await;
+                    server_guard.room_paths.insert(room_info.id, server_address.clone());
+                    server_guard.room_servers.insert(room_info.id, server_id);
+                    server_guard.update_room(room_info.clone());
+
+                    if let Some((_, sender)) = server_guard.room_creation_requests.remove(&request_id) {
+                        let _ = sender.send((room_info, server_address));
+                    }
+                },
+                GSToMM::CreateRoomFailed{request_id, reason} => {
+                    // Dropping the responder lets the client know it failed
+                    println!("Game server {} couldn't create a room: {}", server_id, reason);
+                    let mut server_guard = server.write().await;
+                    server_guard.room_creation_requests.remove(&request_id);
+                    if let Some(node) = server_guard.game_servers.get_mut(&server_id) {
+                        node.room_count = node.room_count.saturating_sub(1);
+                    }
+                },
+                GSToMM::RoomUpdated { room_info } => {
+                    // Only rooms this game server told us it made, anything else is stale
+                    let mut server_guard = server.write().await;
+                    if server_guard.room_servers.get(&room_info.id) == Some(&server_id) {
+                        server_guard.update_room(room_info);
+                    }
+                },
+                GSToMM::RoomSync { rooms } => {
+                    server.write().await.sync_rooms(server_id, rooms);
+                },
+                GSToMM::RoomDeleted { room_id } => {
+                    // Room no longer exists, remove it from the room directory
+                    let mut server_guard = server.write().await;
+                    server_guard.remove_room(&room_id);
+                }
+                _ => println!("Unimplemented")
+            },
+            Err(e) => println!("Game server {} sent an invalid message: {}", server_id, e),
+        }
+    }
 
-                        let mut server_guard = server.write().await;
-                        server_guard.rooms.insert(room_id, room_info.clone());
-                        server_guard.room_paths.insert(room_id, address.clone());
+    // The game server is gone, and so are all of its rooms
+    writer.abort();
+    match server.write().await.remove_game_server(&server_id, &connection_id) {
+        Some(dropped) => println!("Game server {} disconnected, dropped {} rooms", server_id, dropped),
+        None => println!("Old connection to game server {} closed, it has already reconnected", server_id),
+    }
+    result
+}
 
-                        if let Some(sender) = server_guard.room_creation_requests.remove(&request_id) {
-                            let _ = sender.send((room_info, address));
-                        }
-                    },
-                    GSToMM::ClientJoinedRoom { room_id } => {
-                        // Someone has joined, increment the player counter
-                        let mut server_guard = server.write().await;
-                        if let Some(room) = server_guard.rooms.get_mut(&room_id) {
-                            room.player_count += 1;
-                        }
-                    },
-                    GSToMM::ClientDisconnectRoom { room_id } => {
-                        // Someone has left, decrement the player counter
-                        let mut server_guard = server.write().await;
-                        if let Some(room) = server_guard.rooms.get_mut(&room_id) {
-                            room.player_count -= 1;
-                        }
-                    },
-                    GSToMM::RoomDeleted { room_id } => {
-                        // Room no longer exists, remove it from the room directory
-                        let mut server_guard = server.write().await;
-                        server_guard.rooms.remove(&room_id);
-                        server_guard.room_paths.remove(&room_id);
+
+impl MatchmakingServer {
+    /// A game server has connected (or reconnected), gives back the id of this connection
+    fn register_game_server(&mut self, server_id: Uuid, max_rooms: usize, sender: mpsc::UnboundedSender<MMToGS>) -> Uuid {
+        let connection_id = Uuid::new_v4();
+        // A reconnect replaces the old connection, even if that one hasn't noticed it's dead yet
+        self.game_servers.insert(server_id, GameServerNode { sender, max_rooms, room_count: 0, player_count: 0, connection_id });
+        connection_id
+    }
+
+    /// Forget a game server and its rooms, unless it has already come back on a newer connection
+    /// Gives back how many rooms were dropped, or None if the connection had been replaced
+    fn remove_game_server(&mut self, server_id: &Uuid, connection_id: &Uuid) -> Option<usize> {
+        if self.game_servers.get(server_id).is_none_or(|node| node.connection_id != *connection_id) {
+            return None;
+        }
+        self.game_servers.remove(server_id);
+        let rooms: Vec<Uuid> = self.room_servers.iter()
+            .filter(|(_, id)| *id == server_id)
+            .map(|(room_id, _)| *room_id)
+            .collect();
+        for room_id in &rooms {
+            self.remove_room(room_id);
+        }
+        self.room_creation_requests.retain(|_, (id, _)| id != server_id);
+        Some(rooms.len())
+    }
+
+    /// Make what we know about a game server's rooms match what it actually has
+    /// Rooms we don't know about get added back, e.g. ones it kept running while it was reconnecting
+    fn sync_rooms(&mut self, server_id: Uuid, rooms: Vec<SyncedRoom>) {
+        let gone: Vec<Uuid> = self.room_servers.iter()
+            .filter(|(room_id, id)| **id == server_id && !rooms.iter().any(|room| room.room_info.id == **room_id))
+            .map(|(room_id, _)| *room_id)
+            .collect();
+        for room_id in &gone {
+            self.remove_room(room_id);
+        }
+
+        for room in rooms {
+            let room_id = room.room_info.id;
+            match self.room_servers.get(&room_id) {
+                Some(id) if *id == server_id => {},
+                // Another server has a room with the same id, leave it be
+                Some(_) => continue,
+                None => {
+                    self.room_servers.insert(room_id, server_id);
+                    self.room_paths.insert(room_id, room.server_address);
+                    // The code could have gone to a new room while this one was missing
+                    match self.room_codes.get(&room.room_code) {
+                        Some(other) if *other != room_id => println!("Room {} lost its code {} to another room", room_id, room.room_code),
+                        _ => { self.room_codes.insert(room.room_code, room_id); },
                     }
-                    _ => println!("Unimplemented")
-                }
+                },
             }
+            self.update_room(room.room_info);
         }
     }
+
+    /// The room with this code, however the player typed it in
+    fn find_room_code(&self, room_code: &str) -> Option<&Uuid> {
+        self.room_codes.get(&room_code.trim().to_uppercase())
+    }
+
+    fn remove_room(&mut self, room_id: &Uuid) {
+        let removed = self.rooms.remove(room_id);
+        self.room_paths.remove(room_id);
+        self.room_servers.remove(room_id);
+        self.room_codes.retain(|_, id| id != room_id);
+
+        if removed.is_some_and(|room| is_listed(&room)) {
+            self.notify_browsers(&MatchmakingServerToClient::RoomRemoved { room_id: *room_id });
+        }
+    }
+
+    /// Add or replace a room, then tell the browsing clients how their list changed
+    fn update_room(&mut self, room_info: RoomInfo) {
+        let old = self.rooms.get(&room_info.id);
+        let update = match (old.is_some_and(is_listed), is_listed(&room_info)) {
+            (false, true) => Some(MatchmakingServerToClient::RoomAdded(room_info.clone())),
+            (true, true) if old != Some(&room_info) => Some(MatchmakingServerToClient::RoomUpdated(room_info.clone())),
+            (true, false) => Some(MatchmakingServerToClient::RoomRemoved { room_id: room_info.id }),
+            _ => None,
+        };
+
+        self.rooms.insert(room_info.id, room_info);
+        if let Some(update) = update {
+            self.notify_browsers(&update);
+        }
+    }
+
+    /// Send a directory change to everyone browsing, dropping anyone whose connection has gone
+    fn notify_browsers(&mut self, msg: &MatchmakingServerToClient) {
+        match to_message(msg) {
+            Ok(msg) => self.browsers.retain(|_, tx| tx.send(msg.clone()).is_ok()),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    /// The game server with the most free space, if any have space
+    fn pick_game_server(&self) -> Option<Uuid> {
+        self.game_servers.iter()
+            .filter(|(_, node)| node.room_count < node.max_rooms)
+            .min_by(|(_, a), (_, b)| {
+                let load_a = a.room_count as f32 / a.max_rooms as f32;
+                let load_b = b.room_count as f32 / b.max_rooms as f32;
+                load_a.total_cmp(&load_b).then(a.player_count.cmp(&b.player_count))
+            })
+            .map(|(id, _)| *id)
+    }
 }
 
 
 
 
-async fn handle_client_connection(stream: TcpStream, server: SharedServer, mm_to_gs_tx: Sender<MMToGS>) {
-    let ws_stream = accept_async(stream).await.expect("WebSocket accept error");
+async fn handle_client_connection(stream: TcpStream, server: SharedServer, heartbeat: Heartbeat) -> Result<(), String> {
+    let ws_stream = accept_async(stream).await.map_err(|e| format!("WebSocket accept error: {}", e))?;
     println!("New client connected");
 
-    let (mut write, mut read) = ws_stream.split();
+    let (mut write, read) = ws_stream.split();
+
+    // Everything going to the client goes through here so pings can be sent from the heartbeat
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            let is_close = matches!(msg, Message::Close(_));
+            if write.send(msg).await.is_err() || is_close {
+                break;
+            }
+        }
+    });
+
+    let mut read = heartbeat.watch(read, tx.clone());
 
+    let client_id = Uuid::new_v4();
+    let result = handle_client_messages(&mut read, &tx, &server, client_id).await;
+
+    // Stop sending them room updates
+    server.write().await.browsers.remove(&client_id);
+    println!("Client disconnected");
+    result
+}
+
+// Answer a clients requests until they leave
+async fn handle_client_messages<S>(read: &mut S, tx: &mpsc::UnboundedSender<Message>, server: &SharedServer, client_id: Uuid) -> Result<(), String>
+where
+    S: Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+{
     while let Some(msg) = read.next().await {
         match msg {
             Ok(Message::Text(text)) => {
                 match serde_json::from_str::<ClientToMatchmakingServer>(&text) {
                     Ok(ClientToMatchmakingServer::GetRooms) => {
-                        // Temporary info
                         println!("Sending room directory");
-                        // READ lock since we're not modifying
-                        let server_guard = server.read().await;
+                        // Write lock so no change can slip in between the directory and subscribing to changes
+                        let mut server_guard = server.write().await;
                         let response = MatchmakingServerToClient::RoomDirectory(
-                            server_guard.rooms.clone() // Make sure RoomInfo is Clone
+                            server_guard.rooms.iter()
+                                .filter(|(_, room)| is_listed(room))
+                                .map(|(id, room)| (*id, room.clone()))
+                                .collect()
                         );
-                        let json = serde_json::to_string(&response).unwrap();
-                        write.send(Message::Text(json.into())).await.unwrap();
+                        send_to_client(tx, &response)?;
+                        // From now on they get told about every change to the directory
+                        server_guard.browsers.insert(client_id, tx.clone());
                     },
                     Ok(ClientToMatchmakingServer::CreateRoom{room_name, is_private, max_players}) => {
                         let request_id = Uuid::new_v4();
@@ -138,68 +376,275 @@ async fn handle_client_connection(stream: TcpStream, server: SharedServer, mm_to
 
                         let (res_tx, res_rx) = oneshot::channel::<(RoomInfo, String)>();
 
-                        // Save pending request
-                        {
+                        // Pick a game server, save pending request and reserve a code for the room
+                        let created = {
                             let mut guard = server.write().await;
-                            guard.room_creation_requests.insert(request_id, res_tx);
-                        }
-
-
-                        // Ask the game server to make a room
-                        mm_to_gs_tx.send(MMToGS::CreateRoom { request_id, room_name: room_name, is_private: is_private, max_players: max_players }).await.unwrap();
-                        
+                            let room_code = generate_room_code(&guard.room_codes);
+                            let picked = guard.pick_game_server();
+                            match picked.and_then(|server_id| guard.game_servers.get_mut(&server_id).map(|node| (server_id, node))) {
+                                Some((server_id, node)) => {
+                                    // Count the room straight away so the next request goes somewhere else
+                                    node.room_count += 1;
+                                    // Ask the game server to make a room
+                                    let _ = node.sender.send(MMToGS::CreateRoom { request_id, room_name: room_name, room_code: room_code.clone(), is_private: is_private, max_players: max_players });
+
+                                    guard.room_creation_requests.insert(request_id, (server_id, res_tx));
+                                    // Nil until the game server tells us the room id
+                                    guard.room_codes.insert(room_code.clone(), Uuid::nil());
+                                    Some(room_code)
+                                },
+                                None => None,
+                            }
+                        };
+
+                        let Some(room_code) = created else {
+                            let error = MatchmakingServerToClient::Error("No game servers available".into());
+                            send_to_client(tx, &error)?;
+                            continue;
+                        };
 
                         // Wait for response from game server
-                        if let Ok((room_info, address)) = res_rx.await {
+                        if let Ok((room_info, _address)) = res_rx.await {
+                            server.write().await.room_codes.insert(room_code, room_info.id);
                             let confirmation = MatchmakingServerToClient::RoomCreated {room_id: room_info.id};
-                            let json = serde_json::to_string(&confirmation).unwrap();
-                            write.send(Message::Text(json.into())).await.unwrap();
+                            send_to_client(tx, &confirmation)?;
                         } else {
+                            server.write().await.room_codes.remove(&room_code);
                             let error = MatchmakingServerToClient::Error("Room creation failed".into());
-                            let json = serde_json::to_string(&error).unwrap();
-                            write.send(Message::Text(json.into())).await.unwrap();
+                            send_to_client(tx, &error)?;
                         }
 
                     },
                     Ok(ClientToMatchmakingServer::GetRoomInfo{ room_id}) => {
                         //Room joining logic - send back the route to the room 
                         let server_guard = server.read().await;
-                        if let Some(room_path) = server_guard.room_paths.get(&room_id) {
-                            let info = MatchmakingServerToClient::RoomInfo {server_address: room_path.to_string()};
-                            let json = serde_json::to_string(&info).unwrap();
-                            write.send(Message::Text(json.into())).await.unwrap();
-                        } else {
-                            let error = MatchmakingServerToClient::Error("Room not found".to_string());
-                            let json = serde_json::to_string(&error).unwrap();
-                            write.send(Message::Text(json.into())).await.unwrap();
-                        }
-                        
+                        let response = join_response(&server_guard, &room_id);
+                        send_to_client(tx, &response)?;
+                    },
+                    Ok(ClientToMatchmakingServer::JoinByCode{ room_code }) => {
+                        // Same as above but the room can be private, so look it up by its code
+                        let server_guard = server.read().await;
+                        let response = match server_guard.find_room_code(&room_code) {
+                            Some(room_id) => join_response(&server_guard, room_id),
+                            None => MatchmakingServerToClient::JoinError(JoinError::RoomNotFound),
+                        };
+                        send_to_client(tx, &response)?;
                     },
                     Ok(ClientToMatchmakingServer:: Disconnect) => {
                         println!("Client requested disconnect");
 
                         // Close the connection
-                        write.close().await.unwrap_or_else(|e| {
-                            eprintln!("Failed to close WebSocket: {}", e);
-                        });
+                        let _ = tx.send(Message::Close(None));
 
                         break;
                     }
                     _ => {
                         let error = MatchmakingServerToClient::Error("Unknown or unsupported message".to_string());
-                        let json = serde_json::to_string(&error).unwrap();
-                        write.send(Message::Text(json.into())).await.unwrap()
+                        send_to_client(tx, &error)?;
                     }
                     
                 }
             }
-            _ => {
-                println!("Invalid WebSocket message of the connection has been closed");
-                break;
-            }
+            Ok(Message::Ping(_) | Message::Pong(_)) => {},
+            Ok(Message::Close(_)) => break,
+            Ok(_) => return Err("Unexpected non text message".to_string()),
+            Err(e) => return Err(format!("Connection error: {}", e)),
         }
     }
 
-    println!("Client disconnected");
-    
+    Ok(())
+}
+
+// Queue a reply for the client, fails once their connection has gone
+fn send_to_client(tx: &mpsc::UnboundedSender<Message>, msg: &MatchmakingServerToClient) -> Result<(), String> {
+    tx.send(to_message(msg)?).map_err(|_| "Client connection closed".to_string())
+}
+
+
+
+// Only public rooms that can still be joined show up in the directory
+fn is_listed(room: &RoomInfo) -> bool {
+    !room.has_started && !room.is_private
+}
+
+// Where to send a client that wants to join a room, or why they can't
+fn join_response(server: &MatchmakingServer, room_id: &Uuid) -> MatchmakingServerToClient {
+    match (server.rooms.get(room_id), server.room_paths.get(room_id)) {
+        (Some(room), _) if room.has_started => MatchmakingServerToClient::JoinError(JoinError::GameStarted),
+        (Some(room), _) if room.player_count >= room.max_players => MatchmakingServerToClient::JoinError(JoinError::RoomFull),
+        (Some(_), Some(room_path)) => MatchmakingServerToClient::RoomInfo {server_address: room_path.to_string()},
+        _ => MatchmakingServerToClient::JoinError(JoinError::RoomNotFound),
+    }
+}
+
+// Letters and numbers that look alike (O/0, I/1) are left out so codes are easy to read out
+const CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 5;
+
+// Make a short code for a room that no other room is using
+fn generate_room_code(existing: &HashMap<String, Uuid>) -> String {
+    unused_room_code(existing, random_room_code)
+}
+
+// Keep making codes until one comes up that isn't taken
+fn unused_room_code(existing: &HashMap<String, Uuid>, mut make_code: impl FnMut() -> String) -> String {
+    loop {
+        let code = make_code();
+        if !existing.contains_key(&code) {
+            return code;
+        }
+    }
+}
+
+fn random_room_code() -> String {
+    Uuid::new_v4().as_bytes()[..CODE_LENGTH].iter()
+        .map(|byte| CODE_CHARS[*byte as usize % CODE_CHARS.len()] as char)
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_server() -> MatchmakingServer {
+        MatchmakingServer {
+            rooms: HashMap::new(),
+            room_paths: HashMap::new(),
+            room_codes: HashMap::new(),
+            room_servers: HashMap::new(),
+            browsers: HashMap::new(),
+            game_servers: HashMap::new(),
+            room_creation_requests: HashMap::new(),
+        }
+    }
+
+    fn synced_room(name: &str, room_code: &str) -> SyncedRoom {
+        let id = Uuid::new_v4();
+        SyncedRoom {
+            room_info: RoomInfo { id, name: name.to_string(), max_players: 4, player_count: 1, spectator_count: 0, is_private: false, has_started: false },
+            server_address: format!("ws://game/room/{}", id),
+            room_code: room_code.to_string(),
+        }
+    }
+
+    fn join_address(server: &MatchmakingServer, room_id: &Uuid) -> Option<String> {
+        match join_response(server, room_id) {
+            MatchmakingServerToClient::RoomInfo { server_address } => Some(server_address),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn rooms_can_be_joined_again_after_their_server_reconnects() {
+        let mut server = test_server();
+        let server_id = Uuid::new_v4();
+        let room = synced_room("Still going", "ABCDE");
+        let room_id = room.room_info.id;
+
+        let first = server.register_game_server(server_id, 4, mpsc::unbounded_channel().0);
+        server.sync_rooms(server_id, vec![room.clone()]);
+        assert_eq!(join_address(&server, &room_id), Some(room.server_address.clone()));
+
+        // The connection drops and everything it had goes with it
+        assert_eq!(server.remove_game_server(&server_id, &first), Some(1));
+        assert_eq!(join_address(&server, &room_id), None);
+        assert_eq!(server.find_room_code("ABCDE"), None);
+
+        // It comes back with the room still running
+        server.register_game_server(server_id, 4, mpsc::unbounded_channel().0);
+        server.sync_rooms(server_id, vec![room.clone()]);
+        assert_eq!(join_address(&server, &room_id), Some(room.server_address));
+        assert_eq!(server.find_room_code("abcde"), Some(&room_id));
+    }
+
+    #[test]
+    fn an_old_connection_closing_late_leaves_the_new_one_alone() {
+        let mut server = test_server();
+        let server_id = Uuid::new_v4();
+        let room = synced_room("Still going", "ABCDE");
+        let room_id = room.room_info.id;
+
+        let old = server.register_game_server(server_id, 4, mpsc::unbounded_channel().0);
+        server.sync_rooms(server_id, vec![room.clone()]);
+        let new = server.register_game_server(server_id, 4, mpsc::unbounded_channel().0);
+
+        assert_eq!(server.remove_game_server(&server_id, &old), None);
+        assert!(server.game_servers.contains_key(&server_id));
+        assert!(join_address(&server, &room_id).is_some());
+
+        assert_eq!(server.remove_game_server(&server_id, &new), Some(1));
+        assert!(server.game_servers.is_empty());
+    }
+
+    #[test]
+    fn sync_drops_rooms_the_server_no_longer_has() {
+        let mut server = test_server();
+        let server_id = Uuid::new_v4();
+        let (kept, closed) = (synced_room("Kept", "AAAAA"), synced_room("Closed", "BBBBB"));
+        server.register_game_server(server_id, 4, mpsc::unbounded_channel().0);
+        server.sync_rooms(server_id, vec![kept.clone(), closed.clone()]);
+
+        server.sync_rooms(server_id, vec![kept.clone()]);
+        assert!(join_address(&server, &kept.room_info.id).is_some());
+        assert!(join_address(&server, &closed.room_info.id).is_none());
+        assert_eq!(server.find_room_code("BBBBB"), None);
+    }
+
+    #[test]
+    fn a_synced_room_doesnt_take_a_code_that_was_given_away() {
+        let mut server = test_server();
+        let server_id = Uuid::new_v4();
+        let newer_room = Uuid::new_v4();
+        server.room_codes.insert("ABCDE".to_string(), newer_room);
+
+        let room = synced_room("Old", "ABCDE");
+        server.sync_rooms(server_id, vec![room.clone()]);
+        assert_eq!(server.find_room_code("ABCDE"), Some(&newer_room));
+        assert!(join_address(&server, &room.room_info.id).is_some());
+    }
+
+    #[test]
+    fn room_codes_only_use_easy_to_read_characters() {
+        for _ in 0..1000 {
+            let code = random_room_code();
+            assert_eq!(code.len(), CODE_LENGTH);
+            assert!(code.bytes().all(|c| CODE_CHARS.contains(&c)), "{} has a character it shouldn't", code);
+        }
+    }
+
+    #[test]
+    fn taken_codes_are_skipped() {
+        let existing: HashMap<String, Uuid> = [("AAAAA".to_string(), Uuid::new_v4()), ("BBBBB".to_string(), Uuid::new_v4())].into();
+        let mut codes = ["AAAAA", "BBBBB", "AAAAA", "CCCCC"].into_iter();
+        let mut made = 0;
+        let code = unused_room_code(&existing, || {
+            made += 1;
+            codes.next().unwrap().to_string()
+        });
+        assert_eq!(code, "CCCCC");
+        assert_eq!(made, 4);
+    }
+
+    #[test]
+    fn generated_codes_never_repeat_a_room_in_use() {
+        let mut existing = HashMap::new();
+        for _ in 0..2000 {
+            let code = generate_room_code(&existing);
+            assert!(existing.insert(code, Uuid::new_v4()).is_none());
+        }
+    }
+
+    #[test]
+    fn codes_are_found_however_they_are_typed_and_freed_with_the_room() {
+        let mut server = test_server();
+        let room_id = Uuid::new_v4();
+        server.room_codes.insert("ABCDE".to_string(), room_id);
+
+        assert_eq!(server.find_room_code(" abcde "), Some(&room_id));
+        assert_eq!(server.find_room_code("ABCDF"), None);
+
+        server.remove_room(&room_id);
+        assert_eq!(server.find_room_code("ABCDE"), None);
+    }
 }
@@ -5,15 +5,29 @@ use uuid::Uuid;
 // Link between matchmaking and gameserver
 #[derive(Serialize, Deserialize)]
 pub enum MMToGS {
-    CreateRoom{request_id: Uuid, room_name: String, is_private: bool, max_players: usize},
+    CreateRoom{request_id: Uuid, room_name: String, room_code: String, is_private: bool, max_players: usize},
 }
 
 // Link between gameserver and matchmaking
 #[derive(Serialize, Deserialize)]
 pub enum GSToMM {
-    CreateRoomSuccess{request_id: Uuid, room_info: RoomInfo, port: u16},
-    ClientJoinedRoom{room_id: Uuid},
-    ClientDisconnectRoom{room_id: Uuid},
+    Register{server_id: Uuid, max_rooms: usize},
+    LoadReport{room_count: usize, player_count: usize},
+    CreateRoomSuccess{request_id: Uuid, room_info: RoomInfo, server_address: String},
+    CreateRoomFailed{request_id: Uuid, reason: String},
+    // The whole state of a room, sent whenever anything about it changes
+    RoomUpdated{room_info: RoomInfo},
+    // Every room the game server is running, sent every so often in case anything got out of sync
+    // (and straight after reconnecting, so the rooms it kept running can be joined again)
+    RoomSync{rooms: Vec<SyncedRoom>},
     RoomDeleted{room_id: Uuid},
     Error(String),
+}
+
+// Everything matchmaking needs to route players to a room, even one it has forgotten about
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SyncedRoom {
+    pub room_info: RoomInfo,
+    pub server_address: String,
+    pub room_code: String,
 }
\ No newline at end of file
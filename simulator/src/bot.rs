@@ -0,0 +1,70 @@
+// Scripted bots that play through the GameManger like a player would, using the same calls the server makes
+
+use common::{game::{card::Card, choice::ChoiceRequest}, GameManger};
+use uuid::Uuid;
+
+pub struct ScriptedBot {
+    pub player_id: Uuid,
+}
+
+impl ScriptedBot {
+    pub fn new(player_id: Uuid) -> Self {
+        Self { player_id }
+    }
+
+    /// Play a whole turn - characters first, everything else onto the first character in play - then end it
+    /// Returns the card ids that were played
+    pub fn take_turn(&self, game: &mut GameManger) -> Result<Vec<String>, String> {
+        let mut hand: Vec<(Uuid, String, bool)> = vec![];
+        if let Some(player) = game.get_player_manager().get_player_by_id(&self.player_id) {
+            for instance_id in player.get_hand() {
+                if let Some(card) = game.get_card_manager().get_card_from_instance_id(instance_id) {
+                    hand.push((*instance_id, card.get_card_id().clone(), matches!(card, Card::Character(_))));
+                }
+            }
+        }
+        // Characters go out first so the rest have something to go onto
+        hand.sort_by_key(|(_, _, is_character)| !is_character);
+
+        let mut played = vec![];
+        for (instance_id, card_id, is_character) in hand {
+            let target = if is_character { None } else { self.first_character(game) };
+            // The bot doesn't know which cards can go where, anything turned down just stays in its hand
+            if let Ok(request) = game.play_card(self.player_id, instance_id, target) {
+                played.push(card_id);
+                if let Some(request) = request {
+                    self.answer_choice(game, &request)?;
+                }
+            }
+        }
+
+        game.end_turn(self.player_id)?;
+        Ok(played)
+    }
+
+    /// Instance and card ids of everything currently in hand
+    pub fn hand(&self, game: &GameManger) -> Vec<(Uuid, String)> {
+        let mut cards = vec![];
+        if let Some(player) = game.get_player_manager().get_player_by_id(&self.player_id) {
+            for instance_id in player.get_hand() {
+                if let Some(card) = game.get_card_manager().get_card_from_instance_id(instance_id) {
+                    cards.push((*instance_id, card.get_card_id().clone()));
+                }
+            }
+        }
+        cards
+    }
+
+    fn first_character(&self, game: &GameManger) -> Option<Uuid> {
+        let player = game.get_player_manager().get_player_by_id(&self.player_id)?;
+        player.get_table().iter()
+            .find(|instance_id| matches!(game.get_card_manager().get_card_from_instance_id(instance_id), Some(Card::Character(_))))
+            .copied()
+    }
+
+    // Always go with the first options offered
+    fn answer_choice(&self, game: &mut GameManger, request: &ChoiceRequest) -> Result<(), String> {
+        let selected = request.options.iter().take(request.min.max(1).min(request.max)).map(|option| option.id()).collect();
+        game.answer_choice(self.player_id, request.choice_id, selected)
+    }
+}
@@ -0,0 +1,204 @@
+// Runs lots of games between scripted bots to see how balanced a deck is
+// Usage: simulator <deck_path> [--games N] [--players N] [--seed N] [--format csv|json] [--out path]
+
+use std::{collections::HashSet, fs};
+
+use common::GameManger;
+use uuid::Uuid;
+
+use crate::{bot::ScriptedBot, stats::{GameResult, Report, Stats}};
+
+pub mod bot;
+pub mod stats;
+
+// Every turn draws a card so games always end, this just stops a rules bug from hanging the whole run
+const MAX_TURNS: usize = 10_000;
+
+struct Options {
+    deck_path: String,
+    games: usize,
+    players: usize,
+    seed: u64,
+    format: String,
+    out: Option<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        deck_path: String::new(),
+        games: 1000,
+        players: 4,
+        seed: 0,
+        format: "csv".to_string(),
+        out: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("Missing value for {}", name));
+        match arg.as_str() {
+            "--games" => options.games = value("--games")?.parse().map_err(|_| "--games must be a number")?,
+            "--players" => options.players = value("--players")?.parse().map_err(|_| "--players must be a number")?,
+            "--seed" => options.seed = value("--seed")?.parse().map_err(|_| "--seed must be a number")?,
+            "--format" => options.format = value("--format")?,
+            "--out" => options.out = Some(value("--out")?),
+            _ if options.deck_path.is_empty() => options.deck_path = arg,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if options.deck_path.is_empty() {
+        return Err("No deck path given".to_string());
+    }
+    if options.players < 2 {
+        return Err("Need at least 2 players".to_string());
+    }
+    if options.format != "csv" && options.format != "json" {
+        return Err("--format must be csv or json".to_string());
+    }
+    Ok(options)
+}
+
+fn run_game(options: &Options, seed: u64) -> Result<(GameResult, GameManger), String> {
+    let mut game = GameManger::with_seed(seed);
+    // Thousands of games get run, and the seed is all that's needed to play one again
+    game.set_logging(false);
+    game.load_deck(&options.deck_path)?;
+
+    let bots: Vec<ScriptedBot> = (0..options.players).map(|i| {
+        let player_id = Uuid::from_u128(i as u128 + 1);
+        game.add_player(player_id, format!("Bot {}", i + 1));
+        ScriptedBot::new(player_id)
+    }).collect();
+    game.start_game(bots.iter().map(|bot| bot.player_id).collect())?;
+
+    let mut result = GameResult {
+        rounds: 0,
+        battle_scores: vec![],
+        winner: None,
+        held_cards: vec![HashSet::new(); bots.len()],
+        drawn_cards: vec![],
+        played_cards: vec![],
+    };
+    let mut seen = HashSet::new();
+
+    let mut turns = 0;
+    while !game.is_game_over() {
+        if turns == MAX_TURNS {
+            return Err(format!("Game {} didn't end after {} turns", seed, MAX_TURNS));
+        }
+        turns += 1;
+
+        let current = game.get_game_state().get_current_player().cloned();
+        let Some(index) = bots.iter().position(|bot| Some(bot.player_id) == current) else {
+            return Err(format!("Game {} has nobody to take the turn", seed));
+        };
+        // Anything in their hand that hasn't been seen before was drawn since their last turn
+        for (instance_id, card_id) in bots[index].hand(&game) {
+            if seen.insert(instance_id) {
+                result.drawn_cards.push(card_id.clone());
+            }
+            result.held_cards[index].insert(card_id);
+        }
+        result.played_cards.extend(bots[index].take_turn(&mut game)?);
+    }
+    result.rounds = game.get_game_state().get_round() as usize;
+
+    // Whoever would hit hardest once the cards run out wins, nobody does on a tie
+    result.battle_scores = bots.iter().map(|bot| game.battle_score(&bot.player_id)).collect();
+    let best = *result.battle_scores.iter().max().unwrap_or(&0);
+    let leaders: Vec<usize> = (0..bots.len()).filter(|i| result.battle_scores[*i] == best).collect();
+    if leaders.len() == 1 {
+        result.winner = Some(leaders[0]);
+    }
+
+    Ok((result, game))
+}
+
+/// Play every game and put the results together, the same options always give the same report
+fn simulate(options: &Options) -> Result<Report, String> {
+    let mut stats = Stats::new(options.players);
+    let mut last_game = None;
+    for game_number in 0..options.games {
+        let (result, game) = run_game(options, options.seed.wrapping_add(game_number as u64))?;
+        stats.add_game(result);
+        last_game = Some(game);
+    }
+
+    // Any of the games can be used to look the card names up as they all use the same deck
+    Ok(stats.report(|card_id| {
+        last_game.as_ref()
+            .and_then(|game| game.get_card_manager().get_card(card_id))
+            .map(|card| card.get_name().clone())
+            .unwrap_or_default()
+    }))
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("Usage: simulator <deck_path> [--games N] [--players N] [--seed N] [--format csv|json] [--out path]");
+            std::process::exit(1);
+        }
+    };
+
+    let report = match simulate(&options) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let output = match options.format.as_str() {
+        "json" => serde_json::to_string_pretty(&report).expect("Failed to serialise report"),
+        _ => report.to_csv(),
+    };
+
+    match &options.out {
+        Some(path) => fs::write(path, output).expect("Failed to write output"),
+        None => print!("{}", output),
+    }
+
+    eprintln!("Games: {}", report.summary.games);
+    eprintln!("Games without a winner: {}", report.summary.games_without_winner);
+    eprintln!("Average game length: {:.2} rounds", report.summary.average_game_length);
+    eprintln!("Average final battle score: {:.2}", report.summary.average_battle_score);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_options(seed: u64) -> Options {
+        Options {
+            deck_path: concat!(env!("CARGO_MANIFEST_DIR"), "/../res/decks/base_deck").to_string(),
+            games: 5,
+            players: 3,
+            seed,
+            format: "json".to_string(),
+            out: None,
+        }
+    }
+
+    #[test]
+    fn games_are_played_to_the_end() {
+        let (result, game) = run_game(&test_options(1), 1).unwrap();
+        assert!(game.is_game_over());
+        assert!(game.get_deck_manager().get_item_draw_pile().is_empty());
+        assert_eq!(result.battle_scores.len(), 3);
+        assert!(!result.played_cards.is_empty());
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_report() {
+        let first = serde_json::to_string(&simulate(&test_options(7)).unwrap()).unwrap();
+        let again = serde_json::to_string(&simulate(&test_options(7)).unwrap()).unwrap();
+        assert_eq!(first, again);
+
+        let other_seed = serde_json::to_string(&simulate(&test_options(8)).unwrap()).unwrap();
+        assert_ne!(first, other_seed);
+    }
+}
@@ -0,0 +1,164 @@
+// Collects the results of every simulated game and turns them into a report
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+// What happened in a single game
+pub struct GameResult {
+    pub rounds: usize,
+    // Per player: their battle score when the game ended, which decides the winner
+    pub battle_scores: Vec<i32>,
+    pub winner: Option<usize>,
+    // Per player: every card id they held at some point in the game
+    pub held_cards: Vec<HashSet<String>>,
+    pub drawn_cards: Vec<String>,
+    pub played_cards: Vec<String>,
+}
+
+#[derive(Default)]
+struct CardTally {
+    times_drawn: usize,
+    times_played: usize,
+    games_held: usize,
+    wins_when_held: usize,
+}
+
+pub struct Stats {
+    players: usize,
+    games: usize,
+    games_without_winner: usize,
+    total_rounds: usize,
+    total_battle_score: i64,
+    battles: usize,
+    cards: BTreeMap<String, CardTally>,
+}
+
+#[derive(Serialize)]
+pub struct Summary {
+    pub games: usize,
+    pub players: usize,
+    pub games_without_winner: usize,
+    pub average_game_length: f64,
+    pub average_battle_score: f64,
+}
+
+#[derive(Serialize)]
+pub struct CardReport {
+    pub card_id: String,
+    pub name: String,
+    pub times_drawn: usize,
+    pub times_played: usize,
+    pub games_held: usize,
+    pub wins_when_held: usize,
+    pub win_rate: f64,
+    /// How far above (or below) an even share of wins the holder of this card got
+    pub win_rate_contribution: f64,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub summary: Summary,
+    pub cards: Vec<CardReport>,
+}
+
+impl Stats {
+    pub fn new(players: usize) -> Self {
+        Self {
+            players,
+            games: 0,
+            games_without_winner: 0,
+            total_rounds: 0,
+            total_battle_score: 0,
+            battles: 0,
+            cards: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_game(&mut self, result: GameResult) {
+        self.games += 1;
+        self.total_rounds += result.rounds;
+        self.battles += result.battle_scores.len();
+        self.total_battle_score += result.battle_scores.iter().map(|s| *s as i64).sum::<i64>();
+
+        if result.winner.is_none() {
+            self.games_without_winner += 1;
+        }
+
+        for card_id in result.drawn_cards {
+            self.cards.entry(card_id).or_default().times_drawn += 1;
+        }
+        for card_id in result.played_cards {
+            self.cards.entry(card_id).or_default().times_played += 1;
+        }
+        for (player_index, held) in result.held_cards.into_iter().enumerate() {
+            for card_id in held {
+                let tally = self.cards.entry(card_id).or_default();
+                tally.games_held += 1;
+                if result.winner == Some(player_index) {
+                    tally.wins_when_held += 1;
+                }
+            }
+        }
+    }
+
+    /// Build the final report, card_name is used to look up the display name of a card id
+    pub fn report<F: Fn(&str) -> String>(&self, card_name: F) -> Report {
+        let even_share = 1.0 / self.players.max(1) as f64;
+
+        let cards = self.cards.iter().map(|(card_id, tally)| {
+            let win_rate = if tally.games_held == 0 { 0.0 } else { tally.wins_when_held as f64 / tally.games_held as f64 };
+            CardReport {
+                card_id: card_id.clone(),
+                name: card_name(card_id),
+                times_drawn: tally.times_drawn,
+                times_played: tally.times_played,
+                games_held: tally.games_held,
+                wins_when_held: tally.wins_when_held,
+                win_rate,
+                win_rate_contribution: win_rate - even_share,
+            }
+        }).collect();
+
+        Report {
+            summary: Summary {
+                games: self.games,
+                players: self.players,
+                games_without_winner: self.games_without_winner,
+                average_game_length: if self.games == 0 { 0.0 } else { self.total_rounds as f64 / self.games as f64 },
+                average_battle_score: if self.battles == 0 { 0.0 } else { self.total_battle_score as f64 / self.battles as f64 },
+            },
+            cards,
+        }
+    }
+}
+
+impl Report {
+    /// The summary on its own lines first, then a line per card
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("games,players,games_without_winner,average_game_length,average_battle_score\n");
+        csv.push_str(&format!(
+            "{},{},{},{:.4},{:.4}\n\n",
+            self.summary.games,
+            self.summary.players,
+            self.summary.games_without_winner,
+            self.summary.average_game_length,
+            self.summary.average_battle_score,
+        ));
+        csv.push_str("card_id,name,times_drawn,times_played,games_held,wins_when_held,win_rate,win_rate_contribution\n");
+        for card in &self.cards {
+            csv.push_str(&format!(
+                "{},\"{}\",{},{},{},{},{:.4},{:.4}\n",
+                card.card_id,
+                card.name.replace('"', "\"\""),
+                card.times_drawn,
+                card.times_played,
+                card.games_held,
+                card.wins_when_held,
+                card.win_rate,
+                card.win_rate_contribution,
+            ));
+        }
+        csv
+    }
+}